@@ -0,0 +1,117 @@
+use brick_breaker::gameplay_entities::{Ball, BrickGrid, BrickPath, BrickState, BricksWall};
+use brick_breaker::levels::{LevelLayout, PathRule, PathTarget};
+use brick_breaker::types::Point2;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// Compares the linear scan of all the bricks with the query of the bricks
+// index, for the default wall and for a big generated-size wall.
+fn collisions(c: &mut Criterion) {
+    let screen_sizes = [("default", (800.0, 600.0)), ("big", (8000.0, 6000.0))];
+    let mut group = c.benchmark_group("ball_brick_collisions");
+
+    for &(name, (screen_width, screen_height)) in screen_sizes.iter() {
+        let grid = BrickGrid::new(screen_width, screen_height, 20.0, 40.0);
+        let wall = BricksWall::from_layout(&grid, &LevelLayout::full(grid.columns, grid.rows));
+        let balls = balls_over(screen_width, screen_height);
+        let seconds = 1.0 / 60.0;
+
+        group.bench_with_input(BenchmarkId::new("linear", name), &wall, |b, wall| {
+            b.iter(|| {
+                let mut collisions = 0;
+                for ball in &balls {
+                    for index in 0..wall.bricks.len() {
+                        collisions += colliding(wall, ball, index) as usize;
+                    }
+                }
+                black_box(collisions)
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("indexed", name), &wall, |b, wall| {
+            b.iter(|| {
+                let mut collisions = 0;
+                for ball in &balls {
+                    for index in wall.bricks_near(ball.swept_bounds(seconds)) {
+                        collisions += colliding(wall, ball, index) as usize;
+                    }
+                }
+                black_box(collisions)
+            })
+        });
+    }
+    group.finish();
+}
+
+// A step of a wall whose rows all move: the bricks are moved in the index
+// and the balls query it, compared with building the index again.
+fn moving_bricks(c: &mut Criterion) {
+    let (screen_width, screen_height) = (800.0, 600.0);
+    let grid = BrickGrid::new(screen_width, screen_height, 20.0, 40.0);
+    let mut layout = LevelLayout::full(grid.columns, grid.rows);
+    for row in 0..grid.rows {
+        layout.paths.push(PathRule {
+            target: PathTarget::Row(row),
+            path: BrickPath::Oscillate {
+                amplitude: 40.0,
+                period: 2.0,
+            },
+        });
+    }
+    let wall = BricksWall::from_layout(&grid, &layout);
+    let balls = balls_over(screen_width, screen_height);
+    let seconds = 1.0 / 60.0;
+    let mut group = c.benchmark_group("moving_bricks_step");
+
+    group.bench_function("rebuilt", |b| {
+        let mut wall = wall.clone();
+        b.iter(|| {
+            wall.update(seconds);
+            wall.rebuild_index();
+            let mut near = 0;
+            for ball in &balls {
+                near += wall.bricks_near(ball.swept_bounds(seconds)).len();
+            }
+            black_box(near)
+        })
+    });
+
+    group.bench_function("moved", |b| {
+        let mut wall = wall.clone();
+        let mut bricks = Vec::new();
+        b.iter(|| {
+            wall.update(seconds);
+            let mut near = 0;
+            for ball in &balls {
+                wall.bricks_near_into(ball.swept_bounds(seconds), &mut bricks);
+                near += bricks.len();
+            }
+            black_box(near)
+        })
+    });
+    group.finish();
+}
+
+fn colliding(wall: &BricksWall, ball: &Ball, index: usize) -> bool {
+    !matches!(wall.bricks[index].state, BrickState::Broken)
+        && !matches!(
+            ball.collides_rect(wall.brick_rect(index)),
+            brick_breaker::math::Collision::None
+        )
+}
+
+// Balls spread evenly over the upper part of the screen, where the bricks are.
+fn balls_over(screen_width: f32, screen_height: f32) -> Vec<Ball> {
+    (0..100)
+        .map(|i| {
+            let mut ball = Ball::new(screen_width, screen_height, 16.0, 16.0, 16.0);
+            ball.pos = Point2 {
+                x: screen_width * ((i % 10) as f32 + 0.5) / 10.0,
+                y: screen_height * 0.6 * ((i / 10) as f32 + 0.5) / 10.0,
+            };
+            ball
+        })
+        .collect()
+}
+
+criterion_group!(benches, collisions, moving_bricks);
+criterion_main!(benches);
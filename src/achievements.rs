@@ -0,0 +1,242 @@
+use crate::progress::Progress;
+use crate::statistics::Statistics;
+use crate::types::{GameError, GameResult};
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::ErrorKind;
+
+// The definitions of the achievements, see the file for its format. The copy
+// which is built in is used when the game runs outside of the project.
+pub const DEFINITIONS_FILE_NAME: &str = "resources/achievements.txt";
+const BUILT_IN_DEFINITIONS: &str = include_str!("../resources/achievements.txt");
+// The unlocked achievements of a profile, one id per line.
+pub const ACHIEVEMENTS_FILE_NAME: &str = "achievements.txt";
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Condition {
+    GamesPlayed,
+    BricksBroken,
+    LongestRally,
+    BestCombo,
+    PlayMinutes,
+    ReachedLevel,
+    ClearsInAGame,
+    Stars,
+}
+
+impl Condition {
+    pub const ALL: [Condition; 8] = [
+        Condition::GamesPlayed,
+        Condition::BricksBroken,
+        Condition::LongestRally,
+        Condition::BestCombo,
+        Condition::PlayMinutes,
+        Condition::ReachedLevel,
+        Condition::ClearsInAGame,
+        Condition::Stars,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Condition::GamesPlayed => "games_played",
+            Condition::BricksBroken => "bricks_broken",
+            Condition::LongestRally => "longest_rally",
+            Condition::BestCombo => "best_combo",
+            Condition::PlayMinutes => "play_minutes",
+            Condition::ReachedLevel => "reached_level",
+            Condition::ClearsInAGame => "clears_in_a_game",
+            Condition::Stars => "stars",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Condition> {
+        Condition::ALL
+            .iter()
+            .find(|condition| condition.name() == name)
+            .copied()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Achievement {
+    pub id: String,
+    pub condition: Condition,
+    pub target: usize,
+    pub title: String,
+}
+
+// The values which the conditions are checked against, taken from the
+// statistics and the progress of the player.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Facts {
+    pub games_played: usize,
+    pub bricks_broken: usize,
+    pub longest_rally: usize,
+    pub best_combo: usize,
+    pub play_seconds: f64,
+    pub reached_level: i32,
+    pub clears_in_a_game: usize,
+    pub stars: u8,
+}
+
+impl Facts {
+    pub fn new(statistics: &Statistics, progress: &Progress) -> Self {
+        Facts {
+            games_played: statistics.games_played,
+            bricks_broken: statistics.bricks_broken.total(),
+            longest_rally: statistics.longest_rally,
+            best_combo: statistics.best_combo,
+            play_seconds: statistics.play_seconds,
+            reached_level: progress.level,
+            clears_in_a_game: statistics.clears_in_game(),
+            stars: progress
+                .levels
+                .values()
+                .map(|record| record.stars)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    pub fn value(&self, condition: Condition) -> usize {
+        match condition {
+            Condition::GamesPlayed => self.games_played,
+            Condition::BricksBroken => self.bricks_broken,
+            Condition::LongestRally => self.longest_rally,
+            Condition::BestCombo => self.best_combo,
+            Condition::PlayMinutes => (self.play_seconds / 60.0) as usize,
+            Condition::ReachedLevel => self.reached_level.max(0) as usize,
+            Condition::ClearsInAGame => self.clears_in_a_game,
+            Condition::Stars => self.stars as usize,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Achievements {
+    pub definitions: Vec<Achievement>,
+    // The ids of the unlocked achievements. The ones which are not defined
+    // anymore are kept, so they come back with their definition.
+    pub unlocked: BTreeSet<String>,
+}
+
+impl Achievements {
+    // Reads the definitions and the achievements unlocked by the profile.
+    pub fn load(definitions_file_name: &str, file_name: &str) -> GameResult<Achievements> {
+        let definitions = match fs::read_to_string(definitions_file_name) {
+            Ok(contents) => Self::parse_definitions(&contents)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Self::parse_definitions(BUILT_IN_DEFINITIONS)?
+            }
+            Err(_) => {
+                return Err(GameError::ResourceLoadError(format!(
+                    "The file {} could not be read.",
+                    definitions_file_name
+                )))
+            }
+        };
+        let unlocked = match fs::read_to_string(file_name) {
+            Ok(contents) => Self::parse_unlocked(&contents)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => BTreeSet::new(),
+            Err(_) => {
+                return Err(GameError::ResourceLoadError(format!(
+                    "The file {} could not be read.",
+                    file_name
+                )))
+            }
+        };
+        Ok(Achievements {
+            definitions,
+            unlocked,
+        })
+    }
+
+    pub fn parse_definitions(contents: &str) -> GameResult<Vec<Achievement>> {
+        let mut definitions: Vec<Achievement> = Vec::new();
+        for line in contents.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            let invalid = || {
+                GameError::ResourceLoadError(format!("Invalid line in the achievements: {}", line))
+            };
+            let words = line.split_whitespace().collect::<Vec<&str>>();
+            if words.len() < 4 || !is_valid_id(words[0]) {
+                return Err(invalid());
+            }
+            if definitions
+                .iter()
+                .any(|achievement| achievement.id == words[0])
+            {
+                return Err(GameError::ResourceLoadError(format!(
+                    "The achievement {} is defined twice.",
+                    words[0]
+                )));
+            }
+            definitions.push(Achievement {
+                id: String::from(words[0]),
+                condition: Condition::from_name(words[1]).ok_or_else(invalid)?,
+                target: words[2].parse().map_err(|_| invalid())?,
+                title: words[3..].join(" "),
+            });
+        }
+        Ok(definitions)
+    }
+
+    pub fn parse_unlocked(contents: &str) -> GameResult<BTreeSet<String>> {
+        contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|id| {
+                if is_valid_id(id) {
+                    Ok(String::from(id))
+                } else {
+                    Err(GameError::ResourceLoadError(format!(
+                        "Invalid achievement id: {}",
+                        id
+                    )))
+                }
+            })
+            .collect()
+    }
+
+    pub fn to_file_contents(&self) -> String {
+        self.unlocked.iter().map(|id| format!("{}\n", id)).collect()
+    }
+
+    pub fn save(&self, file_name: &str) -> GameResult<()> {
+        let mut file = File::create(file_name)?;
+        file.write_all(self.to_file_contents().as_bytes())?;
+        Ok(())
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.contains(id)
+    }
+
+    // Unlocks every achievement which reached its target, and returns the ones
+    // which were unlocked now in the order of their definitions.
+    pub fn check(&mut self, facts: &Facts) -> Vec<Achievement> {
+        let newly_unlocked = self
+            .definitions
+            .iter()
+            .filter(|achievement| !self.unlocked.contains(&achievement.id))
+            .filter(|achievement| facts.value(achievement.condition) >= achievement.target)
+            .cloned()
+            .collect::<Vec<Achievement>>();
+        for achievement in &newly_unlocked {
+            self.unlocked.insert(achievement.id.clone());
+        }
+        newly_unlocked
+    }
+}
+
+// The ids are written into the profile, one per line.
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
@@ -1,6 +1,11 @@
+use crate::renderer;
+use crate::theme::{self, Palette, Theme};
+use ggez::audio::{self, SoundSource};
+use ggez::filesystem;
 use ggez::graphics;
 use ggez::mint::Point2;
-use ggez::{Context, GameResult};
+use ggez::{Context, GameError, GameResult};
+use std::collections::HashMap;
 use std::fmt::Debug;
 
 #[derive(Debug)]
@@ -10,15 +15,30 @@ pub struct Assets {
     pub skateboard_normal: graphics::Image,
     pub skateboard_rebound: graphics::Image,
     pub ball_flying: graphics::Image,
+    pub background: Option<graphics::Image>,
+    pub bounce_sound: Option<audio::Source>,
+    pub font: graphics::Font,
+    pub theme_name: String,
+    pub theme_palette: Palette,
+    pub palette: Palette,
 }
 
 impl Assets {
-    pub fn new(ctx: &mut Context) -> GameResult<Assets> {
-        let brick_survived = graphics::Image::new(ctx, "/brick_survived.png")?;
-        let brick_touched = graphics::Image::new(ctx, "/brick_touched.png")?;
-        let skateboard_normal = graphics::Image::new(ctx, "/skateboard_normal_1.png")?;
-        let skateboard_rebound = graphics::Image::new(ctx, "/skateboard_rebound.png")?;
-        let ball_flying = graphics::Image::new(ctx, "/ball_flying.png")?;
+    pub fn new(ctx: &mut Context, theme: &Theme) -> GameResult<Assets> {
+        let brick_survived = load_image(ctx, theme, theme::BRICK_SURVIVED)?;
+        let brick_touched = load_image(ctx, theme, theme::BRICK_TOUCHED)?;
+        let skateboard_normal = load_image(ctx, theme, theme::SKATEBOARD_NORMAL)?;
+        let skateboard_rebound = load_image(ctx, theme, theme::SKATEBOARD_REBOUND)?;
+        let ball_flying = load_image(ctx, theme, theme::BALL_FLYING)?;
+        let background = match theme.asset_path(ctx, theme::BACKGROUND) {
+            Some(path) => Some(graphics::Image::new(ctx, path)?),
+            None => None,
+        };
+        let bounce_sound = match theme.asset_path(ctx, theme::BOUNCE_SOUND) {
+            Some(path) if filesystem::exists(ctx, &path) => Some(audio::Source::new(ctx, path)?),
+            _ => None,
+        };
+        let font = graphics::Font::new(ctx, asset_path(ctx, theme, theme::FONT)?)?;
 
         Ok(Assets {
             brick_survived,
@@ -26,8 +46,46 @@ impl Assets {
             skateboard_normal,
             skateboard_rebound,
             ball_flying,
+            background,
+            bounce_sound,
+            font,
+            theme_name: theme.name.clone(),
+            theme_palette: theme.palette,
+            palette: theme.palette,
         })
     }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        if let Some(bounce_sound) = &mut self.bounce_sound {
+            bounce_sound.set_volume(volume);
+        }
+    }
+
+    pub fn set_color_blind_mode(&mut self, enabled: bool) {
+        self.palette = if enabled {
+            self.theme_palette.color_blind()
+        } else {
+            self.theme_palette
+        };
+    }
+
+    pub fn play_bounce_sound(&mut self, ctx: &mut Context) -> GameResult<()> {
+        match &mut self.bounce_sound {
+            Some(bounce_sound) => bounce_sound.play_detached(ctx),
+            None => Ok(()),
+        }
+    }
+}
+
+fn asset_path(ctx: &Context, theme: &Theme, asset: &str) -> GameResult<String> {
+    theme.asset_path(ctx, asset).ok_or_else(|| {
+        GameError::ResourceLoadError(format!("The asset {} has no built-in file.", asset))
+    })
+}
+
+fn load_image(ctx: &mut Context, theme: &Theme, asset: &str) -> GameResult<graphics::Image> {
+    let path = asset_path(ctx, theme, asset)?;
+    graphics::Image::new(ctx, path)
 }
 
 pub trait Sprite: Debug {
@@ -39,14 +97,16 @@ pub trait Sprite: Debug {
 #[derive(Debug)]
 pub struct TextSprite {
     text: graphics::Text,
+    color: graphics::Color,
 }
 
 impl TextSprite {
-    pub fn new(label: &str, ctx: &mut Context) -> GameResult<TextSprite> {
-        let font = graphics::Font::new(ctx, "/DejaVuSerif.ttf")?;
+    pub const FONT_SIZE: f32 = renderer::FONT_SIZE;
+
+    pub fn new(label: &str, font: graphics::Font, color: graphics::Color) -> TextSprite {
         let mut text = graphics::Text::new(label);
-        text.set_font(font, graphics::PxScale::from(26.0));
-        Ok(TextSprite { text })
+        text.set_font(font, graphics::PxScale::from(Self::FONT_SIZE));
+        TextSprite { text, color }
     }
 }
 
@@ -57,6 +117,7 @@ impl Sprite for TextSprite {
             &self.text,
             graphics::DrawParam {
                 dest: top_left,
+                color: self.color,
                 ..Default::default()
             },
         )
@@ -69,3 +130,35 @@ impl Sprite for TextSprite {
         self.text.height(ctx)
     }
 }
+
+// Keeps the text sprites of the HUD and the dialogs between frames.
+// A sprite is rebuilt only when the label stored under its key changes.
+#[derive(Debug)]
+pub struct TextCache {
+    font: graphics::Font,
+    color: graphics::Color,
+    sprites: HashMap<String, (String, TextSprite)>,
+}
+
+impl TextCache {
+    pub fn new(font: graphics::Font, color: graphics::Color) -> Self {
+        TextCache {
+            font,
+            color,
+            sprites: HashMap::new(),
+        }
+    }
+
+    pub fn sprite(&mut self, key: &str, label: &str) -> &mut TextSprite {
+        let (font, color) = (self.font, self.color);
+        let cached =
+            matches!(self.sprites.get(key), Some((cached_label, _)) if cached_label == label);
+        if !cached {
+            self.sprites.insert(
+                key.to_string(),
+                (label.to_string(), TextSprite::new(label, font, color)),
+            );
+        }
+        &mut self.sprites.get_mut(key).unwrap().1
+    }
+}
@@ -0,0 +1,180 @@
+use crate::game_settings_entities::{InputState, Score};
+use crate::gameplay_entities::{Ball, GameplayState, Skateboard, SpriteSizes};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AutopilotSkill {
+    Novice,
+    Skilled,
+    Perfect,
+}
+
+impl AutopilotSkill {
+    pub const ALL: [AutopilotSkill; 3] = [
+        AutopilotSkill::Novice,
+        AutopilotSkill::Skilled,
+        AutopilotSkill::Perfect,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AutopilotSkill::Novice => "novice",
+            AutopilotSkill::Skilled => "skilled",
+            AutopilotSkill::Perfect => "perfect",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|skill| skill.name() == name)
+    }
+
+    // The largest distance of the aim from the landing point of the ball, in
+    // widths of the skateboard.
+    pub fn aim_error(self) -> f32 {
+        match self {
+            AutopilotSkill::Novice => 0.6,
+            AutopilotSkill::Skilled => 0.3,
+            AutopilotSkill::Perfect => 0.0,
+        }
+    }
+
+    // The time between two looks at the ball.
+    pub fn reaction_seconds(self) -> f32 {
+        match self {
+            AutopilotSkill::Novice => 0.3,
+            AutopilotSkill::Skilled => 0.1,
+            AutopilotSkill::Perfect => 0.0,
+        }
+    }
+
+    // The part of the full speed of the skateboard which is used.
+    pub fn max_movement(self) -> f32 {
+        match self {
+            AutopilotSkill::Novice => 0.6,
+            AutopilotSkill::Skilled => 0.9,
+            AutopilotSkill::Perfect => 1.0,
+        }
+    }
+}
+
+// A bot which plays the game. It predicts where the ball will come down and
+// moves the skateboard there, the skill decides how often it looks at the
+// ball, how far it misses and how fast it moves.
+#[derive(Debug, Clone)]
+pub struct Autopilot {
+    pub skill: AutopilotSkill,
+    rng: ChaCha8Rng,
+    target_x: Option<f32>,
+    until_next_look: f32,
+    // The miss of the current aim, a new one is drawn when the ball starts
+    // falling.
+    aim_offset: f32,
+    ball_was_falling: bool,
+}
+
+impl Autopilot {
+    pub fn new(skill: AutopilotSkill, seed: u64) -> Self {
+        Autopilot {
+            skill,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            target_x: None,
+            until_next_look: 0.0,
+            aim_offset: 0.0,
+            ball_was_falling: false,
+        }
+    }
+
+    pub fn input(
+        &mut self,
+        state: &GameplayState,
+        sizes: &SpriteSizes,
+        screen_width: f32,
+        seconds: f32,
+    ) -> InputState {
+        let board = state.skateboard.rect(sizes);
+
+        let ball_is_falling = state.ball.direction.y > 0.0;
+        if ball_is_falling && !self.ball_was_falling {
+            let error = self.skill.aim_error();
+            self.aim_offset = if error > 0.0 {
+                self.rng.gen_range(-error..error) * board.w
+            } else {
+                0.0
+            };
+        }
+        self.ball_was_falling = ball_is_falling;
+
+        self.until_next_look -= seconds;
+        if self.until_next_look <= 0.0 || self.target_x.is_none() {
+            self.until_next_look = self.skill.reaction_seconds();
+            // A rising ball usually comes back from a brick long before the
+            // ceiling, so the skateboard waits in the middle until it falls.
+            let landing_x = if ball_is_falling {
+                predict_landing_x(&state.ball, board.y, screen_width)
+            } else {
+                screen_width / 2.0
+            };
+            self.target_x = Some(landing_x + self.aim_offset);
+        }
+
+        let movement = match self.target_x {
+            Some(target_x) if seconds > 0.0 => {
+                let distance = target_x - (board.x + board.w / 2.0);
+                let max_movement = self.skill.max_movement();
+                nalgebra::clamp(
+                    distance / (Skateboard::SPEED * seconds),
+                    -max_movement,
+                    max_movement,
+                )
+            }
+            _ => 0.0,
+        };
+        InputState {
+            movement,
+            pointer_x: None,
+        }
+    }
+}
+
+// Where the center of the ball will come down to the top of the skateboard,
+// with the bounces off the side walls and the ceiling. The bricks are not
+// taken into account, the prediction is made again on every look.
+pub fn predict_landing_x(ball: &Ball, board_top: f32, screen_width: f32) -> f32 {
+    let radius = ball.radius;
+    let center_x = ball.pos.x + radius;
+    let center_y = ball.pos.y + radius;
+    let landing_y = board_top - radius;
+    if ball.direction.y == 0.0 {
+        return center_x;
+    }
+
+    let vertical_distance = if ball.direction.y > 0.0 {
+        (landing_y - center_y).max(0.0)
+    } else {
+        // Up to the ceiling and back down.
+        (center_y - radius) + (landing_y - radius)
+    };
+    let horizontal_distance = vertical_distance * ball.direction.x / ball.direction.y.abs();
+
+    // The walls fold the straight path back into the screen.
+    let width = screen_width - 2.0 * radius;
+    if width <= 0.0 {
+        return center_x;
+    }
+    let folded = (center_x - radius + horizontal_distance).rem_euclid(2.0 * width);
+    radius
+        + if folded > width {
+            2.0 * width - folded
+        } else {
+            folded
+        }
+}
+
+// The attract mode of the main menu, the autopilot plays while the score of
+// the player is kept aside.
+#[derive(Debug, Clone)]
+pub struct Demo {
+    pub autopilot: Autopilot,
+    pub saved_score: Score,
+}
@@ -0,0 +1,174 @@
+use brick_breaker::autopilot::AutopilotSkill;
+use brick_breaker::settings::Difficulty;
+use brick_breaker::simulation::{
+    run_game, stats_to_csv, stats_to_json, GameStats, LevelSource, Policy, SimulationConfig,
+};
+use brick_breaker::types::GameResult;
+
+const USAGE: &str = "Plays games without a window and prints their statistics.
+
+Usage: brick_breaker-sim [options]
+
+    --games N            the number of seeds for every level and difficulty (10)
+    --seed N             the first seed (0)
+    --levels A-B         the levels to play, one number for a single level (1)
+    --generated          plays the generated levels of the seeds
+    --level-file FILE    plays the layout of the file on every level
+    --difficulty NAME    easy, normal, hard or all (normal)
+    --policy NAME        novice, skilled or perfect ball tracking (skilled)
+    --script FILE        moves the skateboard by the \"seconds movement\" lines of the file
+    --max-seconds N      stops a game which isn't cleared by then (300)
+    --ball-speed N       the speed of the ball before the difficulty multiplier
+    --hit-points N       the hits which break a normal brick (2)
+    --format NAME        csv or json (csv)";
+
+struct Options {
+    games: u64,
+    seed: u64,
+    levels: (i32, i32),
+    source: LevelSource,
+    difficulties: Vec<Difficulty>,
+    policy: Policy,
+    json: bool,
+    config: SimulationConfig,
+}
+
+fn main() -> GameResult {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if args.iter().any(|arg| arg == "--help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let mut stats = Vec::new();
+    for level in options.levels.0..=options.levels.1 {
+        for &difficulty in &options.difficulties {
+            for seed in options.seed..options.seed + options.games {
+                let layout = options.config.level_layout(&options.source, level, seed)?;
+                stats.push(run_game(
+                    &options.config,
+                    &layout,
+                    difficulty,
+                    &options.policy,
+                    seed,
+                    level,
+                )?);
+            }
+        }
+    }
+
+    if options.json {
+        print!("{}", stats_to_json(&stats));
+    } else {
+        print!("{}", stats_to_csv(&stats));
+    }
+    print_summary(&stats);
+    Ok(())
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        games: 10,
+        seed: 0,
+        levels: (1, 1),
+        source: LevelSource::Files,
+        difficulties: vec![Difficulty::Normal],
+        policy: Policy::Tracking(AutopilotSkill::Skilled),
+        json: false,
+        config: SimulationConfig::default(),
+    };
+
+    let mut index = 0;
+    while index < args.len() {
+        let name = args[index].as_str();
+        if name == "--generated" {
+            options.source = LevelSource::Generated;
+            index += 1;
+            continue;
+        }
+        let value = args
+            .get(index + 1)
+            .ok_or_else(|| format!("The {} argument needs a value.", name))?;
+        let invalid = || format!("Invalid value of {}: {}", name, value);
+        match name {
+            "--games" => options.games = value.parse().map_err(|_| invalid())?,
+            "--seed" => options.seed = value.parse().map_err(|_| invalid())?,
+            "--levels" => options.levels = parse_levels(value).ok_or_else(invalid)?,
+            "--level-file" => options.source = LevelSource::File(value.clone()),
+            "--difficulty" => {
+                options.difficulties = if value == "all" {
+                    Difficulty::ALL.to_vec()
+                } else {
+                    vec![Difficulty::from_name(value).ok_or_else(invalid)?]
+                }
+            }
+            "--policy" => {
+                options.policy =
+                    Policy::Tracking(AutopilotSkill::from_name(value).ok_or_else(invalid)?)
+            }
+            "--script" => {
+                options.policy = Policy::load_script(value).map_err(|e| format!("{:?}", e))?
+            }
+            "--max-seconds" => options.config.max_seconds = value.parse().map_err(|_| invalid())?,
+            "--ball-speed" => options.config.ball_speed = value.parse().map_err(|_| invalid())?,
+            "--hit-points" => {
+                options.config.brick_hit_points = value
+                    .parse()
+                    .ok()
+                    .filter(|&hit_points| hit_points > 0)
+                    .ok_or_else(invalid)?
+            }
+            "--format" => {
+                options.json = match value.as_str() {
+                    "csv" => false,
+                    "json" => true,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(format!("Unknown argument: {}", name)),
+        }
+        index += 2;
+    }
+    Ok(options)
+}
+
+fn parse_levels(value: &str) -> Option<(i32, i32)> {
+    let mut bounds = value.splitn(2, '-').map(|bound| bound.parse::<i32>());
+    let first = bounds.next()?.ok()?;
+    let last = match bounds.next() {
+        Some(bound) => bound.ok()?,
+        None => first,
+    };
+    if first >= 1 && first <= last {
+        Some((first, last))
+    } else {
+        None
+    }
+}
+
+// The totals go to the standard error, so the output stays a clean table.
+fn print_summary(stats: &[GameStats]) {
+    let cleared = stats
+        .iter()
+        .filter(|game| game.cleared)
+        .collect::<Vec<&GameStats>>();
+    let average_seconds = if cleared.is_empty() {
+        0.0
+    } else {
+        cleared.iter().map(|game| game.seconds).sum::<f32>() / cleared.len() as f32
+    };
+    eprintln!(
+        "{} games, {} cleared in {:.1} seconds on average, {} balls lost",
+        stats.len(),
+        cleared.len(),
+        average_seconds,
+        stats.iter().map(|game| game.balls_lost).sum::<u32>()
+    );
+}
@@ -0,0 +1,540 @@
+use brick_breaker::achievements::{self, Achievements, Facts};
+use brick_breaker::dialogs_handler::{DialogType, DialogsHandler, MainMenuItem, PauseMenuItem};
+use brick_breaker::game_settings_entities::ScoreDetails;
+use brick_breaker::game_workflow_state_reducer::{
+    self, Effect, GameWorkflowIntent, GameWorkflowState, Guards,
+};
+use brick_breaker::gameplay_entities::{BricksWall, GameplayState};
+use brick_breaker::levels::LevelLayout;
+use brick_breaker::progress::Progress;
+use brick_breaker::renderer;
+use brick_breaker::save_dir::SaveDir;
+use brick_breaker::settings::Settings;
+use brick_breaker::simulation::{LevelSource, SimulationConfig};
+use brick_breaker::statistics::Statistics;
+use brick_breaker::terminal_renderer::{Cell, TerminalRenderer};
+use brick_breaker::theme::Palette;
+use brick_breaker::timestep::FixedTimestep;
+use brick_breaker::types::{Color, GameError, GameResult};
+use brick_breaker::widgets::{Navigation, Toasts};
+use crossterm::event::{self, Event, KeyCode};
+use crossterm::style::{self, Print, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, queue, terminal};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// The terminal doesn't report released keys, so a press holds the movement
+// for a while and the key repeat of a held key keeps it going.
+const HOLD_SECONDS: f32 = 0.25;
+const FRAME: Duration = Duration::from_millis(33);
+
+fn main() -> GameResult {
+    let args = std::env::args().collect::<Vec<String>>();
+    let save_dir = SaveDir::from_args(&args, |name| std::env::var(name).ok())?;
+    if args.iter().any(|arg| arg == "--profiles") {
+        for profile in save_dir.profiles() {
+            println!("{}", profile);
+        }
+        return Ok(());
+    }
+    save_dir.create()?;
+    if let Err(e) = save_dir.import_legacy_files(Path::new(".")) {
+        println!(
+            "The saves of the working directory were not imported: {:?} ",
+            e
+        );
+    }
+    let progress = Progress::load(&save_dir.score_file())?;
+    let settings = Settings::load(&save_dir.settings_file()).unwrap_or_else(|e| {
+        println!("Settings error, the default settings are used: {:?} ", e);
+        Settings::default()
+    });
+    let statistics = Statistics::load(&save_dir.stats_file()).unwrap_or_else(|e| {
+        println!("Statistics error, the statistics start again: {:?} ", e);
+        Statistics::default()
+    });
+    let achievements = Achievements::load(
+        achievements::DEFINITIONS_FILE_NAME,
+        &save_dir.achievements_file(),
+    )
+    .unwrap_or_else(|e| {
+        println!("Achievements error, there are no achievements: {:?} ", e);
+        Achievements::default()
+    });
+    let mut game = TerminalGame::new(settings, progress, statistics, achievements, save_dir);
+
+    let mut stdout = io::stdout();
+    let result = {
+        let _screen = GameScreen::enter(&mut stdout)?;
+        run(&mut game, &mut stdout)
+    };
+    // The progress is saved on the way out, after the terminal is restored so
+    // an error is readable.
+    game.save_progress();
+    result
+}
+
+// The raw mode and the alternate screen of the game. The terminal is restored
+// when it is dropped, also by a panic, so the shell of the player keeps
+// working.
+struct GameScreen;
+
+impl GameScreen {
+    fn enter(stdout: &mut io::Stdout) -> GameResult<GameScreen> {
+        terminal::enable_raw_mode().map_err(terminal_error)?;
+        let screen = GameScreen;
+        queue!(stdout, terminal::EnterAlternateScreen, cursor::Hide).map_err(terminal_error)?;
+        Ok(screen)
+    }
+}
+
+impl Drop for GameScreen {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        let _ = queue!(
+            stdout,
+            style::ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = stdout.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn terminal_error(e: crossterm::ErrorKind) -> GameError {
+    GameError::EventLoopError(format!("Terminal error: {:?}", e))
+}
+
+fn run(game: &mut TerminalGame, stdout: &mut io::Stdout) -> GameResult<()> {
+    let mut timestep = FixedTimestep::new(60, 5);
+    let mut last_frame = Instant::now();
+    while !game.quit_requested {
+        while event::poll(Duration::from_secs(0)).map_err(terminal_error)? {
+            if let Event::Key(key) = event::read().map_err(terminal_error)? {
+                game.key_down_event(key.code);
+            }
+        }
+
+        let now = Instant::now();
+        let frame_seconds = now.duration_since(last_frame).as_secs_f32();
+        last_frame = now;
+        for _ in 0..timestep.advance(frame_seconds) {
+            game.update(timestep.step_seconds)?;
+        }
+
+        let (columns, rows) = terminal::size().map_err(terminal_error)?;
+        let columns = (columns as usize).min(TerminalRenderer::COLUMNS);
+        let rows = (rows as usize).min(TerminalRenderer::ROWS);
+        // Nothing is drawn while the terminal has no room, during a resize.
+        if columns > 0 && rows > 0 {
+            let frame = game.draw(columns, rows, timestep.alpha())?;
+            print_frame(stdout, &frame)?;
+        }
+
+        if let Some(rest) = FRAME.checked_sub(now.elapsed()) {
+            std::thread::sleep(rest);
+        }
+    }
+    Ok(())
+}
+
+// The same game as the window, on the headless sizes of the sprites.
+struct TerminalGame {
+    config: SimulationConfig,
+    settings: Settings,
+    palette: Palette,
+    score_details: ScoreDetails,
+    gameplay_state: GameplayState,
+    dialogs_handler: DialogsHandler,
+    movement: f32,
+    movement_seconds: f32,
+    quit_requested: bool,
+    statistics: Statistics,
+    achievements: Achievements,
+    toasts: Toasts,
+    // Where the progress, the statistics and the achievements of the profile
+    // are saved.
+    save_dir: SaveDir,
+}
+
+impl TerminalGame {
+    fn new(
+        settings: Settings,
+        progress: Progress,
+        statistics: Statistics,
+        achievements: Achievements,
+        save_dir: SaveDir,
+    ) -> Self {
+        let config = SimulationConfig::default();
+        let (screen_width, screen_height) = config.screen_size;
+        let mut dialogs_handler = DialogsHandler::new(screen_width, screen_height);
+        // The terminal has no settings screen, level select or editor.
+        for item in &[
+            MainMenuItem::LevelSelect,
+            MainMenuItem::Editor,
+            MainMenuItem::Settings,
+        ] {
+            dialogs_handler.main_menu.set_enabled(*item, false);
+        }
+        dialogs_handler
+            .pause_menu
+            .set_enabled(PauseMenuItem::Settings, false);
+
+        let level = progress.level;
+        let mut game = TerminalGame {
+            gameplay_state: GameplayState::new(screen_width, screen_height, &config.sizes),
+            config,
+            settings,
+            palette: Palette::default(),
+            score_details: ScoreDetails::new(progress),
+            dialogs_handler,
+            movement: 0.0,
+            movement_seconds: 0.0,
+            quit_requested: false,
+            statistics,
+            achievements,
+            toasts: Toasts::default(),
+            save_dir,
+        };
+        game.reset(level);
+        game.refresh_main_menu();
+        game
+    }
+
+    fn transition(&mut self, intent: GameWorkflowIntent) -> bool {
+        let guards = Guards {
+            can_continue: self.score_details.score.level > 1,
+            testing_level: false,
+            saved_run: false,
+        };
+        match game_workflow_state_reducer::transition(
+            self.score_details.game_workflow_state,
+            intent,
+            guards,
+        ) {
+            Ok(transition) => {
+                self.score_details.game_workflow_state = transition.state;
+                for effect in transition.effects {
+                    self.run_effect(effect);
+                }
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    // The terminal has no settings screen or level select to refresh and no
+    // saved run.
+    fn run_effect(&mut self, effect: Effect) {
+        let breakable_bricks = self.gameplay_state.bricks_wall.breakable_bricks_count();
+        if let Err(e) =
+            self.score_details
+                .run_effect(effect, breakable_bricks, &self.save_dir.score_file())
+        {
+            println!("Progress saving error: {:?} ", e);
+        }
+        match effect {
+            Effect::RecordGameOver => {}
+            Effect::RecordClear => self.statistics.record_clear(),
+            Effect::SaveProgress => self.save_statistics(),
+            Effect::ResetLevel | Effect::ResetFirstLevel | Effect::ResetNextLevel => {
+                self.reset_gameplay()
+            }
+            Effect::StopSkateboard => self.movement = 0.0,
+            Effect::EndEndlessRun => self.end_endless_run(),
+            Effect::RefreshMainMenu => self.refresh_main_menu(),
+            Effect::RecordGameStart => self.statistics.record_game_start(),
+            Effect::RefreshSettingsMenu
+            | Effect::RefreshLevelSelect
+            | Effect::RestoreRun
+            | Effect::DiscardSavedRun => {}
+        }
+    }
+
+    fn refresh_main_menu(&mut self) {
+        let can_continue = self.score_details.score.level > 1;
+        self.dialogs_handler
+            .main_menu
+            .set_enabled(MainMenuItem::Continue, can_continue);
+    }
+
+    fn end_endless_run(&mut self) {
+        if self.score_details.score.endless_seed.take().is_some() {
+            self.reset(self.score_details.progress.level);
+        }
+    }
+
+    fn reset(&mut self, level: i32) {
+        self.score_details.start_level(level);
+        self.reset_gameplay();
+    }
+
+    // Builds the wall, the ball and the skateboard of the started level.
+    fn reset_gameplay(&mut self) {
+        let level = self.score_details.score.level;
+        let (source, seed) = match self.score_details.score.endless_seed {
+            Some(seed) => (LevelSource::Generated, seed),
+            None => (LevelSource::Files, 0),
+        };
+        let grid = self.config.brick_grid();
+        let layout = self
+            .config
+            .level_layout(&source, level, seed)
+            .unwrap_or_else(|_| LevelLayout::full(grid.columns, grid.rows));
+
+        let (screen_width, screen_height) = self.config.screen_size;
+        self.gameplay_state = GameplayState::new(screen_width, screen_height, &self.config.sizes);
+        self.gameplay_state.bricks_wall = BricksWall::from_layout(&grid, &layout);
+        self.gameplay_state.ball.speed =
+            self.config.ball_speed * self.settings.difficulty.ball_speed_multiplier();
+        self.movement = 0.0;
+    }
+
+    fn key_down_event(&mut self, code: KeyCode) {
+        if code == KeyCode::Char('q') {
+            self.quit_requested = true;
+            return;
+        }
+        let state = self.score_details.game_workflow_state;
+        if let GameWorkflowState::Play = state {
+            match code {
+                KeyCode::Left | KeyCode::Char('a') => self.hold_movement(-1.0),
+                KeyCode::Right | KeyCode::Char('d') => self.hold_movement(1.0),
+                KeyCode::Esc | KeyCode::Char('p') => {
+                    self.transition(GameWorkflowIntent::Pause);
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        let navigation = match code {
+            KeyCode::Up => Navigation::Previous,
+            KeyCode::Down => Navigation::Next,
+            KeyCode::Left => Navigation::Decrease,
+            KeyCode::Right => Navigation::Increase,
+            KeyCode::Enter | KeyCode::Char(' ') => Navigation::Activate,
+            KeyCode::Esc => Navigation::Back,
+            _ => return,
+        };
+        self.navigate(state, navigation);
+    }
+
+    fn hold_movement(&mut self, movement: f32) {
+        self.movement = movement;
+        self.movement_seconds = HOLD_SECONDS;
+    }
+
+    // The same transitions as the dialogs of the window.
+    fn navigate(&mut self, state: GameWorkflowState, navigation: Navigation) {
+        match (state, navigation) {
+            (GameWorkflowState::MainMenu, _) => {
+                if let Some(item) = self.dialogs_handler.main_menu.navigate(navigation) {
+                    self.activate_main_menu_item(item);
+                }
+            }
+            (GameWorkflowState::Pause, Navigation::Back) => {
+                self.transition(GameWorkflowIntent::Resume);
+            }
+            (GameWorkflowState::Pause, _) => {
+                match self.dialogs_handler.pause_menu.navigate(navigation) {
+                    Some(PauseMenuItem::Resume) => {
+                        self.transition(GameWorkflowIntent::Resume);
+                    }
+                    Some(PauseMenuItem::MainMenu) => {
+                        self.transition(GameWorkflowIntent::OpenMainMenu);
+                    }
+                    _ => {}
+                }
+            }
+            (GameWorkflowState::NextLevel, Navigation::Activate) => {
+                self.transition(GameWorkflowIntent::StartGame);
+            }
+            (GameWorkflowState::GameOver, Navigation::Activate) => {
+                self.transition(GameWorkflowIntent::GoToHomePage);
+            }
+            (GameWorkflowState::NextLevel, Navigation::Back)
+            | (GameWorkflowState::GameOver, Navigation::Back)
+            | (GameWorkflowState::HighScores, Navigation::Activate)
+            | (GameWorkflowState::HighScores, Navigation::Back)
+            | (GameWorkflowState::Statistics, Navigation::Activate)
+            | (GameWorkflowState::Statistics, Navigation::Back) => {
+                self.transition(GameWorkflowIntent::OpenMainMenu);
+            }
+            _ => {}
+        }
+    }
+
+    fn activate_main_menu_item(&mut self, item: MainMenuItem) {
+        match item {
+            MainMenuItem::Play => self.start_game(None),
+            MainMenuItem::Continue => {
+                self.transition(GameWorkflowIntent::ContinueGame);
+            }
+            MainMenuItem::Endless => self.start_game(Some(rand::random())),
+            MainMenuItem::HighScores => {
+                self.transition(GameWorkflowIntent::OpenHighScores);
+            }
+            MainMenuItem::Statistics => {
+                self.transition(GameWorkflowIntent::OpenStatistics);
+            }
+            MainMenuItem::Quit => self.quit_requested = true,
+            _ => {}
+        }
+    }
+
+    // Starts from the first level, the levels of an endless run are generated
+    // from its seed.
+    fn start_game(&mut self, endless_seed: Option<u64>) {
+        let previous_seed = self.score_details.score.endless_seed;
+        self.score_details.score.endless_seed = endless_seed;
+        if !self.transition(GameWorkflowIntent::NewGame) {
+            self.score_details.score.endless_seed = previous_seed;
+        }
+    }
+
+    fn update(&mut self, seconds: f32) -> GameResult<()> {
+        self.toasts.update(seconds);
+        if !matches!(
+            self.score_details.game_workflow_state,
+            GameWorkflowState::Play
+        ) {
+            return Ok(());
+        }
+        self.movement_seconds -= seconds;
+        if self.movement_seconds <= 0.0 {
+            self.movement = 0.0;
+        }
+
+        let events = self.gameplay_state.step(
+            seconds,
+            self.movement,
+            self.config.screen_size,
+            &self.config.sizes,
+            false,
+        )?;
+        self.score_details.level_seconds += seconds;
+        self.statistics.record_step(&events, seconds);
+
+        if events.ball_lost {
+            self.transition(GameWorkflowIntent::Lose);
+            self.check_achievements();
+            return Ok(());
+        }
+
+        self.score_details.score.score_result =
+            self.gameplay_state.bricks_wall.broken_bricks_count();
+        if self.gameplay_state.bricks_wall.all_bricks_are_broken() {
+            self.transition(GameWorkflowIntent::Win);
+        }
+        self.check_achievements();
+        Ok(())
+    }
+
+    fn check_achievements(&mut self) {
+        let facts = Facts::new(&self.statistics, &self.score_details.progress);
+        let unlocked = self.achievements.check(&facts);
+        if unlocked.is_empty() {
+            return;
+        }
+        for achievement in unlocked {
+            self.toasts
+                .push(format!("Achievement unlocked: {}", achievement.title));
+        }
+        if let Err(e) = self.achievements.save(&self.save_dir.achievements_file()) {
+            println!("Achievements saving error: {:?} ", e);
+        }
+    }
+
+    fn save_progress(&mut self) {
+        if let Err(e) = self
+            .score_details
+            .save_progress(&self.save_dir.score_file())
+        {
+            println!("Progress saving error: {:?} ", e);
+        }
+        self.save_statistics();
+    }
+
+    fn save_statistics(&self) {
+        if let Err(e) = self.statistics.save(&self.save_dir.stats_file()) {
+            println!("Statistics saving error: {:?} ", e);
+        }
+    }
+
+    fn draw(&mut self, columns: usize, rows: usize, alpha: f32) -> GameResult<TerminalRenderer> {
+        let mut terminal = TerminalRenderer::new(
+            columns,
+            rows,
+            self.config.screen_size,
+            self.config.sizes,
+            self.palette,
+        );
+        let TerminalGame {
+            config,
+            palette,
+            score_details,
+            gameplay_state,
+            dialogs_handler,
+            statistics,
+            toasts,
+            ..
+        } = self;
+
+        renderer::render(&mut terminal, |draw_list| {
+            let dialog_type = match score_details.game_workflow_state {
+                GameWorkflowState::Play => {
+                    score_details.draw(draw_list, config.screen_size.0)?;
+                    gameplay_state.draw(draw_list, palette, &config.sizes, alpha)?;
+                    return toasts.draw(draw_list, config.screen_size.0, palette);
+                }
+                GameWorkflowState::NextLevel => DialogType::NextLevelDialog,
+                GameWorkflowState::GameOver => DialogType::GameOverDialog,
+                GameWorkflowState::HighScores => DialogType::HighScoresDialog,
+                GameWorkflowState::Statistics => DialogType::StatisticsDialog,
+                GameWorkflowState::Pause => DialogType::PauseDialog,
+                _ => DialogType::MainMenuDialog,
+            };
+            dialogs_handler.draw(
+                draw_list,
+                dialog_type,
+                score_details.score,
+                &score_details.progress,
+                statistics,
+                palette,
+            )?;
+            toasts.draw(draw_list, config.screen_size.0, palette)
+        })?;
+        Ok(terminal)
+    }
+}
+
+fn terminal_color(color: Color) -> style::Color {
+    let (r, g, b) = color.to_rgb();
+    style::Color::Rgb { r, g, b }
+}
+
+// Prints the cells row by row, the colours only change between the cells
+// which differ.
+fn print_frame(stdout: &mut io::Stdout, frame: &TerminalRenderer) -> GameResult<()> {
+    let mut last: Option<Cell> = None;
+    for (row, cells) in frame.cells().chunks(frame.columns).enumerate() {
+        queue!(stdout, cursor::MoveTo(0, row as u16)).map_err(terminal_error)?;
+        for cell in cells {
+            if last.map_or(true, |last| last.foreground != cell.foreground) {
+                queue!(stdout, SetForegroundColor(terminal_color(cell.foreground)))
+                    .map_err(terminal_error)?;
+            }
+            if last.map_or(true, |last| last.background != cell.background) {
+                let background = cell.background.map_or(style::Color::Reset, terminal_color);
+                queue!(stdout, SetBackgroundColor(background)).map_err(terminal_error)?;
+            }
+            queue!(stdout, Print(cell.character)).map_err(terminal_error)?;
+            last = Some(*cell);
+        }
+    }
+    stdout.flush()?;
+    Ok(())
+}
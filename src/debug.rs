@@ -1,20 +1,14 @@
-use ggez::graphics;
-use ggez::{Context, GameResult};
+use crate::renderer::{RectStyle, Renderer};
+use crate::types::{Color, GameResult, Rect};
 
 pub fn is_active() -> bool {
     std::env::var("DEBUG").is_ok()
 }
 
-pub fn draw_rect_outline(bounding_box: graphics::Rect, ctx: &mut Context) -> GameResult<()> {
-    let draw_mode =
-        graphics::DrawMode::Stroke(graphics::StrokeOptions::default().with_line_width(1.0));
-    let red = graphics::Color::from_rgb(255, 0, 0);
-    let outline = graphics::MeshBuilder::new()
-        .rectangle(draw_mode, bounding_box, red)
-        .unwrap()
-        .build(ctx)
-        .unwrap();
-
-    graphics::draw(ctx, &outline, graphics::DrawParam::default())?;
-    Ok(())
+pub fn draw_rect_outline(
+    bounding_box: Rect,
+    color: Color,
+    renderer: &mut dyn Renderer,
+) -> GameResult<()> {
+    renderer.rectangle(bounding_box, RectStyle::Stroke(1.0), color)
 }
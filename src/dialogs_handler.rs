@@ -1,109 +1,605 @@
-use crate::assets::{Sprite, TextSprite};
 use crate::game_settings_entities::Score;
-use ggez::mint::Point2;
-use ggez::{Context, GameResult};
+use crate::progress::Progress;
+use crate::renderer::Renderer;
+use crate::settings::{key_name, ControlScheme, Settings};
+use crate::statistics::Statistics;
+use crate::theme::Palette;
+use crate::types::{GameResult, Point2};
+use crate::widgets::{centered_column, Menu, Navigation};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MainMenuItem {
+    Play,
+    Continue,
+    Endless,
+    LevelSelect,
+    Editor,
+    HighScores,
+    Statistics,
+    Settings,
+    Quit,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PauseMenuItem {
+    Resume,
+    Settings,
+    MainMenu,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum QuitMenuItem {
+    Quit,
+    Cancel,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SettingsItem {
+    Volume,
+    ControlScheme,
+    MoveLeftKey,
+    MoveRightKey,
+    PauseKey,
+    Difficulty,
+    Theme,
+    Fullscreen,
+    ColorBlindMode,
+    Back,
+}
 
 #[derive(Debug)]
-pub struct DialogsHandler {}
+pub struct DialogsHandler {
+    pub main_menu: Menu<MainMenuItem>,
+    pub pause_menu: Menu<PauseMenuItem>,
+    pub quit_menu: Menu<QuitMenuItem>,
+    pub settings_menu: Menu<SettingsItem>,
+    pub level_select_menu: Menu<i32>,
+    // The shown page of the level select grid, counted from 0.
+    pub level_select_page: i32,
+    screen_width: f32,
+    screen_height: f32,
+}
 
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum DialogType {
+    MainMenuDialog,
     NextLevelDialog,
     GameOverDialog,
+    HighScoresDialog,
+    StatisticsDialog,
+    PauseDialog,
+    SettingsDialog,
+    LevelSelectDialog,
+    QuitDialog,
 }
 
 impl DialogsHandler {
     pub const PADDING: f32 = 15.0;
-    pub fn new() -> Self {
-        DialogsHandler {}
+    pub const LEVEL_SELECT_COLUMNS: usize = 5;
+    pub const LEVEL_SELECT_ROWS: usize = 4;
+    pub const LEVEL_CELL_SIZE: (f32, f32) = (120.0, 60.0);
+    pub fn new(screen_width: f32, screen_height: f32) -> Self {
+        let main_menu = Menu::new(
+            vec![
+                (MainMenuItem::Play, "main_menu_play", "Play"),
+                (MainMenuItem::Continue, "main_menu_continue", "Continue"),
+                (MainMenuItem::Endless, "main_menu_endless", "Endless"),
+                (
+                    MainMenuItem::LevelSelect,
+                    "main_menu_level_select",
+                    "Level Select",
+                ),
+                (MainMenuItem::Editor, "main_menu_editor", "Level Editor"),
+                (
+                    MainMenuItem::HighScores,
+                    "main_menu_high_scores",
+                    "High Scores",
+                ),
+                (
+                    MainMenuItem::Statistics,
+                    "main_menu_statistics",
+                    "Statistics",
+                ),
+                (MainMenuItem::Settings, "main_menu_settings", "Settings"),
+                (MainMenuItem::Quit, "main_menu_quit", "Quit"),
+            ],
+            screen_width,
+            screen_height,
+        );
+
+        let pause_menu = Menu::new(
+            vec![
+                (PauseMenuItem::Resume, "pause_menu_resume", "Resume"),
+                (PauseMenuItem::Settings, "pause_menu_settings", "Settings"),
+                (PauseMenuItem::MainMenu, "pause_menu_main_menu", "Main menu"),
+            ],
+            screen_width,
+            screen_height,
+        );
+
+        let quit_menu = Menu::new(
+            vec![
+                (QuitMenuItem::Quit, "quit_menu_quit", "Save and quit"),
+                (QuitMenuItem::Cancel, "quit_menu_cancel", "Keep playing"),
+            ],
+            screen_width,
+            screen_height,
+        );
+
+        let settings_menu = Menu::new(
+            vec![
+                (SettingsItem::Volume, "settings_volume", ""),
+                (SettingsItem::ControlScheme, "settings_control_scheme", ""),
+                (SettingsItem::MoveLeftKey, "settings_move_left_key", ""),
+                (SettingsItem::MoveRightKey, "settings_move_right_key", ""),
+                (SettingsItem::PauseKey, "settings_pause_key", ""),
+                (SettingsItem::Difficulty, "settings_difficulty", ""),
+                (SettingsItem::Theme, "settings_theme", ""),
+                (SettingsItem::Fullscreen, "settings_fullscreen", ""),
+                (
+                    SettingsItem::ColorBlindMode,
+                    "settings_color_blind_mode",
+                    "",
+                ),
+                (SettingsItem::Back, "settings_back", "Back"),
+            ],
+            screen_width,
+            screen_height,
+        );
+
+        DialogsHandler {
+            main_menu,
+            pause_menu,
+            quit_menu,
+            settings_menu,
+            level_select_menu: Menu::grid(
+                Vec::new(),
+                Self::LEVEL_SELECT_COLUMNS,
+                Self::LEVEL_CELL_SIZE,
+                screen_width,
+                screen_height,
+            ),
+            level_select_page: 0,
+            screen_width,
+            screen_height,
+        }
     }
 
     pub fn draw(
         &mut self,
-        ctx: &mut Context,
-        screen_width: f32,
-        screen_height: f32,
+        renderer: &mut dyn Renderer,
         dialog_type: DialogType,
         score: Score,
+        progress: &Progress,
+        statistics: &Statistics,
+        palette: &Palette,
     ) -> GameResult<()> {
         match dialog_type {
-            DialogType::GameOverDialog => {
-                self.draw_game_over_dialog(ctx, screen_width, screen_height, score)
+            DialogType::MainMenuDialog => self.draw_main_menu_dialog(renderer, palette),
+            DialogType::GameOverDialog => self.draw_game_over_dialog(renderer, score),
+            DialogType::NextLevelDialog => self.draw_next_level_dialog(renderer, score),
+            DialogType::HighScoresDialog => self.draw_high_scores_dialog(renderer, score),
+            DialogType::StatisticsDialog => self.draw_statistics_dialog(renderer, statistics),
+            DialogType::PauseDialog => self.draw_pause_dialog(renderer, palette),
+            DialogType::QuitDialog => self.draw_quit_dialog(renderer, palette),
+            DialogType::SettingsDialog => self.settings_menu.draw(renderer, palette),
+            DialogType::LevelSelectDialog => {
+                self.draw_level_select_dialog(renderer, progress, palette)
             }
-            DialogType::NextLevelDialog => {
-                self.draw_next_level_dialog(ctx, screen_width, screen_height, score)
+        }
+    }
+
+    // Writes the current values into the labels of the settings screen. The key
+    // which is being rebound shows a prompt instead of its value. The theme can
+    // be changed only from the main menu, because it rebuilds the bricks wall.
+    pub fn refresh_settings_menu(
+        &mut self,
+        settings: &Settings,
+        rebinding: Option<SettingsItem>,
+        from_pause: bool,
+    ) {
+        let on_off = |enabled: bool| if enabled { "On" } else { "Off" };
+        let key_label = |item: SettingsItem, name: &str, keycode| {
+            if rebinding == Some(item) {
+                format!("{}: press a key", name)
+            } else {
+                format!("{}: {}", name, key_name(keycode))
             }
+        };
+        let control_scheme = match settings.control_scheme {
+            ControlScheme::Keyboard => "Keyboard",
+            ControlScheme::Mouse => "Mouse",
+        };
+
+        let labels = [
+            (
+                SettingsItem::Volume,
+                format!("Volume: {}%", (settings.volume * 100.0).round()),
+            ),
+            (
+                SettingsItem::ControlScheme,
+                format!("Controls: {}", control_scheme),
+            ),
+            (
+                SettingsItem::MoveLeftKey,
+                key_label(
+                    SettingsItem::MoveLeftKey,
+                    "Move left",
+                    settings.key_bindings.left,
+                ),
+            ),
+            (
+                SettingsItem::MoveRightKey,
+                key_label(
+                    SettingsItem::MoveRightKey,
+                    "Move right",
+                    settings.key_bindings.right,
+                ),
+            ),
+            (
+                SettingsItem::PauseKey,
+                key_label(SettingsItem::PauseKey, "Pause", settings.key_bindings.pause),
+            ),
+            (
+                SettingsItem::Difficulty,
+                format!("Difficulty: {:?}", settings.difficulty),
+            ),
+            (SettingsItem::Theme, format!("Theme: {}", settings.theme)),
+            (
+                SettingsItem::Fullscreen,
+                format!("Fullscreen: {}", on_off(settings.fullscreen)),
+            ),
+            (
+                SettingsItem::ColorBlindMode,
+                format!("Colour-blind mode: {}", on_off(settings.color_blind_mode)),
+            ),
+        ];
+        for (item, label) in labels.iter() {
+            self.settings_menu.set_label(*item, label);
         }
+        self.settings_menu
+            .set_enabled(SettingsItem::Theme, !from_pause);
     }
 
-    pub fn draw_game_over_dialog(
+    // Opens the level select screen on the page with the highest unlocked
+    // level, which is focused.
+    pub fn refresh_level_select_menu(&mut self, progress: &Progress) {
+        let highest_unlocked_level = progress.highest_unlocked_level();
+        self.show_level_select_page(progress, Self::level_select_last_page(progress));
+        self.level_select_menu.focus_item(highest_unlocked_level);
+    }
+
+    // The pages after the one with the highest unlocked level would only have
+    // locked levels, so it is the last page.
+    pub fn level_select_last_page(progress: &Progress) -> i32 {
+        (progress.highest_unlocked_level() - 1) / Self::level_select_page_size()
+    }
+
+    fn level_select_page_size() -> i32 {
+        (Self::LEVEL_SELECT_COLUMNS * Self::LEVEL_SELECT_ROWS) as i32
+    }
+
+    // Moves to an earlier (negative pages) or a later page and keeps the
+    // focus in the same cell when its level is unlocked. Returns false at the
+    // first and the last page.
+    pub fn turn_level_select_page(&mut self, progress: &Progress, pages: i32) -> bool {
+        let page = nalgebra::clamp(
+            self.level_select_page + pages,
+            0,
+            Self::level_select_last_page(progress),
+        );
+        if page == self.level_select_page {
+            return false;
+        }
+        let focused = self.level_select_menu.focused;
+        self.show_level_select_page(progress, page);
+        self.focus_level_select_cell(progress, focused);
+        true
+    }
+
+    // The left and right navigation turn the page at the edges of the grid,
+    // the rest is the navigation of the grid.
+    pub fn navigate_level_select(
         &mut self,
-        ctx: &mut Context,
-        screen_width: f32,
-        screen_height: f32,
-        score: Score,
+        progress: &Progress,
+        navigation: Navigation,
+    ) -> Option<i32> {
+        let columns = Self::LEVEL_SELECT_COLUMNS;
+        let focused = self.level_select_menu.focused;
+        let column = focused % columns;
+        let row_start = focused - column;
+        let (pages, cell) = match navigation {
+            Navigation::Decrease if column == 0 => (-1, row_start + columns - 1),
+            Navigation::Increase if column == columns - 1 => (1, row_start),
+            _ => return self.level_select_menu.navigate(navigation),
+        };
+        if self.turn_level_select_page(progress, pages) {
+            self.focus_level_select_cell(progress, cell);
+            None
+        } else {
+            self.level_select_menu.navigate(navigation)
+        }
+    }
+
+    // Focuses the cell at the given index, or the highest unlocked level when
+    // the level of the cell is locked.
+    fn focus_level_select_cell(&mut self, progress: &Progress, index: usize) {
+        match self.level_select_menu.buttons.get(index) {
+            Some(button) if button.enabled => self.level_select_menu.focused = index,
+            _ => self
+                .level_select_menu
+                .focus_item(progress.highest_unlocked_level()),
+        }
+    }
+
+    // Rebuilds the grid of the level select screen for the given page. Every
+    // cell shows the stars of its level and the locked levels can't be
+    // focused.
+    pub fn show_level_select_page(&mut self, progress: &Progress, page: i32) {
+        let page_size = Self::level_select_page_size();
+        let page = nalgebra::clamp(page, 0, Self::level_select_last_page(progress));
+        let first_level = page * page_size + 1;
+        self.level_select_page = page;
+
+        let items = (first_level..first_level + page_size)
+            .map(|level| {
+                let stars = "*".repeat(progress.record(level).stars as usize);
+                let label = format!("{} {}", level, stars);
+                (
+                    level,
+                    format!("level_select_{}", level - first_level),
+                    String::from(label.trim_end()),
+                )
+            })
+            .collect();
+
+        self.level_select_menu = Menu::grid(
+            items,
+            Self::LEVEL_SELECT_COLUMNS,
+            Self::LEVEL_CELL_SIZE,
+            self.screen_width,
+            self.screen_height,
+        );
+        for level in first_level..first_level + page_size {
+            self.level_select_menu
+                .set_enabled(level, progress.is_unlocked(level));
+        }
+    }
+
+    // Draws the given lines one under another in the middle of the screen.
+    pub fn draw_text_column(
+        &self,
+        renderer: &mut dyn Renderer,
+        lines: &[(&'static str, &str)],
     ) -> GameResult<()> {
-        let game_over_text = "Game over!";
-        let score_text = format!("Score: {}", score.score_result);
-        let play_instructions_text = "Press SPACE to go to the home page.";
+        let sizes = lines
+            .iter()
+            .map(|(key, label)| renderer.text_size(key, label))
+            .collect::<Vec<(f32, f32)>>();
 
-        let mut game_over_sprite = Box::new(TextSprite::new(&game_over_text, ctx)?);
-        let mut score_sprite = Box::new(TextSprite::new(&score_text, ctx)?);
-        let mut play_instructions_sprite = Box::new(TextSprite::new(&play_instructions_text, ctx)?);
+        let bounds = centered_column(&sizes, self.screen_width, self.screen_height, Self::PADDING);
+        for ((key, label), rect) in lines.iter().zip(bounds) {
+            let pos = Point2 {
+                x: rect.x,
+                y: rect.y,
+            };
+            renderer.text(key, label, pos)?;
+        }
+        Ok(())
+    }
 
-        let game_over_pos = Point2 {
-            x: (screen_width / 2.0) - game_over_sprite.width(ctx) / 2.0,
-            y: (screen_height / 2.0) - game_over_sprite.height(ctx) / 2.0,
+    // Draws the title centered horizontally above the first button of a menu,
+    // but not above the top of the screen when the menu is tall.
+    pub fn draw_title(
+        &self,
+        renderer: &mut dyn Renderer,
+        key: &'static str,
+        title: &str,
+        first_button_top: Option<f32>,
+    ) -> GameResult<()> {
+        let (title_width, title_height) = renderer.text_size(key, title);
+        let title_pos = Point2 {
+            x: (self.screen_width / 2.0) - title_width / 2.0,
+            y: (first_button_top.unwrap_or(self.screen_height / 2.0)
+                - 2.0 * title_height
+                - Self::PADDING)
+                .max(Self::PADDING),
         };
+        renderer.text(key, title, title_pos)
+    }
 
-        let score_pos = Point2 {
-            x: (screen_width / 2.0) - score_sprite.width(ctx) / 2.0,
-            y: game_over_pos.y + Self::PADDING + score_sprite.height(ctx) / 2.0,
-        };
+    pub fn draw_main_menu_dialog(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        palette: &Palette,
+    ) -> GameResult<()> {
+        let first_button_top = self.main_menu.buttons.first().map(|button| button.bounds.y);
+        self.draw_title(
+            renderer,
+            "main_menu_title",
+            "Brick breaker",
+            first_button_top,
+        )?;
 
-        let play_instructions_pos = Point2 {
-            x: (screen_width / 2.0) - play_instructions_sprite.width(ctx) / 2.0,
-            y: score_pos.y + Self::PADDING + play_instructions_sprite.height(ctx) / 2.0,
-        };
-        game_over_sprite.draw(game_over_pos, ctx)?;
-        score_sprite.draw(score_pos, ctx)?;
-        play_instructions_sprite.draw(play_instructions_pos, ctx)?;
+        self.main_menu.draw(renderer, palette)
+    }
+
+    pub fn draw_pause_dialog(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        palette: &Palette,
+    ) -> GameResult<()> {
+        let first_button_top = self
+            .pause_menu
+            .buttons
+            .first()
+            .map(|button| button.bounds.y);
+        self.draw_title(renderer, "pause_title", "Paused", first_button_top)?;
+
+        self.pause_menu.draw(renderer, palette)
+    }
+
+    pub fn draw_quit_dialog(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        palette: &Palette,
+    ) -> GameResult<()> {
+        let first_button_top = self.quit_menu.buttons.first().map(|button| button.bounds.y);
+        self.draw_title(
+            renderer,
+            "quit_title",
+            "Quit? The run can be resumed later.",
+            first_button_top,
+        )?;
+
+        self.quit_menu.draw(renderer, palette)
+    }
+
+    pub fn draw_level_select_dialog(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        progress: &Progress,
+        palette: &Palette,
+    ) -> GameResult<()> {
+        let first_button_top = self
+            .level_select_menu
+            .buttons
+            .first()
+            .map(|button| button.bounds.y);
+        self.draw_title(
+            renderer,
+            "level_select_title",
+            "Level select",
+            first_button_top,
+        )?;
+        self.level_select_menu.draw(renderer, palette)?;
+
+        if let Some(level) = self.level_select_menu.focused_item() {
+            let best_score_text = format!("Best score: {}", progress.record(level).best_score);
+            let (best_score_width, _) =
+                renderer.text_size("level_select_best_score", &best_score_text);
+            let grid_bottom = self
+                .level_select_menu
+                .buttons
+                .last()
+                .map_or(self.screen_height / 2.0, |button| button.bounds.bottom());
+            let best_score_pos = Point2 {
+                x: (self.screen_width - best_score_width) / 2.0,
+                y: grid_bottom + 2.0 * Self::PADDING,
+            };
+            renderer.text("level_select_best_score", &best_score_text, best_score_pos)?;
+        }
 
+        let last_page = Self::level_select_last_page(progress);
+        if last_page > 0 {
+            let page_text = format!(
+                "Page {}/{} - PageUp, PageDown",
+                self.level_select_page + 1,
+                last_page + 1
+            );
+            let (page_width, page_height) = renderer.text_size("level_select_page", &page_text);
+            let page_pos = Point2 {
+                x: (self.screen_width - page_width) / 2.0,
+                y: self.screen_height - page_height - 2.0 * Self::PADDING,
+            };
+            renderer.text("level_select_page", &page_text, page_pos)?;
+        }
         Ok(())
     }
 
+    pub fn draw_game_over_dialog(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        score: Score,
+    ) -> GameResult<()> {
+        let score_text = format!("Score: {}", score.score_result);
+
+        self.draw_text_column(
+            renderer,
+            &[
+                ("game_over_title", "Game over!"),
+                ("game_over_score", &score_text),
+                (
+                    "game_over_instructions",
+                    "Press SPACE to go to the home page.",
+                ),
+                ("game_over_menu", "Press ESC to go to the main menu."),
+            ],
+        )
+    }
+
     pub fn draw_next_level_dialog(
         &mut self,
-        ctx: &mut Context,
-        screen_width: f32,
-        screen_height: f32,
+        renderer: &mut dyn Renderer,
         score: Score,
     ) -> GameResult<()> {
         let level_text = format!("Level: {}", score.level);
         let max_score_text = format!("Max score: {}", score.max_score_result);
-        let play_instructions_text = "Press SPACE to play a new game.";
+        let mut lines = vec![
+            ("next_level_level", level_text.as_str()),
+            ("next_level_max_score", max_score_text.as_str()),
+            ("next_level_instructions", "Press SPACE to play."),
+            ("next_level_menu", "Press ESC to go to the main menu."),
+        ];
 
-        let mut level_sprite = Box::new(TextSprite::new(&level_text, ctx)?);
-        let mut max_score_sprite = Box::new(TextSprite::new(&max_score_text, ctx)?);
-        let mut play_instructions_sprite = Box::new(TextSprite::new(&play_instructions_text, ctx)?);
+        let seed_text = score
+            .endless_seed
+            .map(|seed| format!("Endless run, seed: {}", seed));
+        if let Some(seed_text) = &seed_text {
+            lines.insert(1, ("next_level_seed", seed_text.as_str()));
+        }
 
-        let level_pos = Point2 {
-            x: (screen_width / 2.0) - level_sprite.width(ctx) / 2.0,
-            y: (screen_height / 2.0) - level_sprite.height(ctx) / 2.0,
-        };
+        self.draw_text_column(renderer, &lines)
+    }
 
-        let max_score_pos = Point2 {
-            x: (screen_width / 2.0) - max_score_sprite.width(ctx) / 2.0,
-            y: level_pos.y + Self::PADDING + max_score_sprite.height(ctx) / 2.0,
-        };
+    pub fn draw_high_scores_dialog(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        score: Score,
+    ) -> GameResult<()> {
+        let max_score_text = format!("Max score: {}", score.max_score_result);
+        let level_text = format!("Reached level: {}", score.level);
 
-        let play_instructions_pos = Point2 {
-            x: (screen_width / 2.0) - play_instructions_sprite.width(ctx) / 2.0,
-            y: max_score_pos.y + Self::PADDING + play_instructions_sprite.height(ctx) / 2.0,
-        };
-        level_sprite.draw(level_pos, ctx)?;
-        max_score_sprite.draw(max_score_pos, ctx)?;
-        play_instructions_sprite.draw(play_instructions_pos, ctx)?;
+        self.draw_text_column(
+            renderer,
+            &[
+                ("high_scores_title", "High scores"),
+                ("high_scores_max_score", &max_score_text),
+                ("high_scores_level", &level_text),
+                ("high_scores_back", "Press ESC to go back."),
+            ],
+        )
+    }
 
-        Ok(())
+    pub fn draw_statistics_dialog(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        statistics: &Statistics,
+    ) -> GameResult<()> {
+        let games_text = format!("Games played: {}", statistics.games_played);
+        let bricks_text = format!(
+            "Bricks broken: {} ({} normal, {} weak)",
+            statistics.bricks_broken.total(),
+            statistics.bricks_broken.normal,
+            statistics.bricks_broken.weak
+        );
+        let balls_text = format!("Balls lost: {}", statistics.balls_lost);
+        let rally_text = format!("Longest rally: {}", statistics.longest_rally);
+        let combo_text = format!("Best combo: {}", statistics.best_combo);
+        let time_text = format!("Play time: {}", statistics.play_time_text());
+
+        self.draw_text_column(
+            renderer,
+            &[
+                ("statistics_title", "Statistics"),
+                ("statistics_games", &games_text),
+                ("statistics_bricks", &bricks_text),
+                ("statistics_balls", &balls_text),
+                ("statistics_rally", &rally_text),
+                ("statistics_combo", &combo_text),
+                ("statistics_time", &time_text),
+                ("statistics_back", "Press ESC to go back."),
+            ],
+        )
     }
 }
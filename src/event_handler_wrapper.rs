@@ -1,28 +1,78 @@
-use crate::assets::Assets;
+use crate::achievements::{Achievements, Facts};
+use crate::assets::{Assets, TextCache};
+use crate::autopilot::{Autopilot, AutopilotSkill, Demo};
 use crate::debug;
-use crate::dialogs_handler::{DialogType, DialogsHandler};
+use crate::dialogs_handler::{
+    DialogType, DialogsHandler, MainMenuItem, PauseMenuItem, QuitMenuItem, SettingsItem,
+};
 use crate::game_settings_entities::{GameSettingsState, InputState, ScoreDetails, ScreenSize};
-use crate::game_workflow_state_reducer::{reduce, GameWorkflowIntent, GameWorkflowState};
-use crate::gameplay_entities::{Ball, BrickState, BricksWall, GameplayState, Size, Skateboard};
-use crate::math::Collision;
-use ggez::conf::Conf;
+use crate::game_workflow_state_reducer::{
+    self, Effect, GameWorkflowIntent, GameWorkflowState, Guards,
+};
+use crate::gameplay_entities::{
+    Ball, BallWatchdog, BrickGrid, BricksWall, GameplayState, Size, Skateboard, SpriteSizes,
+};
+use crate::level_editor::LevelEditor;
+use crate::level_generator;
+use crate::levels::{self, LevelLayout};
+use crate::progress::Progress;
+use crate::renderer::{self, GgezRenderer, Renderer, SpriteKind, TextMetrics};
+use crate::save_dir::SaveDir;
+use crate::saved_run::SavedRun;
+use crate::settings::{self, ControlScheme, KeyAction, Settings};
+use crate::statistics::Statistics;
+use crate::theme::{Theme, ThemeSelector};
+use crate::timestep::FixedTimestep;
+use crate::widgets::{self, Navigation, Toasts};
+use ggez::conf::{Conf, FullscreenType};
 use ggez::event::{self};
+use ggez::graphics;
+use ggez::mint::Point2;
 use ggez::timer;
 use ggez::{Context, GameError, GameResult};
-use std::fs::File;
-use std::io::prelude::*;
-
-pub const SCORE_FILE_NAME: &str = "score.txt";
 
 pub struct EventHandlerWrapper {
     game_settings_state: GameSettingsState,
     gameplay_state: GameplayState,
     dialogs_handler: DialogsHandler,
     conf: Conf,
+    quit_requested: bool,
+    rebinding: Option<SettingsItem>,
+    level_editor: LevelEditor,
+    timestep: FixedTimestep,
+    demo: Option<Demo>,
+    // The time without any input on the main menu, the demo starts after
+    // ATTRACT_MODE_SECONDS.
+    idle_seconds: f32,
+    // The run which was saved when the game was closed, the main menu offers
+    // to resume it.
+    saved_run: Option<SavedRun>,
+    // Where the settings, the progress and the run of the profile are saved.
+    save_dir: SaveDir,
+    // The lifetime statistics of the profile, saved with the progress.
+    statistics: Statistics,
+    // The achievements of the profile, an unlock is shown by a toast.
+    achievements: Achievements,
+    toasts: Toasts,
 }
 
 impl EventHandlerWrapper {
-    pub fn new(conf: Conf, assets: Assets, level: i32, max_score: usize) -> Self {
+    pub const UPDATES_PER_SECOND: u32 = 60;
+    // The most updates which are run in a single frame to catch up.
+    pub const MAX_CATCH_UP_STEPS: u32 = 5;
+    pub const ATTRACT_MODE_SECONDS: f32 = 20.0;
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        conf: Conf,
+        assets: Assets,
+        theme_selector: ThemeSelector,
+        settings: Settings,
+        progress: Progress,
+        statistics: Statistics,
+        achievements: Achievements,
+        save_dir: SaveDir,
+    ) -> Self {
         let screen_width = conf.window_mode.width;
         let screen_height = conf.window_mode.height;
 
@@ -31,86 +81,323 @@ impl EventHandlerWrapper {
             screen_height: screen_height,
         };
 
-        let skateboard_size = Size {
-            height: *(&assets.skateboard_normal.height()) as f32,
-            width: *(&assets.skateboard_normal.width()) as f32,
-        };
-        let skateboard_height = skateboard_size.height;
-
-        let brick_height = assets.brick_survived.height() as f32;
-        let brick_width = assets.brick_survived.width() as f32;
-        let ball_width = assets.ball_flying.width() as f32;
-        let ball_height = assets.ball_flying.height() as f32;
+        let gameplay_state = new_gameplay_state(screen_width, screen_height, &assets);
+        let text_cache = TextCache::new(assets.font, assets.palette.text);
 
+        let level = progress.level;
         let game_settings_state = GameSettingsState {
             assets: assets,
+            text_cache,
+            theme_selector,
+            settings,
             input: InputState::default(),
             screen_size: screen_size,
-            score_details: ScoreDetails::new(level, max_score),
+            score_details: ScoreDetails::new(progress),
         };
 
-        let gameplay_state = GameplayState {
-            skateboard: Skateboard::new(skateboard_size, screen_height, screen_width),
-            bricks_wall: BricksWall::new(screen_width, screen_height, brick_height, brick_width),
-            ball: Ball::new(
-                screen_width,
-                screen_height,
-                skateboard_height,
-                ball_width,
-                ball_height,
-            ),
-        };
-
-        EventHandlerWrapper {
+        let mut event_handler_wrapper = EventHandlerWrapper {
             game_settings_state: game_settings_state,
             gameplay_state: gameplay_state,
-            dialogs_handler: DialogsHandler::new(),
+            dialogs_handler: DialogsHandler::new(screen_width, screen_height),
             conf: conf,
+            quit_requested: false,
+            rebinding: None,
+            level_editor: LevelEditor::new(level, LevelLayout::empty(0, 0)),
+            timestep: FixedTimestep::new(Self::UPDATES_PER_SECOND, Self::MAX_CATCH_UP_STEPS),
+            demo: None,
+            idle_seconds: 0.0,
+            saved_run: None,
+            save_dir,
+            statistics,
+            achievements,
+            toasts: Toasts::default(),
+        };
+        event_handler_wrapper.load_level_wall(level);
+        event_handler_wrapper.apply_difficulty();
+        event_handler_wrapper.refresh_main_menu();
+        event_handler_wrapper.refresh_settings_menu();
+        event_handler_wrapper
+    }
+
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
+    pub fn game_workflow_state(&self) -> GameWorkflowState {
+        self.game_settings_state.score_details.game_workflow_state
+    }
+
+    // Applies the intent to the current workflow state and runs the effects of
+    // the transition. Returns false if the transition is not allowed.
+    pub fn transition(&mut self, intent: GameWorkflowIntent) -> bool {
+        match game_workflow_state_reducer::transition(
+            self.game_workflow_state(),
+            intent,
+            self.guards(),
+        ) {
+            Ok(transition) => {
+                self.game_settings_state.score_details.game_workflow_state = transition.state;
+                for effect in transition.effects {
+                    self.run_effect(effect);
+                }
+                true
+            }
+            Err(e) => {
+                println!("State reducer error: {:?} ", e);
+                false
+            }
         }
     }
 
-    pub fn handle_collisions(&mut self) -> GameResult {
-        let skateboard_rect = self
-            .gameplay_state
-            .skateboard
-            .bounding_rect(&self.game_settings_state.assets);
-        let ball_skateboard_collision = self.gameplay_state.ball.collides_rect(skateboard_rect);
-        self.gameplay_state.ball.bounce(ball_skateboard_collision);
-
-        for brick in &mut self.gameplay_state.bricks_wall.bricks {
-            if !matches!(brick.state, BrickState::Broken) {
-                let brick_rect = brick.bounding_rect(&self.game_settings_state.assets);
-                let ball_brick_collision = self.gameplay_state.ball.collides_rect(brick_rect);
-
-                match ball_brick_collision {
-                    Collision::None => {}
-                    _ => {
-                        match brick.broke() {
-                            Ok(_) => {}
-                            _ => {
-                                return Err(GameError::EventLoopError(String::from(
-                                    "An error with breaking of a brick occurred.",
-                                )));
-                            }
-                        }
-                        self.gameplay_state.ball.bounce(ball_brick_collision);
-                    }
+    pub fn guards(&self) -> Guards {
+        Guards {
+            can_continue: self.game_settings_state.score_details.score.level > 1,
+            testing_level: self.level_editor.testing,
+            saved_run: self.saved_run.is_some(),
+        }
+    }
+
+    pub fn run_effect(&mut self, effect: Effect) {
+        let breakable_bricks = self.gameplay_state.bricks_wall.breakable_bricks_count();
+        if let Err(e) = self.game_settings_state.score_details.run_effect(
+            effect,
+            breakable_bricks,
+            &self.save_dir.score_file(),
+        ) {
+            println!("Progress saving error: {:?} ", e);
+        }
+        match effect {
+            Effect::RecordGameOver => {}
+            Effect::RecordClear => {
+                if self.records_statistics() {
+                    self.statistics.record_clear();
                 }
             }
+            Effect::SaveProgress => self.save_statistics(),
+            Effect::ResetLevel | Effect::ResetFirstLevel | Effect::ResetNextLevel => {
+                self.reset_gameplay()
+            }
+            Effect::StopSkateboard => self.game_settings_state.input.movement = 0.0,
+            Effect::EndEndlessRun => self.end_endless_run(),
+            Effect::RefreshMainMenu => self.refresh_main_menu(),
+            Effect::RefreshSettingsMenu => self.refresh_settings_menu(),
+            Effect::RefreshLevelSelect => self
+                .dialogs_handler
+                .refresh_level_select_menu(&self.game_settings_state.score_details.progress),
+            Effect::RestoreRun => {
+                if let Some(saved_run) = self.saved_run.take() {
+                    saved_run.restore(
+                        &mut self.game_settings_state.score_details,
+                        &mut self.gameplay_state,
+                    );
+                }
+                self.remove_saved_run();
+            }
+            Effect::DiscardSavedRun => {
+                if self.saved_run.take().is_some() {
+                    self.remove_saved_run();
+                }
+            }
+            Effect::RecordGameStart => {
+                if self.records_statistics() {
+                    self.statistics.record_game_start();
+                }
+            }
+        }
+    }
+
+    // The demo and the levels tested from the editor are not played by the
+    // player, so they don't count in the statistics.
+    pub fn records_statistics(&self) -> bool {
+        self.demo.is_none() && !self.level_editor.testing
+    }
+
+    // Unlocks the achievements which were reached by the last update. They
+    // are saved right away, so an unlock isn't lost with a crash.
+    pub fn check_achievements(&mut self) {
+        if !self.records_statistics() {
+            return;
+        }
+        let facts = Facts::new(
+            &self.statistics,
+            &self.game_settings_state.score_details.progress,
+        );
+        let unlocked = self.achievements.check(&facts);
+        if unlocked.is_empty() {
+            return;
         }
+        for achievement in unlocked {
+            self.toasts
+                .push(format!("Achievement unlocked: {}", achievement.title));
+        }
+        if let Err(e) = self.achievements.save(&self.save_dir.achievements_file()) {
+            println!("Achievements saving error: {:?} ", e);
+        }
+    }
+
+    pub fn offer_saved_run(&mut self, saved_run: SavedRun) {
+        self.saved_run = Some(saved_run);
+        self.refresh_main_menu();
+    }
+
+    pub fn remove_saved_run(&mut self) {
+        if let Err(e) = SavedRun::remove(&self.save_dir.run_file()) {
+            println!("Saved run removing error: {:?} ", e);
+        }
+    }
+
+    pub fn refresh_main_menu(&mut self) {
+        // The saved run is offered in place of continuing from the reached level.
+        let guards = self.guards();
+        let main_menu = &mut self.dialogs_handler.main_menu;
+        if guards.saved_run {
+            main_menu.set_label(MainMenuItem::Continue, "Resume run");
+            main_menu.set_enabled(MainMenuItem::Continue, true);
+            main_menu.focus_item(MainMenuItem::Continue);
+        } else {
+            main_menu.set_label(MainMenuItem::Continue, "Continue");
+            main_menu.set_enabled(MainMenuItem::Continue, guards.can_continue);
+        }
+    }
+
+    pub fn refresh_settings_menu(&mut self) {
+        let from_pause = matches!(
+            self.game_workflow_state(),
+            GameWorkflowState::Settings { from_pause: true }
+        );
+        self.dialogs_handler.refresh_settings_menu(
+            &self.game_settings_state.settings,
+            self.rebinding,
+            from_pause,
+        );
+    }
+
+    // Applies every option of the settings which is not read directly while playing.
+    pub fn apply_settings(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let settings = self.game_settings_state.settings.clone();
+
+        if settings.theme != self.game_settings_state.assets.theme_name {
+            self.apply_theme(ctx, &settings.theme)?;
+        }
+
+        let fullscreen_type = if settings.fullscreen {
+            FullscreenType::Desktop
+        } else {
+            FullscreenType::Windowed
+        };
+        graphics::set_fullscreen(ctx, fullscreen_type)?;
+
+        self.game_settings_state.assets.set_volume(settings.volume);
+        self.game_settings_state
+            .assets
+            .set_color_blind_mode(settings.color_blind_mode);
+        self.apply_difficulty();
+        Ok(())
+    }
+
+    pub fn apply_difficulty(&mut self) {
+        let difficulty = self.game_settings_state.settings.difficulty;
+        self.gameplay_state.ball.speed = Ball::SPEED * difficulty.ball_speed_multiplier();
+    }
+
+    fn on_settings_changed(&mut self, ctx: &mut Context) {
+        if let Err(e) = self.apply_settings(ctx) {
+            println!("Settings error: {:?} ", e);
+        }
+        if let Err(e) = self
+            .game_settings_state
+            .settings
+            .save(&self.save_dir.settings_file())
+        {
+            println!("Settings saving error: {:?} ", e);
+        }
+        self.refresh_settings_menu();
+    }
+
+    pub fn background_color(&self) -> graphics::Color {
+        self.game_settings_state.assets.palette.background
+    }
+
+    // Reloads the assets from the given theme. The positions of the gameplay
+    // entities depend on the sizes of the sprites, so they are created again.
+    pub fn apply_theme(&mut self, ctx: &mut Context, theme_name: &str) -> GameResult<()> {
+        let theme = Theme::load(ctx, theme_name)?;
+        let assets = Assets::new(ctx, &theme)?;
+
+        self.gameplay_state = new_gameplay_state(
+            self.game_settings_state.screen_size.screen_width,
+            self.game_settings_state.screen_size.screen_height,
+            &assets,
+        );
+        self.game_settings_state.text_cache = TextCache::new(assets.font, assets.palette.text);
+        self.game_settings_state.assets = assets;
+        self.load_level_wall(self.game_settings_state.score_details.score.level);
+        self.apply_difficulty();
         Ok(())
     }
 
+    pub fn brick_grid(&self) -> BrickGrid {
+        BrickGrid::new(
+            self.game_settings_state.screen_size.screen_width,
+            self.game_settings_state.screen_size.screen_height,
+            self.game_settings_state.assets.brick_survived.height() as f32,
+            self.game_settings_state.assets.brick_survived.width() as f32,
+        )
+    }
+
+    // The generated layout in an endless run, otherwise the layout from the
+    // level file of the given level, or the full default wall if the level has no file.
+    pub fn level_layout(&self, level: i32) -> LevelLayout {
+        let grid = self.brick_grid();
+        if let Some(run_seed) = self.game_settings_state.score_details.score.endless_seed {
+            let seed = level_generator::level_seed(run_seed, level);
+            return level_generator::generate(seed, grid.columns, grid.rows);
+        }
+
+        match LevelLayout::load(&levels::level_file_name(level)) {
+            Ok(Some(layout)) => layout.resized(grid.columns, grid.rows),
+            Ok(None) => LevelLayout::full(grid.columns, grid.rows),
+            Err(e) => {
+                println!("Level loading error, the default wall is used: {:?} ", e);
+                LevelLayout::full(grid.columns, grid.rows)
+            }
+        }
+    }
+
+    pub fn load_level_wall(&mut self, level: i32) {
+        let layout = self.level_layout(level);
+        self.gameplay_state.bricks_wall = BricksWall::from_layout(&self.brick_grid(), &layout);
+    }
+
     pub fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        const DESIRED_FPS: u32 = 60;
-
-        while timer::check_update_time(ctx, DESIRED_FPS)? {
-            let seconds = 1.0 / (DESIRED_FPS as f32);
-            return match self.game_settings_state.score_details.game_workflow_state {
-                GameWorkflowState::NextLevel => self.update_next_level_dialog(ctx),
-                GameWorkflowState::GameOver => self.update_game_over_dialog(ctx),
-                GameWorkflowState::Play => self.update_play_game(ctx, seconds),
-            };
+        let frame_seconds = timer::delta(ctx).as_secs_f32();
+        for _ in 0..self.timestep.advance(frame_seconds) {
+            self.update_step(ctx, self.timestep.step_seconds)?;
+        }
+        Ok(())
+    }
+
+    pub fn update_step(&mut self, ctx: &mut Context, seconds: f32) -> GameResult<()> {
+        self.toasts.update(seconds);
+        match self.game_settings_state.score_details.game_workflow_state {
+            GameWorkflowState::MainMenu => self.update_main_menu(seconds),
+            GameWorkflowState::HighScores
+            | GameWorkflowState::Statistics
+            | GameWorkflowState::Pause
+            | GameWorkflowState::Settings { .. }
+            | GameWorkflowState::LevelSelect
+            | GameWorkflowState::Editor
+            | GameWorkflowState::ConfirmQuit => Ok(()),
+            GameWorkflowState::NextLevel => self.update_next_level_dialog(ctx),
+            GameWorkflowState::GameOver => self.update_game_over_dialog(ctx),
+            GameWorkflowState::Play => self.update_play_game(ctx, seconds),
+        }
+    }
+
+    pub fn update_main_menu(&mut self, seconds: f32) -> GameResult<()> {
+        self.idle_seconds += seconds;
+        if self.idle_seconds >= Self::ATTRACT_MODE_SECONDS {
+            self.start_demo(rand::random());
         }
         Ok(())
     }
@@ -123,23 +410,49 @@ impl EventHandlerWrapper {
         Ok(())
     }
 
-    pub fn update_play_game(&mut self, _ctx: &mut Context, seconds: f32) -> GameResult<()> {
-        self.gameplay_state.skateboard.update(
-            seconds,
-            self.game_settings_state.input.movement,
-            self.game_settings_state.screen_size.screen_width,
-            &self.game_settings_state.assets,
-        );
-
-        self.update_ball(seconds);
+    pub fn update_play_game(&mut self, ctx: &mut Context, seconds: f32) -> GameResult<()> {
+        if let Some(demo) = &mut self.demo {
+            let input = demo.autopilot.input(
+                &self.gameplay_state,
+                &SpriteSizes::from_assets(&self.game_settings_state.assets),
+                self.game_settings_state.screen_size.screen_width,
+                seconds,
+            );
+            self.game_settings_state.input.movement = input.movement;
+        } else if let (ControlScheme::Mouse, Some(pointer_x)) = (
+            self.game_settings_state.settings.control_scheme,
+            self.game_settings_state.input.pointer_x,
+        ) {
+            self.game_settings_state.input.movement = self.movement_towards(pointer_x, seconds);
+        }
 
-        match self.handle_collisions() {
-            Ok(_) => {}
-            _ => {
-                return Err(GameError::EventLoopError(String::from(
+        let screen_size = &self.game_settings_state.screen_size;
+        let events = self
+            .gameplay_state
+            .step(
+                seconds,
+                self.game_settings_state.input.movement,
+                (screen_size.screen_width, screen_size.screen_height),
+                &SpriteSizes::from_assets(&self.game_settings_state.assets),
+                debug::is_active(),
+            )
+            .map_err(|_| {
+                GameError::EventLoopError(String::from(
                     "An error while handling collisions occurred.",
-                )));
-            }
+                ))
+            })?;
+        self.game_settings_state.score_details.level_seconds += seconds;
+        if self.records_statistics() {
+            self.statistics.record_step(&events, seconds);
+        }
+
+        if events.ball_lost {
+            self.on_game_over();
+            self.check_achievements();
+            return Ok(());
+        }
+        if events.bounced {
+            self.game_settings_state.assets.play_bounce_sound(ctx)?;
         }
 
         self.game_settings_state.score_details.score.score_result =
@@ -148,199 +461,727 @@ impl EventHandlerWrapper {
         if self.gameplay_state.bricks_wall.all_bricks_are_broken() {
             self.on_win();
         }
+        self.check_achievements();
         Ok(())
     }
 
-    pub fn update_ball(&mut self, seconds: f32) {
-        let new_ball_pos = self.gameplay_state.ball.calculate_new_position(
-            seconds,
-            self.game_settings_state.screen_size.screen_width,
-            self.game_settings_state.screen_size.screen_height,
-            self.game_settings_state.assets.ball_flying.width() as f32,
-            self.game_settings_state.assets.ball_flying.height() as f32,
+    // The movement which brings the center of the skateboard under the mouse
+    // cursor, without overshooting it in a single step.
+    pub fn movement_towards(&self, pointer_x: f32, seconds: f32) -> f32 {
+        let skateboard_rect = self
+            .gameplay_state
+            .skateboard
+            .bounding_rect(&self.game_settings_state.assets);
+        let distance = pointer_x - (skateboard_rect.x + skateboard_rect.w / 2.0);
+        nalgebra::clamp(distance / (Skateboard::SPEED * seconds), -1.0, 1.0)
+    }
+
+    pub fn key_down_event(&mut self, ctx: &mut Context, keycode: event::KeyCode) {
+        if self.on_player_input() {
+            return;
+        }
+        if self.rebinding.is_some() {
+            self.key_down_event_rebinding(ctx, keycode);
+            return;
+        }
+
+        match self.game_settings_state.score_details.game_workflow_state {
+            GameWorkflowState::MainMenu
+            | GameWorkflowState::HighScores
+            | GameWorkflowState::Statistics
+            | GameWorkflowState::Pause
+            | GameWorkflowState::Settings { .. }
+            | GameWorkflowState::ConfirmQuit => {
+                if let Some(navigation) = widgets::navigation_from_key(keycode) {
+                    self.navigate(ctx, navigation);
+                }
+            }
+            GameWorkflowState::LevelSelect => self.key_down_event_level_select(ctx, keycode),
+            GameWorkflowState::NextLevel => self.key_down_event_next_level_dialog(ctx, keycode),
+            GameWorkflowState::GameOver => self.key_down_event_game_over_dialog(ctx, keycode),
+            GameWorkflowState::Play => self.key_down_event_play_game(keycode),
+            GameWorkflowState::Editor => self.key_down_event_editor(ctx, keycode),
+        };
+    }
+
+    // PageUp and PageDown turn the pages of the level select grid like the
+    // levels of the editor.
+    pub fn key_down_event_level_select(&mut self, ctx: &mut Context, keycode: event::KeyCode) {
+        let pages = match keycode {
+            event::KeyCode::PageUp => 1,
+            event::KeyCode::PageDown => -1,
+            _ => {
+                if let Some(navigation) = widgets::navigation_from_key(keycode) {
+                    self.navigate(ctx, navigation);
+                }
+                return;
+            }
+        };
+        self.dialogs_handler
+            .turn_level_select_page(&self.game_settings_state.score_details.progress, pages);
+    }
+
+    // The editor keys which are not part of the common navigation.
+    pub fn key_down_event_editor(&mut self, ctx: &mut Context, keycode: event::KeyCode) {
+        match keycode {
+            event::KeyCode::Delete => {
+                self.level_editor.remove_at_cursor();
+                self.on_level_edited();
+            }
+            event::KeyCode::T => self.start_level_test(),
+            event::KeyCode::S => self.save_edited_level(),
+            event::KeyCode::PageUp => self.edit_level(self.level_editor.level + 1),
+            event::KeyCode::PageDown => self.edit_level((self.level_editor.level - 1).max(1)),
+            _ => {
+                if let Some(navigation) = widgets::navigation_from_key(keycode) {
+                    self.navigate(ctx, navigation);
+                }
+            }
+        }
+    }
+
+    pub fn key_down_event_play_game(&mut self, keycode: event::KeyCode) {
+        let key_bindings = self.game_settings_state.settings.key_bindings;
+        let keyboard_controls = matches!(
+            self.game_settings_state.settings.control_scheme,
+            ControlScheme::Keyboard
         );
 
-        if new_ball_pos.y + self.game_settings_state.assets.ball_flying.height() as f32
-            > self.game_settings_state.screen_size.screen_height
-            && !debug::is_active()
-        {
-            self.on_game_over();
-        } else {
-            self.gameplay_state.ball.update(new_ball_pos);
+        if keycode == key_bindings.pause || keycode == event::KeyCode::Escape {
+            self.transition(GameWorkflowIntent::Pause);
+        } else if keycode == key_bindings.left && keyboard_controls {
+            self.game_settings_state.input.movement = -1.0;
+        } else if keycode == key_bindings.right && keyboard_controls {
+            self.game_settings_state.input.movement = 1.0;
         }
     }
 
-    pub fn key_down_event(&mut self, keycode: event::KeyCode) {
-        match self.game_settings_state.score_details.game_workflow_state {
-            GameWorkflowState::NextLevel => self.key_down_event_next_level_dialog(keycode),
-            GameWorkflowState::GameOver => self.key_down_event_game_over_dialog(keycode),
-            GameWorkflowState::Play => match keycode {
-                event::KeyCode::Left => self.game_settings_state.input.movement = -1.0,
-                event::KeyCode::Right => self.game_settings_state.input.movement = 1.0,
-                event::KeyCode::Space => {}
-                _ => (),
-            },
+    // The next key press after activating a key binding in the settings becomes
+    // the new binding. Escape cancels the rebinding.
+    pub fn key_down_event_rebinding(&mut self, ctx: &mut Context, keycode: event::KeyCode) {
+        let item = match self.rebinding.take() {
+            Some(item) => item,
+            None => return,
         };
+
+        if keycode != event::KeyCode::Escape && settings::is_bindable(keycode) {
+            let key_bindings = &mut self.game_settings_state.settings.key_bindings;
+            match item {
+                SettingsItem::MoveLeftKey => key_bindings.bind(KeyAction::Left, keycode),
+                SettingsItem::MoveRightKey => key_bindings.bind(KeyAction::Right, keycode),
+                SettingsItem::PauseKey => key_bindings.bind(KeyAction::Pause, keycode),
+                _ => {}
+            }
+        }
+        self.on_settings_changed(ctx);
     }
 
-    pub fn key_down_event_next_level_dialog(&mut self, keycode: event::KeyCode) {
+    pub fn key_down_event_next_level_dialog(&mut self, ctx: &mut Context, keycode: event::KeyCode) {
         match keycode {
-            event::KeyCode::Space => match reduce(
-                self.game_settings_state.score_details.game_workflow_state,
-                GameWorkflowIntent::StartGame,
-            ) {
-                Ok(state) => self.game_settings_state.score_details.game_workflow_state = state,
-                Err(e) => println!("State reducer error: {:?} ", e),
-            },
+            event::KeyCode::Space => {
+                self.transition(GameWorkflowIntent::StartGame);
+            }
+            event::KeyCode::Escape => self.navigate(ctx, Navigation::Back),
             _ => (),
         };
     }
 
-    pub fn key_down_event_game_over_dialog(&mut self, keycode: event::KeyCode) {
+    pub fn key_down_event_game_over_dialog(&mut self, ctx: &mut Context, keycode: event::KeyCode) {
         match keycode {
-            event::KeyCode::Space => match reduce(
-                self.game_settings_state.score_details.game_workflow_state,
-                GameWorkflowIntent::GoToHomePage,
-            ) {
-                Ok(state) => {
-                    self.reset(self.game_settings_state.score_details.score.level);
-                    self.game_settings_state.score_details.game_workflow_state = state;
-                }
-                Err(e) => println!("State reducer error: {:?} ", e),
-            },
+            event::KeyCode::Space => {
+                self.transition(GameWorkflowIntent::GoToHomePage);
+            }
+            event::KeyCode::Escape => self.navigate(ctx, Navigation::Back),
             _ => (),
         };
     }
 
-    pub fn on_game_over(&mut self) {
-        let level = self.game_settings_state.score_details.score.level;
-        let max_score = self
+    pub fn mouse_motion_event(&mut self, x: f32, y: f32) {
+        self.game_settings_state.input.pointer_x = Some(x);
+        self.idle_seconds = 0.0;
+
+        let point = Point2 { x, y };
+        match self.game_workflow_state() {
+            GameWorkflowState::MainMenu => {
+                self.dialogs_handler.main_menu.hover(point);
+            }
+            GameWorkflowState::Pause => {
+                self.dialogs_handler.pause_menu.hover(point);
+            }
+            GameWorkflowState::ConfirmQuit => {
+                self.dialogs_handler.quit_menu.hover(point);
+            }
+            GameWorkflowState::Settings { .. } => {
+                self.dialogs_handler.settings_menu.hover(point);
+            }
+            GameWorkflowState::LevelSelect => {
+                self.dialogs_handler.level_select_menu.hover(point);
+            }
+            GameWorkflowState::Editor => {
+                if let Some(cell) = self.brick_grid().cell_at(point) {
+                    self.level_editor.cursor = cell;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // A click activates the button under the cursor. On the settings screen the
+    // left button increases the value under the cursor and the right one decreases it.
+    pub fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: event::MouseButton,
+        x: f32,
+        y: f32,
+    ) {
+        if self.on_player_input() {
+            return;
+        }
+        let point = Point2 { x, y };
+        if let GameWorkflowState::Editor = self.game_workflow_state() {
+            self.mouse_button_down_event_editor(button, point);
+            return;
+        }
+
+        let hovered = match self.game_workflow_state() {
+            GameWorkflowState::MainMenu => self.dialogs_handler.main_menu.hover(point).is_some(),
+            GameWorkflowState::Pause => self.dialogs_handler.pause_menu.hover(point).is_some(),
+            GameWorkflowState::ConfirmQuit => self.dialogs_handler.quit_menu.hover(point).is_some(),
+            GameWorkflowState::Settings { .. } => {
+                self.dialogs_handler.settings_menu.hover(point).is_some()
+            }
+            GameWorkflowState::LevelSelect => self
+                .dialogs_handler
+                .level_select_menu
+                .hover(point)
+                .is_some(),
+            _ => false,
+        };
+
+        match (hovered, button) {
+            (true, event::MouseButton::Left) => self.navigate(ctx, Navigation::Activate),
+            (true, event::MouseButton::Right) => self.navigate(ctx, Navigation::Decrease),
+            _ => {}
+        }
+    }
+
+    // A click on a brick cycles its kind and a click on an empty cell places a
+    // brick there, the right button removes the brick under the cursor.
+    pub fn mouse_button_down_event_editor(
+        &mut self,
+        button: event::MouseButton,
+        point: Point2<f32>,
+    ) {
+        let grid = self.brick_grid();
+        let picked_cell = self
+            .gameplay_state
+            .bricks_wall
+            .bricks
+            .iter()
+            .find(|brick| {
+                brick
+                    .bounding_rect(&self.game_settings_state.assets)
+                    .contains(point)
+            })
+            .and_then(|brick| grid.cell_at(brick.base_pos));
+
+        let (column, row) = match picked_cell.or_else(|| grid.cell_at(point)) {
+            Some(cell) => cell,
+            None => return,
+        };
+        self.level_editor.cursor = (column, row);
+        match button {
+            event::MouseButton::Left => self.level_editor.cycle_at_cursor(),
+            event::MouseButton::Right if picked_cell.is_some() => {
+                self.level_editor.remove_at_cursor()
+            }
+            _ => return,
+        }
+        self.on_level_edited();
+    }
+
+    pub fn gamepad_button_down_event(&mut self, ctx: &mut Context, button: event::Button) {
+        if self.on_player_input() {
+            return;
+        }
+        if let Some(navigation) = widgets::navigation_from_gamepad(button) {
+            self.navigate(ctx, navigation);
+        }
+    }
+
+    // Handles the keyboard, gamepad and mouse navigation of the dialogs.
+    pub fn navigate(&mut self, ctx: &mut Context, navigation: Navigation) {
+        match (self.game_workflow_state(), navigation) {
+            (GameWorkflowState::MainMenu, _) => {
+                if let Some(item) = self.dialogs_handler.main_menu.navigate(navigation) {
+                    self.activate_main_menu_item(item);
+                }
+            }
+            (GameWorkflowState::Pause, Navigation::Back) => {
+                self.transition(GameWorkflowIntent::Resume);
+            }
+            (GameWorkflowState::Pause, _) => {
+                if let Some(item) = self.dialogs_handler.pause_menu.navigate(navigation) {
+                    self.activate_pause_menu_item(item);
+                }
+            }
+            (GameWorkflowState::ConfirmQuit, Navigation::Back) => {
+                self.transition(GameWorkflowIntent::CancelQuit);
+            }
+            (GameWorkflowState::ConfirmQuit, _) => {
+                match self.dialogs_handler.quit_menu.navigate(navigation) {
+                    Some(QuitMenuItem::Quit) => self.quit_requested = true,
+                    Some(QuitMenuItem::Cancel) => {
+                        self.transition(GameWorkflowIntent::CancelQuit);
+                    }
+                    None => {}
+                }
+            }
+            (GameWorkflowState::Settings { .. }, Navigation::Back) => {
+                self.transition(GameWorkflowIntent::CloseSettings);
+            }
+            (GameWorkflowState::Settings { .. }, Navigation::Decrease) => {
+                self.change_focused_setting(ctx, -1);
+            }
+            (GameWorkflowState::Settings { .. }, Navigation::Increase)
+            | (GameWorkflowState::Settings { .. }, Navigation::Activate) => {
+                self.change_focused_setting(ctx, 1);
+            }
+            (GameWorkflowState::Settings { .. }, _) => {
+                self.dialogs_handler.settings_menu.navigate(navigation);
+            }
+            (GameWorkflowState::Editor, Navigation::Back) => {
+                self.transition(GameWorkflowIntent::OpenMainMenu);
+            }
+            (GameWorkflowState::Editor, Navigation::Previous) => {
+                self.level_editor.move_cursor(0, -1);
+            }
+            (GameWorkflowState::Editor, Navigation::Next) => {
+                self.level_editor.move_cursor(0, 1);
+            }
+            (GameWorkflowState::Editor, Navigation::Decrease) => {
+                self.level_editor.move_cursor(-1, 0);
+            }
+            (GameWorkflowState::Editor, Navigation::Increase) => {
+                self.level_editor.move_cursor(1, 0);
+            }
+            (GameWorkflowState::Editor, Navigation::Activate) => {
+                self.level_editor.cycle_at_cursor();
+                self.on_level_edited();
+            }
+            (GameWorkflowState::LevelSelect, Navigation::Back) => {
+                self.transition(GameWorkflowIntent::OpenMainMenu);
+            }
+            (GameWorkflowState::LevelSelect, _) => {
+                if let Some(level) = self.dialogs_handler.navigate_level_select(
+                    &self.game_settings_state.score_details.progress,
+                    navigation,
+                ) {
+                    self.select_level(level);
+                }
+            }
+            (GameWorkflowState::NextLevel, Navigation::Activate) => {
+                self.transition(GameWorkflowIntent::StartGame);
+            }
+            (GameWorkflowState::GameOver, Navigation::Activate) => {
+                self.transition(GameWorkflowIntent::GoToHomePage);
+            }
+            (GameWorkflowState::NextLevel, Navigation::Back)
+            | (GameWorkflowState::GameOver, Navigation::Back)
+            | (GameWorkflowState::HighScores, Navigation::Activate)
+            | (GameWorkflowState::HighScores, Navigation::Back)
+            | (GameWorkflowState::Statistics, Navigation::Activate)
+            | (GameWorkflowState::Statistics, Navigation::Back) => {
+                self.transition(GameWorkflowIntent::OpenMainMenu);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn activate_main_menu_item(&mut self, item: MainMenuItem) {
+        match item {
+            MainMenuItem::Play => {
+                self.transition(GameWorkflowIntent::NewGame);
+            }
+            MainMenuItem::Continue if self.saved_run.is_some() => {
+                self.transition(GameWorkflowIntent::ResumeRun);
+            }
+            MainMenuItem::Continue => {
+                self.transition(GameWorkflowIntent::ContinueGame);
+            }
+            MainMenuItem::HighScores => {
+                self.transition(GameWorkflowIntent::OpenHighScores);
+            }
+            MainMenuItem::Statistics => {
+                self.transition(GameWorkflowIntent::OpenStatistics);
+            }
+            MainMenuItem::Settings => {
+                self.transition(GameWorkflowIntent::OpenSettings);
+            }
+            MainMenuItem::LevelSelect => {
+                self.transition(GameWorkflowIntent::OpenLevelSelect);
+            }
+            MainMenuItem::Endless => self.start_endless_run(rand::random()),
+            MainMenuItem::Editor => self.open_editor(),
+            MainMenuItem::Quit => self.quit_requested = true,
+        }
+    }
+
+    // Starts a run of generated levels from the first one. The levels of an
+    // endless run don't change the progress.
+    pub fn start_endless_run(&mut self, seed: u64) {
+        let score = &mut self.game_settings_state.score_details.score;
+        let previous_seed = score.endless_seed.replace(seed);
+        if !self.transition(GameWorkflowIntent::NewGame) {
+            self.game_settings_state.score_details.score.endless_seed = previous_seed;
+        }
+    }
+
+    // The attract mode plays generated levels with the autopilot until the
+    // player presses a key, the score of the player is restored after it.
+    pub fn start_demo(&mut self, seed: u64) {
+        let saved_score = self.game_settings_state.score_details.score;
+        if self.transition(GameWorkflowIntent::StartDemo) {
+            self.demo = Some(Demo {
+                autopilot: Autopilot::new(AutopilotSkill::Skilled, seed),
+                saved_score,
+            });
+            self.game_settings_state.score_details.score.endless_seed = Some(seed);
+            self.reset(1);
+        }
+    }
+
+    pub fn stop_demo(&mut self) {
+        if let Some(demo) = self.demo.take() {
+            self.game_settings_state.score_details.score.endless_seed = None;
+            self.reset(demo.saved_score.level);
+            self.game_settings_state.score_details.score = demo.saved_score;
+            self.transition(GameWorkflowIntent::StopDemo);
+        }
+    }
+
+    // Any key or button of the player resets the idle time of the main menu
+    // and stops the demo. Returns true if the input stopped the demo.
+    pub fn on_player_input(&mut self) -> bool {
+        self.idle_seconds = 0.0;
+        if self.demo.is_some() {
+            self.stop_demo();
+            return true;
+        }
+        false
+    }
+
+    // Goes back to the reached level of the progress after an endless run.
+    pub fn end_endless_run(&mut self) {
+        if self
             .game_settings_state
             .score_details
             .score
-            .max_score_result;
-        write_score_to_file(level, max_score);
+            .endless_seed
+            .take()
+            .is_some()
+        {
+            self.reset(self.game_settings_state.score_details.progress.level);
+        }
+    }
 
-        match reduce(
-            self.game_settings_state.score_details.game_workflow_state,
-            GameWorkflowIntent::Lose,
-        ) {
-            Ok(state) => self.game_settings_state.score_details.game_workflow_state = state,
-            Err(e) => println!("State reducer error: {:?} ", e),
+    // Opens the editor on the current level.
+    pub fn open_editor(&mut self) {
+        if self.transition(GameWorkflowIntent::OpenEditor) {
+            self.edit_level(self.game_settings_state.score_details.score.level);
         }
     }
 
-    pub fn on_win(&mut self) {
-        let level = self.game_settings_state.score_details.score.level + 1;
-        self.reset(level);
-        let max_score = self
+    pub fn edit_level(&mut self, level: i32) {
+        self.level_editor = LevelEditor::new(level, self.level_layout(level));
+        self.on_level_edited();
+    }
+
+    // Builds the bricks wall again from the edited layout.
+    pub fn on_level_edited(&mut self) {
+        self.gameplay_state.bricks_wall =
+            BricksWall::from_layout(&self.brick_grid(), &self.level_editor.layout);
+    }
+
+    pub fn save_edited_level(&mut self) {
+        let file_name = levels::level_file_name(self.level_editor.level);
+        self.level_editor.status = match self.level_editor.layout.save(&file_name) {
+            Ok(_) => format!("Saved to {}", file_name),
+            Err(e) => {
+                println!("Level saving error: {:?} ", e);
+                String::from("The level could not be saved.")
+            }
+        };
+    }
+
+    // Plays the edited layout without touching the progress. Losing or
+    // clearing it returns to the editor.
+    pub fn start_level_test(&mut self) {
+        if self.transition(GameWorkflowIntent::TestLevel) {
+            self.reset(self.game_settings_state.score_details.score.level);
+            self.on_level_edited();
+            self.level_editor.testing = true;
+            self.level_editor.status.clear();
+        }
+    }
+
+    pub fn stop_level_test(&mut self) {
+        self.level_editor.testing = false;
+        self.game_settings_state.score_details.score.score_result = 0;
+        if self.transition(GameWorkflowIntent::StopTest) {
+            self.on_level_edited();
+        }
+    }
+
+    pub fn select_level(&mut self, level: i32) {
+        if self
             .game_settings_state
             .score_details
-            .score
-            .max_score_result;
-        write_score_to_file(level, max_score);
+            .progress
+            .is_unlocked(level)
+            && self.transition(GameWorkflowIntent::SelectLevel)
+        {
+            self.reset(level);
+        }
+    }
 
-        match reduce(
-            self.game_settings_state.score_details.game_workflow_state,
-            GameWorkflowIntent::Win,
-        ) {
-            Ok(state) => self.game_settings_state.score_details.game_workflow_state = state,
-            Err(e) => println!("State reducer error: {:?} ", e),
+    pub fn activate_pause_menu_item(&mut self, item: PauseMenuItem) {
+        match item {
+            PauseMenuItem::Resume => {
+                self.transition(GameWorkflowIntent::Resume);
+            }
+            PauseMenuItem::Settings => {
+                self.transition(GameWorkflowIntent::OpenSettings);
+            }
+            PauseMenuItem::MainMenu if self.level_editor.testing => self.stop_level_test(),
+            PauseMenuItem::MainMenu => {
+                self.transition(GameWorkflowIntent::OpenMainMenu);
+            }
         }
     }
 
-    pub fn key_up_event(&mut self, keycode: event::KeyCode) {
-        match self.game_settings_state.score_details.game_workflow_state {
-            GameWorkflowState::Play => match keycode {
-                event::KeyCode::Left | event::KeyCode::Right => {
-                    self.game_settings_state.input.movement = 0.0
+    // Changes the focused option of the settings screen one step in the given
+    // direction. The options with two values toggle in both directions.
+    pub fn change_focused_setting(&mut self, ctx: &mut Context, direction: i32) {
+        let item = match self.dialogs_handler.settings_menu.focused_item() {
+            Some(item) => item,
+            None => return,
+        };
+
+        let settings = &mut self.game_settings_state.settings;
+        match item {
+            SettingsItem::Volume => settings.change_volume(direction as f32),
+            SettingsItem::ControlScheme => {
+                settings.control_scheme = match settings.control_scheme {
+                    ControlScheme::Keyboard => ControlScheme::Mouse,
+                    ControlScheme::Mouse => ControlScheme::Keyboard,
                 }
-                _ => (),
-            },
-            _ => (),
+            }
+            SettingsItem::MoveLeftKey | SettingsItem::MoveRightKey | SettingsItem::PauseKey => {
+                self.rebinding = Some(item);
+            }
+            SettingsItem::Difficulty => {
+                settings.difficulty = if direction > 0 {
+                    settings.difficulty.harder()
+                } else {
+                    settings.difficulty.easier()
+                }
+            }
+            SettingsItem::Theme => {
+                let theme_selector = &mut self.game_settings_state.theme_selector;
+                let theme_name = if direction > 0 {
+                    theme_selector.select_next()
+                } else {
+                    theme_selector.select_previous()
+                };
+                settings.theme = String::from(theme_name);
+            }
+            SettingsItem::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            SettingsItem::ColorBlindMode => settings.color_blind_mode = !settings.color_blind_mode,
+            SettingsItem::Back => {
+                self.transition(GameWorkflowIntent::CloseSettings);
+                return;
+            }
         }
+        self.on_settings_changed(ctx);
     }
 
-    pub fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        match self.game_settings_state.score_details.game_workflow_state {
-            GameWorkflowState::NextLevel => self.draw_next_level_dialog(ctx),
-            GameWorkflowState::GameOver => self.draw_game_over_dialog(ctx),
-            GameWorkflowState::Play => self.draw_play_game(ctx),
+    pub fn on_game_over(&mut self) {
+        if self.demo.is_some() {
+            self.reset(self.game_settings_state.score_details.score.level);
+            return;
+        }
+        if self.level_editor.testing {
+            self.stop_level_test();
+            return;
         }
-    }
 
-    pub fn draw_next_level_dialog(&mut self, ctx: &mut Context) -> GameResult<()> {
-        self.dialogs_handler.draw(
-            ctx,
-            self.game_settings_state.screen_size.screen_width,
-            self.game_settings_state.screen_size.screen_height,
-            DialogType::NextLevelDialog,
-            self.game_settings_state.score_details.score,
-        )
+        self.transition(GameWorkflowIntent::Lose);
     }
 
-    pub fn draw_game_over_dialog(&mut self, ctx: &mut Context) -> GameResult<()> {
-        self.dialogs_handler.draw(
-            ctx,
-            self.game_settings_state.screen_size.screen_width,
-            self.game_settings_state.screen_size.screen_height,
-            DialogType::GameOverDialog,
-            self.game_settings_state.score_details.score,
-        )
+    pub fn on_win(&mut self) {
+        if self.demo.is_some() {
+            self.reset(self.game_settings_state.score_details.score.level + 1);
+            return;
+        }
+        if self.level_editor.testing {
+            self.level_editor.status = String::from("The level was cleared.");
+            self.stop_level_test();
+            return;
+        }
+
+        self.transition(GameWorkflowIntent::Win);
     }
 
-    pub fn draw_play_game(&mut self, ctx: &mut Context) -> GameResult<()> {
-        self.game_settings_state
-            .score_details
-            .draw(ctx, self.game_settings_state.screen_size.screen_width)?;
-        self.gameplay_state
-            .skateboard
-            .draw(ctx, &self.game_settings_state.assets)?;
-        self.gameplay_state
-            .bricks_wall
-            .draw(ctx, &self.game_settings_state.assets)?;
-        self.gameplay_state
-            .ball
-            .draw(ctx, &self.game_settings_state.assets)?;
-
-        if debug::is_active() {
-            debug::draw_rect_outline(
-                self.gameplay_state
-                    .skateboard
-                    .bounding_rect(&self.game_settings_state.assets),
-                ctx,
-            )
-            .unwrap();
+    // Closing the window in the middle of a level asks first. Returns true if
+    // the game may close, closing it again while asking confirms. The demo and
+    // a level tested from the editor are not saved, so they close right away.
+    pub fn request_quit(&mut self) -> bool {
+        let playing = matches!(
+            self.game_workflow_state(),
+            GameWorkflowState::Play | GameWorkflowState::Pause
+        );
+        if playing && self.demo.is_none() && !self.level_editor.testing {
+            return !self.transition(GameWorkflowIntent::RequestQuit);
+        }
+        true
+    }
 
-            for brick in &mut self.gameplay_state.bricks_wall.bricks {
-                debug::draw_rect_outline(
-                    brick.bounding_rect(&self.game_settings_state.assets),
-                    ctx,
-                )
-                .unwrap();
+    // The progress is saved when the game is closed as well, with the level
+    // which is being played. The score of the demo and of a tested level is
+    // not the one of the player.
+    pub fn on_quit(&mut self) {
+        self.stop_demo();
+        if self.level_editor.testing {
+            self.stop_level_test();
+        }
+        if let GameWorkflowState::Play
+        | GameWorkflowState::Pause
+        | GameWorkflowState::Settings { from_pause: true }
+        | GameWorkflowState::ConfirmQuit = self.game_workflow_state()
+        {
+            let saved_run = SavedRun::capture(
+                &self.game_settings_state.score_details,
+                &self.gameplay_state,
+            );
+            if let Err(e) = saved_run.save(&self.save_dir.run_file()) {
+                println!("Run saving error: {:?} ", e);
             }
         }
-        Ok(())
+        self.save_progress();
     }
 
-    pub fn reset(&mut self, level: i32) {
-        if self.game_settings_state.score_details.score.score_result
-            > self
-                .game_settings_state
-                .score_details
-                .score
-                .max_score_result
-        {
-            self.game_settings_state
-                .score_details
-                .score
-                .max_score_result = self.game_settings_state.score_details.score.score_result;
+    pub fn save_progress(&mut self) {
+        let score_details = &mut self.game_settings_state.score_details;
+        if let Err(e) = score_details.save_progress(&self.save_dir.score_file()) {
+            println!("Progress saving error: {:?} ", e);
         }
+        self.save_statistics();
+    }
 
-        self.game_settings_state.score_details.score.score_result = 0;
-        self.game_settings_state.score_details.score.level = level;
+    fn save_statistics(&self) {
+        if let Err(e) = self.statistics.save(&self.save_dir.stats_file()) {
+            println!("Statistics saving error: {:?} ", e);
+        }
+    }
+
+    pub fn key_up_event(&mut self, keycode: event::KeyCode) {
+        let key_bindings = self.game_settings_state.settings.key_bindings;
+        if let GameWorkflowState::Play = self.game_workflow_state() {
+            if keycode == key_bindings.left || keycode == key_bindings.right {
+                self.game_settings_state.input.movement = 0.0;
+            }
+        }
+    }
+
+    // Records the frame into a draw list, which ggez executes afterwards.
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        // The positions are interpolated between the last two updates, so the
+        // movement is smooth on the displays faster than the updates.
+        let alpha = self.timestep.alpha();
+        let grid = self.brick_grid();
+        let EventHandlerWrapper {
+            game_settings_state,
+            gameplay_state,
+            dialogs_handler,
+            level_editor,
+            demo,
+            statistics,
+            toasts,
+            ..
+        } = self;
+        let GameSettingsState {
+            assets,
+            text_cache,
+            screen_size,
+            score_details,
+            ..
+        } = game_settings_state;
+        let palette = assets.palette;
+        let sizes = SpriteSizes::from_assets(assets);
+        let mut renderer = GgezRenderer::new(ctx, assets, text_cache);
+
+        renderer::render(&mut renderer, |draw_list| {
+            draw_list.sprite(
+                SpriteKind::Background,
+                Point2 { x: 0.0, y: 0.0 },
+                graphics::WHITE,
+            )?;
+
+            let dialog_type = match score_details.game_workflow_state {
+                GameWorkflowState::MainMenu => DialogType::MainMenuDialog,
+                GameWorkflowState::NextLevel => DialogType::NextLevelDialog,
+                GameWorkflowState::GameOver => DialogType::GameOverDialog,
+                GameWorkflowState::HighScores => DialogType::HighScoresDialog,
+                GameWorkflowState::Statistics => DialogType::StatisticsDialog,
+                GameWorkflowState::Pause => DialogType::PauseDialog,
+                GameWorkflowState::ConfirmQuit => DialogType::QuitDialog,
+                GameWorkflowState::Settings { .. } => DialogType::SettingsDialog,
+                GameWorkflowState::LevelSelect => DialogType::LevelSelectDialog,
+                GameWorkflowState::Play => {
+                    score_details.draw(draw_list, screen_size.screen_width)?;
+                    gameplay_state.draw(draw_list, &palette, &sizes, alpha)?;
+
+                    if demo.is_some() {
+                        let label = "Demo - press any key";
+                        let (width, _) = draw_list.text_size("demo_hint", label);
+                        let x = (screen_size.screen_width - width) / 2.0;
+                        let y = screen_size.screen_height / 2.0;
+                        draw_list.text("demo_hint", label, Point2 { x, y })?;
+                    }
+                    return toasts.draw(draw_list, screen_size.screen_width, &palette);
+                }
+                GameWorkflowState::Editor => {
+                    gameplay_state.bricks_wall.draw(draw_list, &palette, 1.0)?;
+                    return level_editor.draw(
+                        draw_list,
+                        &grid,
+                        screen_size.screen_height,
+                        &palette,
+                    );
+                }
+            };
+            dialogs_handler.draw(
+                draw_list,
+                dialog_type,
+                score_details.score,
+                &score_details.progress,
+                statistics,
+                &palette,
+            )?;
+            toasts.draw(draw_list, screen_size.screen_width, &palette)
+        })
+    }
+
+    pub fn reset(&mut self, level: i32) {
+        self.game_settings_state.score_details.start_level(level);
+        self.reset_gameplay();
+    }
 
-        self.gameplay_state.bricks_wall.reset_on_game_over();
+    // Builds the wall, the ball and the skateboard of the started level.
+    fn reset_gameplay(&mut self) {
+        self.load_level_wall(self.game_settings_state.score_details.score.level);
 
         let screen_width = self.conf.window_mode.width;
         let screen_height = self.conf.window_mode.height;
@@ -362,13 +1203,15 @@ impl EventHandlerWrapper {
         };
         self.gameplay_state.skateboard =
             Skateboard::new(skateboard_size, screen_height, screen_width);
+        self.gameplay_state.watchdog = BallWatchdog::default();
+        self.apply_difficulty();
     }
 }
 
-pub fn write_score_to_file(level: i32, max_score: usize) {
-    let s = SCORE_FILE_NAME;
-    let mut file = File::create(s).expect("Unable to read file");
-    file.set_len(0).expect("Unable to reset the file size");
-    write!(file, "{}\n{}", level, max_score)
-        .expect("Unable to write the new level and score to the file");
+pub fn new_gameplay_state(screen_width: f32, screen_height: f32, assets: &Assets) -> GameplayState {
+    GameplayState::new(
+        screen_width,
+        screen_height,
+        &SpriteSizes::from_assets(assets),
+    )
 }
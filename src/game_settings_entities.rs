@@ -1,12 +1,21 @@
-use crate::assets::{Assets, Sprite, TextSprite};
-use crate::game_workflow_state_reducer::GameWorkflowState;
-use ggez::mint::Point2;
-use ggez::{Context, GameResult};
+#[cfg(feature = "ggez")]
+use crate::assets::{Assets, TextCache};
+use crate::game_workflow_state_reducer::{Effect, GameWorkflowState};
+use crate::progress::{self, Progress};
+use crate::renderer::Renderer;
+#[cfg(feature = "ggez")]
+use crate::settings::Settings;
+#[cfg(feature = "ggez")]
+use crate::theme::ThemeSelector;
+use crate::types::{GameResult, Point2};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::clone::Clone;
 
 #[derive(Debug, Default)]
 pub struct InputState {
     pub movement: f32,
+    pub pointer_x: Option<f32>,
 }
 
 #[derive(Debug, Default)]
@@ -15,9 +24,14 @@ pub struct ScreenSize {
     pub screen_height: f32,
 }
 
+// The state of the window front end.
+#[cfg(feature = "ggez")]
 #[derive(Debug)]
 pub struct GameSettingsState {
     pub assets: Assets,
+    pub text_cache: TextCache,
+    pub theme_selector: ThemeSelector,
+    pub settings: Settings,
     pub input: InputState,
     pub screen_size: ScreenSize,
     pub score_details: ScoreDetails,
@@ -27,51 +41,127 @@ pub struct GameSettingsState {
 pub struct ScoreDetails {
     pub game_workflow_state: GameWorkflowState,
     pub score: Score,
+    pub progress: Progress,
+    // The time spent on the current level, it decides the stars of a clear.
+    pub level_seconds: f32,
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Score {
     pub score_result: usize,
     pub level: i32,
     pub max_score_result: usize,
+    // The seed of the endless run, the levels of which are generated.
+    pub endless_seed: Option<u64>,
 }
 
 impl ScoreDetails {
-    pub fn new(level: i32, max_score: usize) -> Self {
+    pub fn new(progress: Progress) -> Self {
         let score = Score {
             score_result: 0,
-            level: level,
-            max_score_result: max_score,
+            level: progress.level,
+            max_score_result: progress.max_score,
+            endless_seed: None,
         };
 
         ScoreDetails {
-            game_workflow_state: GameWorkflowState::NextLevel,
+            game_workflow_state: GameWorkflowState::MainMenu,
             score: score,
+            progress,
+            level_seconds: 0.0,
         }
     }
 
-    pub fn draw(&mut self, ctx: &mut Context, screen_width: f32) -> GameResult<()> {
+    // Moves to the level with a new score, the max score keeps the best one.
+    pub fn start_level(&mut self, level: i32) {
+        if self.score.score_result > self.score.max_score_result {
+            self.score.max_score_result = self.score.score_result;
+        }
+        self.score.score_result = 0;
+        self.score.level = level;
+        self.level_seconds = 0.0;
+    }
+
+    // Records the lost level in the progress. The levels of an endless run are
+    // not recorded.
+    pub fn record_game_over(&mut self) {
+        if self.score.endless_seed.is_none() {
+            self.progress
+                .record_attempt(self.score.level, self.score.score_result);
+        }
+    }
+
+    // Records the cleared level with the stars for its time, like above.
+    pub fn record_clear(&mut self, breakable_bricks: usize) {
+        if self.score.endless_seed.is_none() {
+            let stars = progress::stars_for_clear(self.level_seconds, breakable_bricks);
+            self.progress
+                .record_clear(self.score.level, self.score.score_result, stars);
+        }
+    }
+
+    // The max score together with the score of the running level, which is
+    // folded into the max score only when the next level starts.
+    pub fn best_score(&self) -> usize {
+        self.score.max_score_result.max(self.score.score_result)
+    }
+
+    // Writes the reached level and the best score together with the records
+    // of the levels into the score file. It is called after a game over, after
+    // a cleared level and when the game is closed. The levels of an endless run
+    // don't change the reached level.
+    pub fn save_progress(&mut self, file_name: &str) -> GameResult<()> {
+        if self.score.endless_seed.is_none() {
+            self.progress.level = self.progress.level.max(self.score.level);
+        }
+        self.progress.max_score = self.progress.max_score.max(self.best_score());
+        self.progress.save(file_name)
+    }
+
+    // Runs the part of an effect which belongs to the score, the same for
+    // every front end. The reset effects only start the level, the front end
+    // builds the wall of the started level after.
+    pub fn run_effect(
+        &mut self,
+        effect: Effect,
+        breakable_bricks: usize,
+        file_name: &str,
+    ) -> GameResult<()> {
+        let level = self.score.level;
+        match effect {
+            Effect::RecordGameOver => self.record_game_over(),
+            Effect::RecordClear => self.record_clear(breakable_bricks),
+            Effect::SaveProgress => return self.save_progress(file_name),
+            Effect::ResetLevel => self.start_level(level),
+            Effect::ResetFirstLevel => self.start_level(1),
+            Effect::ResetNextLevel => self.start_level(level + 1),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn draw(&self, renderer: &mut dyn Renderer, screen_width: f32) -> GameResult<()> {
         let score_text = format!("Score: {}", self.score.score_result);
         let level_text = format!("Level: {}", self.score.level);
         let max_score_text = format!("Max score: {}", self.score.max_score_result);
 
-        let mut score_sprite = Box::new(TextSprite::new(&score_text, ctx)?);
-        let mut level_sprite = Box::new(TextSprite::new(&level_text, ctx)?);
-        let mut max_score_sprite = Box::new(TextSprite::new(&max_score_text, ctx)?);
-
         let score_pos = Point2 { x: 0.0, y: 0.0 };
+        renderer.text("hud_score", &score_text, score_pos)?;
+
+        let (level_width, _) = renderer.text_size("hud_level", &level_text);
         let level_pos = Point2 {
-            x: (screen_width / 2.0) - level_sprite.width(ctx) / 2.0,
+            x: (screen_width / 2.0) - level_width / 2.0,
             y: 0.0,
         };
+        renderer.text("hud_level", &level_text, level_pos)?;
+
+        let (max_score_width, _) = renderer.text_size("hud_max_score", &max_score_text);
         let max_score_pos = Point2 {
-            x: screen_width - max_score_sprite.width(ctx),
+            x: screen_width - max_score_width,
             y: 0.0,
         };
-
-        score_sprite.draw(score_pos, ctx)?;
-        level_sprite.draw(level_pos, ctx)?;
-        max_score_sprite.draw(max_score_pos, ctx)?;
+        renderer.text("hud_max_score", &max_score_text, max_score_pos)?;
 
         Ok(())
     }
@@ -1,20 +1,143 @@
-use ggez::{GameError, GameResult};
+use crate::types::{GameError, GameResult};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::clone::Clone;
 use std::fmt;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GameWorkflowState {
+    MainMenu,
     NextLevel,
     Play,
     GameOver,
+    HighScores,
+    Pause,
+    Settings { from_pause: bool },
+    LevelSelect,
+    Editor,
+    // Asks before closing the game in the middle of a level.
+    ConfirmQuit,
+    Statistics,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GameWorkflowIntent {
     StartGame,
     Lose,
     Win,
     GoToHomePage,
+    OpenMainMenu,
+    NewGame,
+    ContinueGame,
+    OpenHighScores,
+    Pause,
+    Resume,
+    OpenSettings,
+    CloseSettings,
+    OpenLevelSelect,
+    SelectLevel,
+    OpenEditor,
+    TestLevel,
+    StopTest,
+    StartDemo,
+    StopDemo,
+    RequestQuit,
+    CancelQuit,
+    ResumeRun,
+    OpenStatistics,
+}
+
+impl GameWorkflowState {
+    pub const ALL: [GameWorkflowState; 12] = [
+        GameWorkflowState::MainMenu,
+        GameWorkflowState::NextLevel,
+        GameWorkflowState::Play,
+        GameWorkflowState::GameOver,
+        GameWorkflowState::HighScores,
+        GameWorkflowState::Pause,
+        GameWorkflowState::Settings { from_pause: false },
+        GameWorkflowState::Settings { from_pause: true },
+        GameWorkflowState::LevelSelect,
+        GameWorkflowState::Editor,
+        GameWorkflowState::ConfirmQuit,
+        GameWorkflowState::Statistics,
+    ];
+}
+
+impl GameWorkflowIntent {
+    pub const ALL: [GameWorkflowIntent; 23] = [
+        GameWorkflowIntent::StartGame,
+        GameWorkflowIntent::Lose,
+        GameWorkflowIntent::Win,
+        GameWorkflowIntent::GoToHomePage,
+        GameWorkflowIntent::OpenMainMenu,
+        GameWorkflowIntent::NewGame,
+        GameWorkflowIntent::ContinueGame,
+        GameWorkflowIntent::OpenHighScores,
+        GameWorkflowIntent::Pause,
+        GameWorkflowIntent::Resume,
+        GameWorkflowIntent::OpenSettings,
+        GameWorkflowIntent::CloseSettings,
+        GameWorkflowIntent::OpenLevelSelect,
+        GameWorkflowIntent::SelectLevel,
+        GameWorkflowIntent::OpenEditor,
+        GameWorkflowIntent::TestLevel,
+        GameWorkflowIntent::StopTest,
+        GameWorkflowIntent::StartDemo,
+        GameWorkflowIntent::StopDemo,
+        GameWorkflowIntent::RequestQuit,
+        GameWorkflowIntent::CancelQuit,
+        GameWorkflowIntent::ResumeRun,
+        GameWorkflowIntent::OpenStatistics,
+    ];
+}
+
+// What has to happen in the game besides the change of the state. The front
+// end runs the effects of a transition in their order after the state changed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Effect {
+    // Records the lost level in the progress.
+    RecordGameOver,
+    // Records the cleared level with its stars in the progress.
+    RecordClear,
+    // Writes the reached level and the best score into the score file, and the
+    // statistics into theirs.
+    SaveProgress,
+    // Starts the current level again with a new score.
+    ResetLevel,
+    ResetFirstLevel,
+    ResetNextLevel,
+    StopSkateboard,
+    // Goes back to the reached level after an endless run.
+    EndEndlessRun,
+    RefreshMainMenu,
+    RefreshSettingsMenu,
+    RefreshLevelSelect,
+    // Continues the run which was saved when the game was closed.
+    RestoreRun,
+    // Another run starts, so the saved one is not offered anymore.
+    DiscardSavedRun,
+    // Counts the started game in the statistics of the player.
+    RecordGameStart,
+}
+
+// The conditions of the game which allow a transition besides the state.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Guards {
+    // There is a reached level after the first one.
+    pub can_continue: bool,
+    // A level tested from the editor only goes back to the editor.
+    pub testing_level: bool,
+    // There is a run which was saved when the game was closed.
+    pub saved_run: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    pub state: GameWorkflowState,
+    pub effects: Vec<Effect>,
 }
 
 impl fmt::Display for GameWorkflowState {
@@ -32,6 +155,107 @@ pub fn reduce(
         GameWorkflowIntent::Lose => reduce_lose_intent(state),
         GameWorkflowIntent::Win => reduce_win_intent(state),
         GameWorkflowIntent::GoToHomePage => reduce_go_to_home_page_intent(state),
+        GameWorkflowIntent::OpenMainMenu => reduce_open_main_menu_intent(state),
+        GameWorkflowIntent::NewGame => reduce_new_game_intent(state),
+        GameWorkflowIntent::ContinueGame => reduce_continue_game_intent(state),
+        GameWorkflowIntent::OpenHighScores => reduce_open_high_scores_intent(state),
+        GameWorkflowIntent::Pause => reduce_pause_intent(state),
+        GameWorkflowIntent::Resume => reduce_resume_intent(state),
+        GameWorkflowIntent::OpenSettings => reduce_open_settings_intent(state),
+        GameWorkflowIntent::CloseSettings => reduce_close_settings_intent(state),
+        GameWorkflowIntent::OpenLevelSelect => reduce_open_level_select_intent(state),
+        GameWorkflowIntent::SelectLevel => reduce_select_level_intent(state),
+        GameWorkflowIntent::OpenEditor => reduce_open_editor_intent(state),
+        GameWorkflowIntent::TestLevel => reduce_test_level_intent(state),
+        GameWorkflowIntent::StopTest => reduce_stop_test_intent(state),
+        GameWorkflowIntent::StartDemo => reduce_start_demo_intent(state),
+        GameWorkflowIntent::StopDemo => reduce_stop_demo_intent(state),
+        GameWorkflowIntent::RequestQuit => reduce_request_quit_intent(state),
+        GameWorkflowIntent::CancelQuit => reduce_cancel_quit_intent(state),
+        GameWorkflowIntent::ResumeRun => reduce_resume_run_intent(state),
+        GameWorkflowIntent::OpenStatistics => reduce_open_statistics_intent(state),
+    }
+}
+
+// Reduces the intent when the guards allow it. The effects are the ones of
+// leaving the old state, of the intent and of entering the new state.
+pub fn transition(
+    state: GameWorkflowState,
+    intent: GameWorkflowIntent,
+    guards: Guards,
+) -> GameResult<Transition> {
+    check_guards(state, intent, guards)?;
+    let new_state = reduce(state, intent)?;
+    let mut effects = on_exit(state);
+    effects.extend(intent_effects(state, intent));
+    effects.extend(on_enter(new_state));
+    Ok(Transition {
+        state: new_state,
+        effects,
+    })
+}
+
+pub fn check_guards(
+    state: GameWorkflowState,
+    intent: GameWorkflowIntent,
+    guards: Guards,
+) -> GameResult<()> {
+    let allowed = match intent {
+        GameWorkflowIntent::ContinueGame => guards.can_continue,
+        GameWorkflowIntent::ResumeRun => guards.saved_run,
+        GameWorkflowIntent::Lose | GameWorkflowIntent::Win | GameWorkflowIntent::OpenMainMenu => {
+            !guards.testing_level
+        }
+        _ => true,
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(GameError::EventLoopError(format!(
+            "The guards don't allow the {:?} intent in {}",
+            intent, state
+        )))
+    }
+}
+
+pub fn on_enter(state: GameWorkflowState) -> Vec<Effect> {
+    match state {
+        GameWorkflowState::MainMenu => vec![Effect::EndEndlessRun, Effect::RefreshMainMenu],
+        GameWorkflowState::Settings { .. } => vec![Effect::RefreshSettingsMenu],
+        GameWorkflowState::LevelSelect => vec![Effect::RefreshLevelSelect],
+        _ => Vec::new(),
+    }
+}
+
+pub fn on_exit(state: GameWorkflowState) -> Vec<Effect> {
+    match state {
+        GameWorkflowState::Play => vec![Effect::StopSkateboard],
+        _ => Vec::new(),
+    }
+}
+
+pub fn intent_effects(state: GameWorkflowState, intent: GameWorkflowIntent) -> Vec<Effect> {
+    match (state, intent) {
+        (_, GameWorkflowIntent::Lose) => vec![Effect::RecordGameOver, Effect::SaveProgress],
+        (_, GameWorkflowIntent::Win) => vec![
+            Effect::RecordClear,
+            Effect::ResetNextLevel,
+            Effect::SaveProgress,
+        ],
+        (_, GameWorkflowIntent::NewGame) => vec![
+            Effect::DiscardSavedRun,
+            Effect::ResetFirstLevel,
+            Effect::RecordGameStart,
+        ],
+        (_, GameWorkflowIntent::ContinueGame) | (_, GameWorkflowIntent::SelectLevel) => {
+            vec![Effect::DiscardSavedRun, Effect::RecordGameStart]
+        }
+        (_, GameWorkflowIntent::ResumeRun) => vec![Effect::RestoreRun],
+        (_, GameWorkflowIntent::GoToHomePage) => vec![Effect::ResetLevel, Effect::RecordGameStart],
+        (GameWorkflowState::GameOver, GameWorkflowIntent::OpenMainMenu)
+        | (GameWorkflowState::Pause, GameWorkflowIntent::OpenMainMenu)
+        | (GameWorkflowState::Editor, GameWorkflowIntent::OpenMainMenu) => vec![Effect::ResetLevel],
+        _ => Vec::new(),
     }
 }
 
@@ -82,3 +306,203 @@ pub fn reduce_go_to_home_page_intent(state: GameWorkflowState) -> GameResult<Gam
         }
     }
 }
+
+pub fn reduce_open_main_menu_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::NextLevel
+        | GameWorkflowState::GameOver
+        | GameWorkflowState::HighScores
+        | GameWorkflowState::Statistics
+        | GameWorkflowState::Pause
+        | GameWorkflowState::LevelSelect
+        | GameWorkflowState::Editor => Ok(GameWorkflowState::MainMenu),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with OpenMainMenu intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_new_game_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::MainMenu => Ok(GameWorkflowState::NextLevel),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with NewGame intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_continue_game_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::MainMenu => Ok(GameWorkflowState::NextLevel),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with ContinueGame intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_open_high_scores_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::MainMenu => Ok(GameWorkflowState::HighScores),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with OpenHighScores intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_open_statistics_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::MainMenu => Ok(GameWorkflowState::Statistics),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with OpenStatistics intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_pause_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::Play => Ok(GameWorkflowState::Pause),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with Pause intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_resume_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::Pause => Ok(GameWorkflowState::Play),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with Resume intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_open_settings_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::MainMenu => Ok(GameWorkflowState::Settings { from_pause: false }),
+        GameWorkflowState::Pause => Ok(GameWorkflowState::Settings { from_pause: true }),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with OpenSettings intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_close_settings_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::Settings { from_pause: false } => Ok(GameWorkflowState::MainMenu),
+        GameWorkflowState::Settings { from_pause: true } => Ok(GameWorkflowState::Pause),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with CloseSettings intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_open_level_select_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::MainMenu => Ok(GameWorkflowState::LevelSelect),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with OpenLevelSelect intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_select_level_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::LevelSelect => Ok(GameWorkflowState::NextLevel),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with SelectLevel intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_open_editor_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::MainMenu => Ok(GameWorkflowState::Editor),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with OpenEditor intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_test_level_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::Editor => Ok(GameWorkflowState::Play),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with TestLevel intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_stop_test_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::Play | GameWorkflowState::Pause => Ok(GameWorkflowState::Editor),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with StopTest intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_start_demo_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::MainMenu => Ok(GameWorkflowState::Play),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with StartDemo intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_stop_demo_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::Play => Ok(GameWorkflowState::MainMenu),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with StopDemo intent",
+            state
+        ))),
+    }
+}
+
+pub fn reduce_request_quit_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::Play | GameWorkflowState::Pause => Ok(GameWorkflowState::ConfirmQuit),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with RequestQuit intent",
+            state
+        ))),
+    }
+}
+
+// The game stays paused after the player changed their mind.
+pub fn reduce_cancel_quit_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::ConfirmQuit => Ok(GameWorkflowState::Pause),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with CancelQuit intent",
+            state
+        ))),
+    }
+}
+
+// The saved run starts paused, so the player has time to get ready.
+pub fn reduce_resume_run_intent(state: GameWorkflowState) -> GameResult<GameWorkflowState> {
+    match state {
+        GameWorkflowState::MainMenu => Ok(GameWorkflowState::Pause),
+        _ => Err(GameError::EventLoopError(format!(
+            "Not allowed transition from {} with ResumeRun intent",
+            state
+        ))),
+    }
+}
@@ -1,14 +1,184 @@
+#[cfg(feature = "ggez")]
 use ggez::graphics;
-use ggez::mint::{Point2, Vector2};
-use ggez::{Context, GameError, GameResult};
 
+#[cfg(feature = "ggez")]
 use crate::assets::Assets;
-use crate::math::{circle_collides_rect, Collision};
+use crate::debug;
+use crate::levels::LevelLayout;
+use crate::math::{
+    circle_collides_rect, circle_contact_rect, lerp_point, merge_contacts, Collision, Contact,
+};
+use crate::renderer::{Renderer, SpriteKind};
+use crate::theme::Palette;
+use crate::types::{GameError, GameResult, Point2, Rect, Vector2, WHITE};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GameplayState {
     pub skateboard: Skateboard,
     pub bricks_wall: BricksWall,
     pub ball: Ball,
+    pub watchdog: BallWatchdog,
+}
+
+// What happened in a single update of the game.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct StepEvents {
+    // The ball bounced off the skateboard or a brick.
+    pub bounced: bool,
+    pub board_bounced: bool,
+    pub ball_lost: bool,
+    pub bricks_broken: BrokenBricks,
+}
+
+// The sizes of the sprites which the collisions depend on. In the game they
+// come from the assets, a headless game gives them directly.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpriteSizes {
+    pub ball: (f32, f32),
+    pub skateboard_normal: (f32, f32),
+    pub skateboard_rebound: (f32, f32),
+    pub brick_survived: (f32, f32),
+    pub brick_touched: (f32, f32),
+}
+
+impl SpriteSizes {
+    // The sizes used when the game runs without the assets.
+    pub const HEADLESS: SpriteSizes = SpriteSizes {
+        ball: (16.0, 16.0),
+        skateboard_normal: (120.0, 20.0),
+        skateboard_rebound: (120.0, 20.0),
+        brick_survived: (60.0, 20.0),
+        brick_touched: (60.0, 20.0),
+    };
+
+    #[cfg(feature = "ggez")]
+    pub fn from_assets(assets: &Assets) -> Self {
+        let size = |image: &graphics::Image| (image.width() as f32, image.height() as f32);
+        SpriteSizes {
+            ball: size(&assets.ball_flying),
+            skateboard_normal: size(&assets.skateboard_normal),
+            skateboard_rebound: size(&assets.skateboard_rebound),
+            brick_survived: size(&assets.brick_survived),
+            brick_touched: size(&assets.brick_touched),
+        }
+    }
+}
+
+impl GameplayState {
+    pub fn new(screen_width: f32, screen_height: f32, sizes: &SpriteSizes) -> Self {
+        let (skateboard_width, skateboard_height) = sizes.skateboard_normal;
+        let (brick_width, brick_height) = sizes.brick_survived;
+        let (ball_width, ball_height) = sizes.ball;
+        let skateboard_size = Size {
+            height: skateboard_height,
+            width: skateboard_width,
+        };
+
+        GameplayState {
+            skateboard: Skateboard::new(skateboard_size, screen_height, screen_width),
+            bricks_wall: BricksWall::new(screen_width, screen_height, brick_height, brick_width),
+            ball: Ball::new(
+                screen_width,
+                screen_height,
+                skateboard_height,
+                ball_width,
+                ball_height,
+            ),
+            watchdog: BallWatchdog::default(),
+        }
+    }
+
+    // Moves everything by a single update and resolves the collisions. The
+    // movement of the skateboard is from -1 (full speed left) to 1. The ball
+    // touching the floor is lost, unless the floor bounces it back.
+    pub fn step(
+        &mut self,
+        seconds: f32,
+        movement: f32,
+        screen_size: (f32, f32),
+        sizes: &SpriteSizes,
+        floor_bounces: bool,
+    ) -> GameResult<StepEvents> {
+        let (screen_width, screen_height) = screen_size;
+        let (ball_width, ball_height) = sizes.ball;
+        self.remember_positions();
+
+        self.skateboard
+            .update(seconds, movement, screen_width, sizes);
+        self.bricks_wall.update(seconds);
+
+        let new_ball_pos = self.ball.calculate_new_position(
+            seconds,
+            screen_width,
+            screen_height,
+            ball_width,
+            ball_height,
+        );
+        if new_ball_pos.y + ball_height > screen_height && !floor_bounces {
+            return Ok(StepEvents {
+                ball_lost: true,
+                ..StepEvents::default()
+            });
+        }
+        self.ball.update(Point2 {
+            x: new_ball_pos.x,
+            y: new_ball_pos.y.min(screen_height - ball_height),
+        });
+
+        let board_bounced = self.ball.bounce_off_board(self.skateboard.rect(sizes));
+        let hits = self
+            .bricks_wall
+            .collide_ball(&mut self.ball, seconds, |brick| brick.rect(sizes))?;
+        self.watchdog
+            .update(seconds, hits.breakable_bricks > 0, &mut self.ball);
+        self.ball.enforce_min_vertical_speed();
+
+        // Pushing the ball out of the board or a brick may move it past a wall.
+        self.ball.pos.x =
+            nalgebra::clamp(self.ball.pos.x, 0.0, (screen_width - ball_width).max(0.0));
+        self.ball.pos.y = self.ball.pos.y.max(0.0);
+
+        Ok(StepEvents {
+            bounced: board_bounced || hits.bricks > 0,
+            board_bounced,
+            ball_lost: false,
+            bricks_broken: hits.broken,
+        })
+    }
+
+    // Has to be called before every update of the game, the drawing
+    // interpolates between these positions and the updated ones.
+    pub fn remember_positions(&mut self) {
+        self.ball.previous_pos = self.ball.pos;
+        self.skateboard.previous_pos = self.skateboard.pos;
+        for brick in &mut self.bricks_wall.bricks {
+            brick.previous_pos = brick.pos;
+        }
+    }
+
+    // Draws the entities, with their bounding boxes in the debug mode.
+    pub fn draw(
+        &self,
+        renderer: &mut dyn Renderer,
+        palette: &Palette,
+        sizes: &SpriteSizes,
+        alpha: f32,
+    ) -> GameResult<()> {
+        self.skateboard.draw(renderer, alpha)?;
+        self.bricks_wall.draw(renderer, palette, alpha)?;
+        self.ball.draw(renderer, alpha)?;
+
+        if debug::is_active() {
+            debug::draw_rect_outline(self.skateboard.rect(sizes), palette.debug_outline, renderer)?;
+            for brick in &self.bricks_wall.bricks {
+                debug::draw_rect_outline(brick.rect(sizes), palette.debug_outline, renderer)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -17,15 +187,23 @@ pub struct Size {
     pub width: f32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Ball {
     pub state: BallState,
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::serde_point2"))]
     pub pos: Point2<f32>,
+    // The position before the last update, the drawing interpolates from it.
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::serde_point2"))]
+    pub previous_pos: Point2<f32>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::serde_point2"))]
     pub direction: Point2<f32>,
     pub radius: f32,
+    pub speed: f32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BallState {
     Flying,
     Crashing,
@@ -34,6 +212,11 @@ pub enum BallState {
 impl Ball {
     pub const SPEED: f32 = 350.0;
     pub const INITIAL_OFFSET: f32 = 10.0;
+    pub const PUSH_OUT_PASSES: usize = 4;
+    // The gap left between the ball and a surface it was pushed out of.
+    pub const SEPARATION: f32 = 0.01;
+    // The smallest part of the speed of the ball which goes up or down.
+    pub const MIN_VERTICAL_RATIO: f32 = 0.25;
 
     pub fn new(
         screen_width: f32,
@@ -50,8 +233,10 @@ impl Ball {
         return Ball {
             state: BallState::Flying,
             pos: ball_pos,
+            previous_pos: ball_pos,
             direction: Point2 { x: 1.0, y: 1.0 },
             radius: ball_width / 2.0,
+            speed: Self::SPEED,
         };
     }
 
@@ -60,6 +245,9 @@ impl Ball {
         self.pos.y = point2.y;
     }
 
+    // The position of the ball after the step. The ball is reflected off the
+    // side walls and the ceiling and moved back inside the screen. Crossing the
+    // floor only turns the ball up, the caller decides if it ends the game.
     pub fn calculate_new_position(
         &mut self,
         seconds: f32,
@@ -68,17 +256,119 @@ impl Ball {
         ball_width: f32,
         ball_height: f32,
     ) -> Point2<f32> {
-        let new_x = self.pos.x + Self::SPEED * seconds * self.direction.x;
-        let new_y = self.pos.y + Self::SPEED * seconds * self.direction.y;
+        let mut new_x = self.pos.x + self.speed * seconds * self.direction.x;
+        let mut new_y = self.pos.y + self.speed * seconds * self.direction.y;
+        let max_x = (screen_width - ball_width).max(0.0);
+        let max_y = (screen_height - ball_height).max(0.0);
+
+        if new_x < 0.0 {
+            new_x = -new_x;
+            self.direction.x = self.direction.x.abs();
+        } else if new_x > max_x {
+            new_x = 2.0 * max_x - new_x;
+            self.direction.x = -self.direction.x.abs();
+        }
+
+        if new_y < 0.0 {
+            new_y = -new_y;
+            self.direction.y = self.direction.y.abs();
+        } else if new_y > max_y {
+            self.direction.y = -self.direction.y.abs();
+        }
+        Point2 {
+            x: nalgebra::clamp(new_x, 0.0, max_x),
+            y: new_y.max(0.0),
+        }
+    }
+
+    // Keeps the angle of the ball at least MIN_VERTICAL_RATIO from the
+    // horizontal, so it can't bounce between the side walls forever. The
+    // speed of the ball doesn't change.
+    pub fn enforce_min_vertical_speed(&mut self) {
+        let length =
+            (self.direction.x * self.direction.x + self.direction.y * self.direction.y).sqrt();
+        let min_y = Self::MIN_VERTICAL_RATIO * length;
+        if self.direction.y.abs() >= min_y {
+            return;
+        }
+        self.direction.y = min_y.copysign(self.direction.y);
+        self.direction.x = (length * length - min_y * min_y)
+            .sqrt()
+            .copysign(self.direction.x);
+    }
 
-        if new_x + ball_width > screen_width || new_x < 0.0 {
-            self.direction.x = -self.direction.x;
+    // Bounces the ball off the skateboard and moves it out of the board. A ball
+    // with its center inside the board, for example after spawning there,
+    // always leaves through the top instead of flipping inside it every step.
+    pub fn bounce_off_board(&mut self, board: Rect) -> bool {
+        let mut contact = match self.contact_rect(board) {
+            Some(contact) => contact,
+            None => return false,
+        };
+        let center = Point2 {
+            x: self.pos.x + self.radius,
+            y: self.pos.y + self.radius,
+        };
+        if board.contains(center) {
+            contact = Contact {
+                normal: Vector2 { x: 0.0, y: -1.0 },
+                depth: center.y - board.y + self.radius,
+            };
         }
 
-        if new_y + ball_height > screen_height || new_y < 0.0 {
-            self.direction.y = -self.direction.y;
+        self.reflect(contact.normal, Vector2 { x: 0.0, y: 0.0 });
+        self.pos.x += contact.normal.x * (contact.depth + Self::SEPARATION);
+        self.pos.y += contact.normal.y * (contact.depth + Self::SEPARATION);
+        true
+    }
+
+    // Reflects the velocity of the ball relative to the moving surface, so a
+    // brick moving towards the ball sends it back at a sharper angle. The speed
+    // of the ball doesn't change.
+    pub fn bounce_off_moving(&mut self, collision: Collision, surface_velocity: Vector2<f32>) {
+        if surface_velocity.x == 0.0 && surface_velocity.y == 0.0 {
+            self.bounce(collision);
+            return;
+        }
+
+        let normal = match collision {
+            Collision::Left => Vector2 { x: -1.0, y: 0.0 },
+            Collision::Right => Vector2 { x: 1.0, y: 0.0 },
+            Collision::Top => Vector2 { x: 0.0, y: -1.0 },
+            Collision::Bottom => Vector2 { x: 0.0, y: 1.0 },
+            Collision::None => return,
+        };
+        self.reflect(normal, surface_velocity);
+    }
+
+    // Reflects the velocity of the ball relative to the surface with the given
+    // unit normal. Nothing changes if the ball is already moving away from it.
+    pub fn reflect(&mut self, normal: Vector2<f32>, surface_velocity: Vector2<f32>) {
+        let mut relative = Vector2 {
+            x: self.direction.x * self.speed - surface_velocity.x,
+            y: self.direction.y * self.speed - surface_velocity.y,
+        };
+        let approach = relative.x * normal.x + relative.y * normal.y;
+        if approach >= 0.0 {
+            // Already moving away from the surface.
+            return;
+        }
+        relative.x -= 2.0 * approach * normal.x;
+        relative.y -= 2.0 * approach * normal.y;
+
+        let velocity = Vector2 {
+            x: relative.x + surface_velocity.x,
+            y: relative.y + surface_velocity.y,
+        };
+        let velocity_length = (velocity.x * velocity.x + velocity.y * velocity.y).sqrt();
+        let direction_length =
+            (self.direction.x * self.direction.x + self.direction.y * self.direction.y).sqrt();
+        if velocity_length > 0.0 {
+            self.direction = Point2 {
+                x: velocity.x / velocity_length * direction_length,
+                y: velocity.y / velocity_length * direction_length,
+            };
         }
-        Point2 { x: new_x, y: new_y }
     }
 
     pub fn bounce(&mut self, collision: Collision) {
@@ -93,38 +383,61 @@ impl Ball {
         }
     }
 
-    pub fn draw(&self, ctx: &mut Context, assets: &Assets) -> GameResult<()> {
+    pub fn draw(&self, renderer: &mut dyn Renderer, alpha: f32) -> GameResult<()> {
+        let dest = lerp_point(self.previous_pos, self.pos, alpha);
         match self.state {
-            BallState::Flying => {
-                graphics::draw(
-                    ctx,
-                    &assets.ball_flying,
-                    graphics::DrawParam {
-                        dest: self.pos,
-                        scale: Vector2 { x: 1.0, y: 1.0 },
-                        offset: Point2 { x: 0.0, y: 0.0 },
-                        ..Default::default()
-                    },
-                )?;
-            }
+            BallState::Flying => renderer.sprite(SpriteKind::Ball, dest, WHITE),
+            BallState::Crashing => renderer.sprite(SpriteKind::Ball, dest, WHITE),
+        }
+    }
 
-            BallState::Crashing => {
-                graphics::draw(
-                    ctx,
-                    &assets.ball_flying,
-                    graphics::DrawParam {
-                        dest: self.pos,
-                        scale: Vector2 { x: 1.0, y: 1.0 },
-                        offset: Point2 { x: 0.0, y: 0.0 },
-                        ..Default::default()
-                    },
-                )?;
+    // The bounds of the ball over the last step, from the position before the
+    // step to the current one.
+    pub fn swept_bounds(&self, seconds: f32) -> Rect {
+        let diameter = 2.0 * self.radius;
+        let previous_x = self.pos.x - self.speed * seconds * self.direction.x;
+        let previous_y = self.pos.y - self.speed * seconds * self.direction.y;
+        let left = self.pos.x.min(previous_x);
+        let top = self.pos.y.min(previous_y);
+        Rect::new(
+            left,
+            top,
+            self.pos.x.max(previous_x) - left + diameter,
+            self.pos.y.max(previous_y) - top + diameter,
+        )
+    }
+
+    pub fn contact_rect(&self, bounding_box: Rect) -> Option<Contact> {
+        circle_contact_rect(
+            self.pos.x + self.radius,
+            self.pos.y + self.radius,
+            self.radius,
+            bounding_box.x,
+            bounding_box.y,
+            bounding_box.w,
+            bounding_box.h,
+        )
+    }
+
+    // Moves the ball out of the rectangles. Leaving one rectangle may push the
+    // ball into another one, so it takes a few passes.
+    pub fn push_out(&mut self, rects: &[Rect]) {
+        for _ in 0..Self::PUSH_OUT_PASSES {
+            let mut moved = false;
+            for rect in rects {
+                if let Some(contact) = self.contact_rect(*rect) {
+                    self.pos.x += contact.normal.x * (contact.depth + Self::SEPARATION);
+                    self.pos.y += contact.normal.y * (contact.depth + Self::SEPARATION);
+                    moved = true;
+                }
+            }
+            if !moved {
+                break;
             }
         }
-        Ok(())
     }
 
-    pub fn collides_rect(&self, bounding_box: graphics::Rect) -> Collision {
+    pub fn collides_rect(&self, bounding_box: Rect) -> Collision {
         circle_collides_rect(
             self.pos.x + self.radius,
             self.pos.y + self.radius,
@@ -137,9 +450,341 @@ impl Ball {
     }
 }
 
-#[derive(Debug)]
+// The bricks which the ball touched in a step.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct BrickHits {
+    pub bricks: usize,
+    pub breakable_bricks: usize,
+    pub broken: BrokenBricks,
+}
+
+// The broken bricks by their kind, the unbreakable ones are never broken.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct BrokenBricks {
+    pub normal: usize,
+    pub weak: usize,
+}
+
+impl BrokenBricks {
+    pub fn count(&mut self, kind: BrickKind) {
+        match kind {
+            BrickKind::Normal => self.normal += 1,
+            BrickKind::Weak => self.weak += 1,
+            BrickKind::Unbreakable => {}
+        }
+    }
+
+    pub fn add(&mut self, other: BrokenBricks) {
+        self.normal += other.normal;
+        self.weak += other.weak;
+    }
+
+    pub fn total(&self) -> usize {
+        self.normal + self.weak
+    }
+}
+
+// Watches for a ball which keeps flying without touching a breakable brick,
+// for example between the skateboard and the ceiling in an empty column or
+// between unbreakable bricks, and turns it a bit after NUDGE_SECONDS.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BallWatchdog {
+    pub seconds_without_brick: f32,
+    pub nudges: u32,
+}
+
+impl BallWatchdog {
+    pub const NUDGE_SECONDS: f32 = 10.0;
+    pub const NUDGE_ANGLE: f32 = 0.3;
+
+    // Returns true if the ball was nudged.
+    pub fn update(&mut self, seconds: f32, touched_brick: bool, ball: &mut Ball) -> bool {
+        if touched_brick {
+            self.seconds_without_brick = 0.0;
+            return false;
+        }
+        self.seconds_without_brick += seconds;
+        if self.seconds_without_brick < Self::NUDGE_SECONDS {
+            return false;
+        }
+
+        // The side of the turn changes with every nudge, so the ball doesn't
+        // drift into the same corner.
+        let angle = if self.nudges.is_multiple_of(2) {
+            Self::NUDGE_ANGLE
+        } else {
+            -Self::NUDGE_ANGLE
+        };
+        let (sin, cos) = angle.sin_cos();
+        ball.direction = Point2 {
+            x: ball.direction.x * cos - ball.direction.y * sin,
+            y: ball.direction.x * sin + ball.direction.y * cos,
+        };
+        ball.enforce_min_vertical_speed();
+        self.nudges += 1;
+        self.seconds_without_brick = 0.0;
+        true
+    }
+}
+
+// The snapping grid of the bricks. Every cell holds at most one brick, with
+// BRICK_PADDING of free space around it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BrickGrid {
+    pub columns: usize,
+    pub rows: usize,
+    pub left: f32,
+    pub top: f32,
+    pub cell_width: f32,
+    pub cell_height: f32,
+}
+
+impl BrickGrid {
+    pub fn new(screen_width: f32, screen_height: f32, brick_height: f32, brick_width: f32) -> Self {
+        // Horizontal calculations
+        let brick_horizontal_size = brick_width + 2.0 * BricksWall::BRICK_PADDING;
+        let left_horizontal_space = screen_width - 2.0 * BricksWall::PADDING;
+        let bricks_columns_count = (left_horizontal_space / brick_horizontal_size) as usize;
+        let left_horizontal_space_after_bricks =
+            left_horizontal_space - (brick_horizontal_size * (bricks_columns_count as f32));
+        let offset_from_left = left_horizontal_space_after_bricks / 2.0;
+
+        // Vertical calculations
+        let brick_vertical_size = brick_height + 2.0 * BricksWall::BRICK_PADDING;
+        let left_vertical_space = screen_height * BricksWall::BRICKS_PERCENTAGE_OF_SCREEN_HEIGHT;
+        let bricks_rows_count = (left_vertical_space / brick_vertical_size) as usize;
+
+        BrickGrid {
+            columns: bricks_columns_count,
+            rows: bricks_rows_count,
+            left: BricksWall::PADDING + offset_from_left + BricksWall::BRICK_PADDING,
+            top: BricksWall::PADDING + (brick_vertical_size / 2.0),
+            cell_width: brick_horizontal_size,
+            cell_height: brick_vertical_size,
+        }
+    }
+
+    pub fn brick_size(&self) -> (f32, f32) {
+        (
+            self.cell_width - 2.0 * BricksWall::BRICK_PADDING,
+            self.cell_height - 2.0 * BricksWall::BRICK_PADDING,
+        )
+    }
+
+    // The position of the brick in the given cell.
+    pub fn cell_position(&self, column: usize, row: usize) -> Point2<f32> {
+        Point2 {
+            x: self.left + (column as f32) * self.cell_width,
+            y: self.top + (row as f32) * self.cell_height,
+        }
+    }
+
+    // The whole cell with the padding around the brick.
+    pub fn cell_rect(&self, column: usize, row: usize) -> Rect {
+        let pos = self.cell_position(column, row);
+        Rect::new(
+            pos.x - BricksWall::BRICK_PADDING,
+            pos.y - BricksWall::BRICK_PADDING,
+            self.cell_width,
+            self.cell_height,
+        )
+    }
+
+    pub fn cell_at(&self, point: Point2<f32>) -> Option<(usize, usize)> {
+        let x = point.x - (self.left - BricksWall::BRICK_PADDING);
+        let y = point.y - (self.top - BricksWall::BRICK_PADDING);
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+
+        let (column, row) = (
+            (x / self.cell_width) as usize,
+            (y / self.cell_height) as usize,
+        );
+        if column < self.columns && row < self.rows {
+            Some((column, row))
+        } else {
+            None
+        }
+    }
+}
+
+// A uniform grid over the bricks which lists the bricks overlapping each of
+// its cells, so a query tests only the bricks near the given bounds instead
+// of the whole wall. The grid covers the whole paths of the moving bricks,
+// so a moved brick only changes the cells it is listed in.
+#[derive(Debug, Clone, Default)]
+pub struct BrickIndex {
+    left: f32,
+    top: f32,
+    cell_width: f32,
+    cell_height: f32,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+    // The cells every brick is listed in, as first column, first row, last
+    // column and last row.
+    brick_cells: Vec<Option<(usize, usize, usize, usize)>>,
+}
+
+impl BrickIndex {
+    pub fn new(bricks: &[Brick], brick_size: (f32, f32), cell_size: (f32, f32)) -> Self {
+        let cell_width = cell_size.0.max(1.0);
+        let cell_height = cell_size.1.max(1.0);
+        if bricks.is_empty() {
+            return BrickIndex::default();
+        }
+
+        let reaches = bricks
+            .iter()
+            .map(|brick| brick.reach(brick_size))
+            .collect::<Vec<Rect>>();
+        let left = reaches.iter().map(|rect| rect.x).fold(f32::MAX, f32::min);
+        let top = reaches.iter().map(|rect| rect.y).fold(f32::MAX, f32::min);
+        let right = reaches
+            .iter()
+            .map(|rect| rect.right())
+            .fold(f32::MIN, f32::max);
+        let bottom = reaches
+            .iter()
+            .map(|rect| rect.bottom())
+            .fold(f32::MIN, f32::max);
+
+        let mut index = BrickIndex {
+            left,
+            top,
+            cell_width,
+            cell_height,
+            columns: ((right - left) / cell_width).floor() as usize + 1,
+            rows: ((bottom - top) / cell_height).floor() as usize + 1,
+            cells: Vec::new(),
+            brick_cells: vec![None; bricks.len()],
+        };
+        index.cells = vec![Vec::new(); index.columns * index.rows];
+
+        let (brick_width, brick_height) = brick_size;
+        for (brick_index, brick) in bricks.iter().enumerate() {
+            let bounds = Rect::new(brick.pos.x, brick.pos.y, brick_width, brick_height);
+            index.move_brick(brick_index, bounds);
+        }
+        index
+    }
+
+    // Lists the brick in the cells of its new bounds. Nothing changes while
+    // the brick stays in the same cells.
+    pub fn move_brick(&mut self, brick_index: usize, bounds: Rect) {
+        let range = self.cell_range(bounds);
+        let previous_range = match self.brick_cells.get(brick_index) {
+            Some(previous_range) if *previous_range == range => return,
+            Some(previous_range) => *previous_range,
+            None => return,
+        };
+        if let Some((first_column, first_row, last_column, last_row)) = previous_range {
+            for row in first_row..=last_row {
+                for column in first_column..=last_column {
+                    self.cells[row * self.columns + column].retain(|&brick| brick != brick_index);
+                }
+            }
+        }
+        if let Some((first_column, first_row, last_column, last_row)) = range {
+            for row in first_row..=last_row {
+                for column in first_column..=last_column {
+                    self.cells[row * self.columns + column].push(brick_index);
+                }
+            }
+        }
+        self.brick_cells[brick_index] = range;
+    }
+
+    // The cells overlapped by the bounds, clamped to the grid.
+    fn cell_range(&self, bounds: Rect) -> Option<(usize, usize, usize, usize)> {
+        if self.cells.is_empty() {
+            return None;
+        }
+        let column = |x: f32| ((x - self.left) / self.cell_width).floor();
+        let row = |y: f32| ((y - self.top) / self.cell_height).floor();
+        let (first_column, last_column) = (column(bounds.x), column(bounds.x + bounds.w));
+        let (first_row, last_row) = (row(bounds.y), row(bounds.y + bounds.h));
+
+        if last_column < 0.0
+            || last_row < 0.0
+            || first_column >= self.columns as f32
+            || first_row >= self.rows as f32
+        {
+            return None;
+        }
+        Some((
+            first_column.max(0.0) as usize,
+            first_row.max(0.0) as usize,
+            (last_column as usize).min(self.columns - 1),
+            (last_row as usize).min(self.rows - 1),
+        ))
+    }
+
+    // The indices of the bricks which may overlap the bounds, in increasing order.
+    pub fn query(&self, bounds: Rect) -> Vec<usize> {
+        let mut bricks = Vec::new();
+        self.query_into(bounds, &mut bricks);
+        bricks
+    }
+
+    // The same as query, into a buffer which is kept between the queries.
+    pub fn query_into(&self, bounds: Rect, bricks: &mut Vec<usize>) {
+        bricks.clear();
+        if let Some((first_column, first_row, last_column, last_row)) = self.cell_range(bounds) {
+            for row in first_row..=last_row {
+                for column in first_column..=last_column {
+                    bricks.extend_from_slice(&self.cells[row * self.columns + column]);
+                }
+            }
+        }
+        bricks.sort_unstable();
+        bricks.dedup();
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "BricksWallFields")
+)]
 pub struct BricksWall {
     pub bricks: Vec<Brick>,
+    // The time since the start of the level, it decides the positions of the
+    // bricks which follow a path.
+    pub elapsed: f32,
+    pub brick_size: (f32, f32),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub index: BrickIndex,
+    // The buffer of the bricks near the ball, kept between the steps.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub near_bricks: Vec<usize>,
+}
+
+// The serialized wall, the index is built again from its bricks.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct BricksWallFields {
+    bricks: Vec<Brick>,
+    elapsed: f32,
+    brick_size: (f32, f32),
+}
+
+#[cfg(feature = "serde")]
+impl From<BricksWallFields> for BricksWall {
+    fn from(fields: BricksWallFields) -> Self {
+        let mut wall = BricksWall {
+            bricks: fields.bricks,
+            elapsed: fields.elapsed,
+            brick_size: fields.brick_size,
+            index: BrickIndex::default(),
+            near_bricks: Vec::new(),
+        };
+        wall.rebuild_index();
+        wall
+    }
 }
 
 impl BricksWall {
@@ -148,54 +793,189 @@ impl BricksWall {
     pub const BRICKS_PERCENTAGE_OF_SCREEN_HEIGHT: f32 = 0.6;
 
     pub fn new(screen_width: f32, screen_height: f32, brick_height: f32, brick_width: f32) -> Self {
+        let grid = BrickGrid::new(screen_width, screen_height, brick_height, brick_width);
+        Self::from_layout(&grid, &LevelLayout::full(grid.columns, grid.rows))
+    }
+
+    pub fn from_layout(grid: &BrickGrid, layout: &LevelLayout) -> Self {
         let mut bricks = Vec::new();
 
-        // Horizontal calculations
-        let brick_horizontal_size = brick_width + 2.0 * Self::BRICK_PADDING;
-        let left_horizontal_space = screen_width - 2.0 * Self::PADDING;
-        let bricks_columns_count = (left_horizontal_space / brick_horizontal_size) as i32;
-        let left_horizontal_space_after_bricks =
-            left_horizontal_space - (brick_horizontal_size * (bricks_columns_count as f32));
-        let offset_from_left = left_horizontal_space_after_bricks / 2.0;
+        for i in 0..grid.columns.min(layout.columns) {
+            for j in 0..grid.rows.min(layout.rows) {
+                if let Some(kind) = layout.get(i, j) {
+                    let mut brick = Brick::new(kind, grid.cell_position(i, j));
+                    brick.path = layout.path_at(i, j);
+                    bricks.push(brick);
+                }
+            }
+        }
 
-        // Vertical calculations
-        let brick_vertical_size = brick_height + 2.0 * Self::BRICK_PADDING;
-        let left_vertical_space = screen_height * Self::BRICKS_PERCENTAGE_OF_SCREEN_HEIGHT;
-        let bricks_rows_count = (left_vertical_space / brick_vertical_size) as i32;
-
-        for i in 0..bricks_columns_count {
-            for j in 0..bricks_rows_count {
-                let x = Self::PADDING
-                    + offset_from_left
-                    + Self::BRICK_PADDING
-                    + (i as f32) * brick_horizontal_size;
-                let y =
-                    Self::PADDING + (brick_vertical_size / 2.0) + (j as f32) * brick_vertical_size;
-                let brick = Brick {
-                    state: BrickState::Survived,
-                    pos: Point2 { x, y },
-                };
-                bricks.push(brick);
+        let mut wall = BricksWall {
+            bricks,
+            elapsed: 0.0,
+            brick_size: grid.brick_size(),
+            index: BrickIndex::default(),
+            near_bricks: Vec::new(),
+        };
+        wall.rebuild_index();
+        wall
+    }
+
+    // Has to be called after the bricks or their paths change, the movement
+    // along the paths is followed by update.
+    pub fn rebuild_index(&mut self) {
+        let (brick_width, brick_height) = self.brick_size;
+        let cell_size = (
+            brick_width + 2.0 * Self::BRICK_PADDING,
+            brick_height + 2.0 * Self::BRICK_PADDING,
+        );
+        self.index = BrickIndex::new(&self.bricks, self.brick_size, cell_size);
+    }
+
+    // The indices of the bricks which may overlap the bounds. The padding
+    // covers the sprites of the touched bricks being a bit bigger.
+    pub fn bricks_near(&self, bounds: Rect) -> Vec<usize> {
+        let mut bricks = Vec::new();
+        self.bricks_near_into(bounds, &mut bricks);
+        bricks
+    }
+
+    pub fn bricks_near_into(&self, bounds: Rect, bricks: &mut Vec<usize>) {
+        let padded_bounds = Rect::new(
+            bounds.x - Self::BRICK_PADDING,
+            bounds.y - Self::BRICK_PADDING,
+            bounds.w + 2.0 * Self::BRICK_PADDING,
+            bounds.h + 2.0 * Self::BRICK_PADDING,
+        );
+        self.index.query_into(padded_bounds, bricks);
+    }
+
+    pub fn brick_rect(&self, index: usize) -> Rect {
+        let pos = self.bricks[index].pos;
+        Rect::new(pos.x, pos.y, self.brick_size.0, self.brick_size.1)
+    }
+
+    // Breaks every brick the ball touches in this step and bounces the ball
+    // once, off the merged contact of all of them. The ball is pushed out of
+    // the touched bricks, so it doesn't hit them again in the next step.
+    pub fn collide_ball<F>(
+        &mut self,
+        ball: &mut Ball,
+        seconds: f32,
+        brick_rect: F,
+    ) -> GameResult<BrickHits>
+    where
+        F: Fn(&Brick) -> Rect,
+    {
+        let mut contacts = Vec::new();
+        let mut rects = Vec::new();
+        let mut surface_velocity = Vector2 { x: 0.0, y: 0.0 };
+        let mut hits = BrickHits::default();
+        let mut near_bricks = std::mem::take(&mut self.near_bricks);
+        self.bricks_near_into(ball.swept_bounds(seconds), &mut near_bricks);
+        for &index in &near_bricks {
+            let brick = &mut self.bricks[index];
+            if matches!(brick.state, BrickState::Broken) {
+                continue;
+            }
+            let rect = brick_rect(brick);
+            if let Some(contact) = ball.contact_rect(rect) {
+                brick.broke().map_err(|_| {
+                    GameError::EventLoopError(String::from(
+                        "An error with breaking of a brick occurred.",
+                    ))
+                })?;
+                surface_velocity.x += brick.velocity.x;
+                surface_velocity.y += brick.velocity.y;
+                hits.bricks += 1;
+                if brick.kind != BrickKind::Unbreakable {
+                    hits.breakable_bricks += 1;
+                }
+                if brick.state == BrickState::Broken {
+                    hits.broken.count(brick.kind);
+                }
+                contacts.push(contact);
+                rects.push(rect);
             }
         }
+        self.near_bricks = near_bricks;
 
-        BricksWall { bricks }
+        let contact = match merge_contacts(&contacts) {
+            Some(contact) => contact,
+            None => return Ok(hits),
+        };
+        let count = contacts.len() as f32;
+        ball.reflect(
+            contact.normal,
+            Vector2 {
+                x: surface_velocity.x / count,
+                y: surface_velocity.y / count,
+            },
+        );
+        ball.push_out(&rects);
+        Ok(hits)
+    }
+
+    // Changes the hits which break the bricks of the kind.
+    pub fn set_hit_points(&mut self, kind: BrickKind, hit_points: u8) {
+        for brick in self.bricks.iter_mut().filter(|brick| brick.kind == kind) {
+            brick.set_hit_points(hit_points);
+        }
     }
 
     pub fn reset_on_game_over(&mut self) {
+        self.elapsed = 0.0;
         for brick in &mut self.bricks {
             brick.reset();
         }
+        self.rebuild_index();
     }
 
+    // The unbreakable bricks don't have to be broken to win the level.
     pub fn all_bricks_are_broken(&mut self) -> bool {
-        self.broken_bricks_count() == self.bricks.len()
+        self.broken_bricks_count() == self.breakable_bricks_count()
+    }
+
+    pub fn breakable_bricks_count(&self) -> usize {
+        self.bricks
+            .iter()
+            .filter(|&brick| brick.kind != BrickKind::Unbreakable)
+            .count()
     }
 
-    pub fn update() {}
-    pub fn draw(&self, ctx: &mut Context, assets: &Assets) -> GameResult<()> {
+    // Moves the bricks along their paths and keeps their velocities for the
+    // bounces of the ball.
+    pub fn update(&mut self, seconds: f32) {
+        self.elapsed += seconds;
+        let (brick_width, brick_height) = self.brick_size;
+        for (index, brick) in self.bricks.iter_mut().enumerate() {
+            if let Some(path) = brick.path {
+                let offset = path.offset(self.elapsed);
+                let pos = Point2 {
+                    x: brick.base_pos.x + offset.x,
+                    y: brick.base_pos.y + offset.y,
+                };
+                if seconds > 0.0 {
+                    brick.velocity = Vector2 {
+                        x: (pos.x - brick.pos.x) / seconds,
+                        y: (pos.y - brick.pos.y) / seconds,
+                    };
+                }
+                brick.pos = pos;
+                self.index
+                    .move_brick(index, Rect::new(pos.x, pos.y, brick_width, brick_height));
+            }
+        }
+    }
+
+    pub fn draw(
+        &self,
+        renderer: &mut dyn Renderer,
+        palette: &Palette,
+        alpha: f32,
+    ) -> GameResult<()> {
         for brick in &self.bricks {
-            match brick.draw(ctx, assets) {
+            match brick.draw(renderer, palette, alpha) {
                 Ok(_) => {}
                 _ => {
                     return Err(GameError::EventLoopError(String::from(
@@ -218,93 +998,230 @@ impl BricksWall {
 }
 
 impl Brick {
-    fn draw(&self, ctx: &mut Context, assets: &Assets) -> GameResult<()> {
-        match self.state {
-            BrickState::Survived => graphics::draw(
-                ctx,
-                &assets.brick_survived,
-                graphics::DrawParam {
-                    dest: self.pos,
-                    scale: Vector2 { x: 1.0, y: 1.0 },
-                    offset: Point2 { x: 0.0, y: 0.0 },
-                    ..Default::default()
-                },
-            ),
-
-            BrickState::Touched => graphics::draw(
-                ctx,
-                &assets.brick_touched,
-                graphics::DrawParam {
-                    dest: self.pos,
-                    scale: Vector2 { x: 1.0, y: 1.0 },
-                    offset: Point2 { x: 0.0, y: 0.0 },
-                    ..Default::default()
-                },
-            ),
-
-            BrickState::Broken => Ok(()),
+    pub fn new(kind: BrickKind, pos: Point2<f32>) -> Self {
+        Brick {
+            state: kind.initial_state(),
+            pos,
+            previous_pos: pos,
+            base_pos: pos,
+            kind,
+            path: None,
+            velocity: Vector2 { x: 0.0, y: 0.0 },
+            hit_points: kind.hit_points(),
+            hits_left: kind.hit_points(),
         }
     }
 
-    pub fn bounding_rect(&self, assets: &Assets) -> graphics::Rect {
-        let sprite = match self.state {
-            BrickState::Survived => &assets.brick_survived,
-            BrickState::Touched => &assets.brick_touched,
-            BrickState::Broken => &assets.brick_touched, // Fake
-        };
+    // Makes the brick take the given hits to break, from the start. The last
+    // hit is shown by the touched sprite.
+    pub fn set_hit_points(&mut self, hit_points: u8) {
+        if self.kind == BrickKind::Unbreakable {
+            return;
+        }
+        self.hit_points = hit_points.max(1);
+        self.hits_left = self.hit_points;
+        self.state = Self::state_for(self.hits_left);
+    }
 
-        let left = self.pos.x;
-        let right = self.pos.x + (sprite.width() as f32);
-        let top = self.pos.y;
-        let bottom = self.pos.y + (sprite.height() as f32);
+    fn state_for(hits_left: u8) -> BrickState {
+        match hits_left {
+            0 => BrickState::Broken,
+            1 => BrickState::Touched,
+            _ => BrickState::Survived,
+        }
+    }
 
-        graphics::Rect::new(left, top, right - left, bottom - top)
+    // The bounds of every position of the brick on its path.
+    pub fn reach(&self, brick_size: (f32, f32)) -> Rect {
+        let (brick_width, brick_height) = brick_size;
+        let path_bounds = self
+            .path
+            .map_or(Rect::new(0.0, 0.0, 0.0, 0.0), BrickPath::bounds);
+        let left = self.pos.x.min(self.base_pos.x + path_bounds.x);
+        let top = self.pos.y.min(self.base_pos.y + path_bounds.y);
+        let right = self.pos.x.max(self.base_pos.x + path_bounds.right()) + brick_width;
+        let bottom = self.pos.y.max(self.base_pos.y + path_bounds.bottom()) + brick_height;
+        Rect::new(left, top, right - left, bottom - top)
     }
 
-    pub fn broke(&mut self) -> GameResult<()> {
+    fn draw(&self, renderer: &mut dyn Renderer, palette: &Palette, alpha: f32) -> GameResult<()> {
+        let dest = lerp_point(self.previous_pos, self.pos, alpha);
         match self.state {
-            BrickState::Survived => {
-                self.state = BrickState::Touched;
+            BrickState::Survived if self.kind == BrickKind::Unbreakable => {
+                renderer.sprite(SpriteKind::BrickSurvived, dest, palette.unbreakable_brick)
             }
+            BrickState::Survived => renderer.sprite(SpriteKind::BrickSurvived, dest, WHITE),
             BrickState::Touched => {
-                self.state = BrickState::Broken;
-            }
-            BrickState::Broken => {
-                self.state = BrickState::Broken;
+                renderer.sprite(SpriteKind::BrickTouched, dest, palette.touched_brick)
             }
+            BrickState::Broken => Ok(()),
+        }
+    }
+
+    #[cfg(feature = "ggez")]
+    pub fn bounding_rect(&self, assets: &Assets) -> Rect {
+        self.rect(&SpriteSizes::from_assets(assets))
+    }
+
+    pub fn rect(&self, sizes: &SpriteSizes) -> Rect {
+        let (width, height) = match self.state {
+            BrickState::Survived => sizes.brick_survived,
+            BrickState::Touched => sizes.brick_touched,
+            BrickState::Broken => sizes.brick_touched, // Fake
         };
+        Rect::new(self.pos.x, self.pos.y, width, height)
+    }
+
+    pub fn broke(&mut self) -> GameResult<()> {
+        if self.kind == BrickKind::Unbreakable {
+            return Ok(());
+        }
+
+        self.hits_left = self.hits_left.saturating_sub(1);
+        self.state = Self::state_for(self.hits_left);
         Ok(())
     }
 
     pub fn reset(&mut self) {
-        self.state = BrickState::Survived;
+        self.hits_left = self.hit_points;
+        self.state = if self.kind == BrickKind::Unbreakable {
+            BrickState::Survived
+        } else {
+            Self::state_for(self.hits_left)
+        };
+        self.pos = self.base_pos;
+        self.previous_pos = self.base_pos;
+        self.velocity = Vector2 { x: 0.0, y: 0.0 };
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Brick {
     pub state: BrickState,
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::serde_point2"))]
     pub pos: Point2<f32>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::serde_point2"))]
+    pub previous_pos: Point2<f32>,
+    // The position in the grid, the path of the brick starts from it.
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::serde_point2"))]
+    pub base_pos: Point2<f32>,
+    pub kind: BrickKind,
+    pub path: Option<BrickPath>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::serde_vector2"))]
+    pub velocity: Vector2<f32>,
+    // The hits which break the brick and the ones it still takes.
+    pub hit_points: u8,
+    pub hits_left: u8,
 }
 
-#[derive(Debug)]
+// The movement of a brick relative to its position in the grid.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BrickPath {
+    // Moves left and right around the grid position.
+    Oscillate { amplitude: f32, period: f32 },
+    // Circles through the grid position, clockwise on the screen.
+    Orbit { radius: f32, period: f32 },
+    // Moves down with the given speed until it has moved the given distance.
+    Descend { speed: f32, distance: f32 },
+}
+
+impl BrickPath {
+    // The offset from the grid position after the given time.
+    pub fn offset(self, seconds: f32) -> Vector2<f32> {
+        let angle = |period: f32| 2.0 * std::f32::consts::PI * seconds / period;
+        match self {
+            BrickPath::Oscillate { amplitude, period } => Vector2 {
+                x: amplitude * angle(period).sin(),
+                y: 0.0,
+            },
+            BrickPath::Orbit { radius, period } => Vector2 {
+                x: radius * (angle(period).cos() - 1.0),
+                y: radius * angle(period).sin(),
+            },
+            BrickPath::Descend { speed, distance } => Vector2 {
+                x: 0.0,
+                y: (speed * seconds).min(distance),
+            },
+        }
+    }
+
+    // The bounds of all the offsets of the path.
+    pub fn bounds(self) -> Rect {
+        match self {
+            BrickPath::Oscillate { amplitude, .. } => {
+                Rect::new(-amplitude.abs(), 0.0, 2.0 * amplitude.abs(), 0.0)
+            }
+            BrickPath::Orbit { radius, .. } => Rect::new(
+                -2.0 * radius.abs(),
+                -radius.abs(),
+                2.0 * radius.abs(),
+                2.0 * radius.abs(),
+            ),
+            BrickPath::Descend { distance, .. } => {
+                Rect::new(0.0, distance.min(0.0), 0.0, distance.abs())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BrickKind {
+    Normal,
+    Weak,
+    Unbreakable,
+}
+
+impl BrickKind {
+    // The order in which the level editor cycles through the kinds.
+    pub const ALL: [BrickKind; 3] = [BrickKind::Normal, BrickKind::Weak, BrickKind::Unbreakable];
+
+    // The hits which break a brick of the kind, an unbreakable one takes
+    // none.
+    pub fn hit_points(self) -> u8 {
+        match self {
+            BrickKind::Normal => 2,
+            BrickKind::Weak => 1,
+            BrickKind::Unbreakable => 0,
+        }
+    }
+
+    // A weak brick starts touched, so a single hit breaks it.
+    pub fn initial_state(self) -> BrickState {
+        match self {
+            BrickKind::Weak => BrickState::Touched,
+            _ => BrickState::Survived,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BrickState {
     Survived,
     Touched,
     Broken,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SkateboardState {
     Normal,
     Rebound,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Skateboard {
     pub state: SkateboardState,
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::serde_point2"))]
     pub pos: Point2<f32>,
-    velocity: Vector2<f32>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::serde_point2"))]
+    pub previous_pos: Point2<f32>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::serde_vector2"))]
+    pub velocity: Vector2<f32>,
 }
 
 impl Skateboard {
@@ -319,10 +1236,12 @@ impl Skateboard {
         Skateboard {
             state: SkateboardState::Normal,
             pos: pos,
+            previous_pos: pos,
             velocity: Vector2 { x: 0.0, y: 0.0 },
         }
     }
 
+    #[cfg(feature = "ggez")]
     pub fn get_current_sprite_size(&self, assets: &Assets) -> Size {
         let asset = match self.state {
             SkateboardState::Normal => &assets.skateboard_normal,
@@ -334,35 +1253,34 @@ impl Skateboard {
         }
     }
 
-    pub fn update(&mut self, seconds: f32, amount: f32, max_right: f32, assets: &Assets) {
-        let current_sprite_size = self.get_current_sprite_size(assets);
+    pub fn update(&mut self, seconds: f32, amount: f32, max_right: f32, sizes: &SpriteSizes) {
+        let width = self.rect(sizes).w;
         let new_pos = self.pos.x + Self::SPEED * seconds * amount;
-        self.pos.x = nalgebra::clamp(new_pos, 0.0, max_right - current_sprite_size.width);
+        self.pos.x = nalgebra::clamp(new_pos, 0.0, max_right - width);
     }
 
-    pub fn draw(&self, ctx: &mut Context, assets: &Assets) -> GameResult<()> {
+    pub fn draw(&self, renderer: &mut dyn Renderer, alpha: f32) -> GameResult<()> {
         let current_sprite = match self.state {
-            SkateboardState::Normal => &assets.skateboard_normal,
-            SkateboardState::Rebound => &assets.skateboard_rebound,
+            SkateboardState::Normal => SpriteKind::SkateboardNormal,
+            SkateboardState::Rebound => SpriteKind::SkateboardRebound,
         };
-
-        graphics::draw(
-            ctx,
+        renderer.sprite(
             current_sprite,
-            graphics::DrawParam {
-                dest: self.pos,
-                ..Default::default()
-            },
+            lerp_point(self.previous_pos, self.pos, alpha),
+            WHITE,
         )
     }
 
-    pub fn bounding_rect(&self, assets: &Assets) -> graphics::Rect {
-        let sprite_size = self.get_current_sprite_size(assets);
-        let left = self.pos.x;
-        let right = self.pos.x + (sprite_size.width as f32);
-        let top = self.pos.y;
-        let bottom = self.pos.y + (sprite_size.height as f32);
+    #[cfg(feature = "ggez")]
+    pub fn bounding_rect(&self, assets: &Assets) -> Rect {
+        self.rect(&SpriteSizes::from_assets(assets))
+    }
 
-        graphics::Rect::new(left, top, right - left, bottom - top)
+    pub fn rect(&self, sizes: &SpriteSizes) -> Rect {
+        let (width, height) = match self.state {
+            SkateboardState::Normal => sizes.skateboard_normal,
+            SkateboardState::Rebound => sizes.skateboard_rebound,
+        };
+        Rect::new(self.pos.x, self.pos.y, width, height)
     }
 }
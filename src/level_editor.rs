@@ -0,0 +1,95 @@
+use crate::debug;
+use crate::gameplay_entities::BrickGrid;
+use crate::levels::LevelLayout;
+use crate::renderer::Renderer;
+use crate::theme::Palette;
+use crate::types::{GameResult, Point2};
+
+// The state of the level editor. The edited layout is kept here and the
+// bricks wall of the gameplay state is built from it after every change.
+#[derive(Debug)]
+pub struct LevelEditor {
+    pub level: i32,
+    pub layout: LevelLayout,
+    pub cursor: (usize, usize),
+    // True while the edited level is test-played, the end of the game returns
+    // to the editor instead of changing the progress.
+    pub testing: bool,
+    pub status: String,
+}
+
+impl LevelEditor {
+    pub fn new(level: i32, layout: LevelLayout) -> Self {
+        LevelEditor {
+            level,
+            layout,
+            cursor: (0, 0),
+            testing: false,
+            status: String::new(),
+        }
+    }
+
+    pub fn move_cursor(&mut self, columns: i32, rows: i32) {
+        let (column, row) = self.cursor;
+        let max_column = self.layout.columns.saturating_sub(1) as i32;
+        let max_row = self.layout.rows.saturating_sub(1) as i32;
+        self.cursor = (
+            nalgebra::clamp(column as i32 + columns, 0, max_column) as usize,
+            nalgebra::clamp(row as i32 + rows, 0, max_row) as usize,
+        );
+    }
+
+    pub fn cycle_at_cursor(&mut self) {
+        let (column, row) = self.cursor;
+        self.layout.cycle(column, row);
+    }
+
+    pub fn remove_at_cursor(&mut self) {
+        let (column, row) = self.cursor;
+        self.layout.set(column, row, None);
+    }
+
+    // Draws the cells of the grid and the cursor over the bricks, with the
+    // controls of the editor under the bricks.
+    pub fn draw(
+        &self,
+        renderer: &mut dyn Renderer,
+        grid: &BrickGrid,
+        screen_height: f32,
+        palette: &Palette,
+    ) -> GameResult<()> {
+        let mut cell_color = palette.text;
+        cell_color.a = 0.15;
+        for row in 0..grid.rows {
+            for column in 0..grid.columns {
+                debug::draw_rect_outline(grid.cell_rect(column, row), cell_color, renderer)?;
+            }
+        }
+        if grid.columns > 0 && grid.rows > 0 {
+            let (column, row) = self.cursor;
+            debug::draw_rect_outline(grid.cell_rect(column, row), palette.highlight, renderer)?;
+        }
+
+        let title_text = format!("Editing level {}", self.level);
+        let lines = [
+            ("editor_title", title_text.as_str()),
+            (
+                "editor_help_edit",
+                "Space/click: cycle, Del/right click: remove",
+            ),
+            (
+                "editor_help_level",
+                "T: test, S: save, PgUp/PgDn: level, Esc: back",
+            ),
+            ("editor_status", self.status.as_str()),
+        ];
+
+        let mut y = screen_height;
+        for (key, label) in lines.iter().rev() {
+            let (_, height) = renderer.text_size(key, label);
+            y -= height;
+            renderer.text(key, label, Point2 { x: 10.0, y })?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use crate::gameplay_entities::BrickKind;
+use crate::levels::LevelLayout;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+// The base shapes of the generated walls.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Pattern {
+    Rectangle,
+    Pyramid,
+    Checkerboard,
+    Stripes,
+    Diamond,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 5] = [
+        Pattern::Rectangle,
+        Pattern::Pyramid,
+        Pattern::Checkerboard,
+        Pattern::Stripes,
+        Pattern::Diamond,
+    ];
+
+    // Whether the pattern has a brick in the given cell of a grid with the given size.
+    pub fn contains(self, column: usize, row: usize, columns: usize, rows: usize) -> bool {
+        let center_x = (columns as f32 - 1.0) / 2.0;
+        let center_y = (rows as f32 - 1.0) / 2.0;
+        let dx = (column as f32 - center_x).abs();
+        let dy = (row as f32 - center_y).abs();
+
+        match self {
+            Pattern::Rectangle => true,
+            // The pyramid is upside down, so its widest row is the first one.
+            Pattern::Pyramid => dx < (rows - row) as f32 * columns as f32 / (2.0 * rows as f32),
+            Pattern::Checkerboard => (column + row).is_multiple_of(2),
+            Pattern::Stripes => row.is_multiple_of(2),
+            Pattern::Diamond => dx / (center_x + 1.0) + dy / (center_y + 1.0) <= 1.0,
+        }
+    }
+}
+
+// Everything which is drawn from the seed before the cells are filled.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GeneratorParams {
+    pub pattern: Pattern,
+    // The right half of the wall is the mirror of the left one.
+    pub mirrored: bool,
+    // The cells where the noise is under this value become holes.
+    pub hole_threshold: f32,
+    pub weak_chance: f32,
+    pub unbreakable_chance: f32,
+}
+
+impl GeneratorParams {
+    pub fn random(rng: &mut ChaCha8Rng) -> Self {
+        GeneratorParams {
+            pattern: Pattern::ALL[rng.gen_range(0..Pattern::ALL.len())],
+            mirrored: rng.gen_bool(0.75),
+            hole_threshold: rng.gen_range(0.0..0.35),
+            weak_chance: rng.gen_range(0.0..0.4),
+            unbreakable_chance: rng.gen_range(0.0..0.12),
+        }
+    }
+}
+
+// Smooth random values over the grid, so the holes come in clusters instead
+// of single cells.
+struct ValueNoise {
+    lattice: Vec<Vec<f32>>,
+}
+
+impl ValueNoise {
+    const CELL_SIZE: usize = 3;
+
+    fn new(rng: &mut ChaCha8Rng, columns: usize, rows: usize) -> Self {
+        let lattice = (0..rows / Self::CELL_SIZE + 2)
+            .map(|_| {
+                (0..columns / Self::CELL_SIZE + 2)
+                    .map(|_| rng.gen::<f32>())
+                    .collect()
+            })
+            .collect();
+        ValueNoise { lattice }
+    }
+
+    fn at(&self, column: usize, row: usize) -> f32 {
+        let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+        let (x, y) = (column / Self::CELL_SIZE, row / Self::CELL_SIZE);
+        let tx = smooth((column % Self::CELL_SIZE) as f32 / Self::CELL_SIZE as f32);
+        let ty = smooth((row % Self::CELL_SIZE) as f32 / Self::CELL_SIZE as f32);
+
+        let top = self.lattice[y][x] * (1.0 - tx) + self.lattice[y][x + 1] * tx;
+        let bottom = self.lattice[y + 1][x] * (1.0 - tx) + self.lattice[y + 1][x + 1] * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+// Builds the wall for the given seed. The same seed and grid size always give
+// the same wall.
+pub fn generate(seed: u64, columns: usize, rows: usize) -> LevelLayout {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let params = GeneratorParams::random(&mut rng);
+    generate_with_params(&mut rng, &params, columns, rows)
+}
+
+pub fn generate_with_params(
+    rng: &mut ChaCha8Rng,
+    params: &GeneratorParams,
+    columns: usize,
+    rows: usize,
+) -> LevelLayout {
+    let noise = ValueNoise::new(rng, columns, rows);
+    let generated_columns = if params.mirrored {
+        columns.div_ceil(2)
+    } else {
+        columns
+    };
+
+    let mut layout = LevelLayout::empty(columns, rows);
+    for row in 0..rows {
+        for column in 0..generated_columns {
+            if !params.pattern.contains(column, row, columns, rows)
+                || noise.at(column, row) < params.hole_threshold
+            {
+                continue;
+            }
+
+            let roll = rng.gen::<f32>();
+            let kind = if roll < params.unbreakable_chance {
+                BrickKind::Unbreakable
+            } else if roll < params.unbreakable_chance + params.weak_chance {
+                BrickKind::Weak
+            } else {
+                BrickKind::Normal
+            };
+            layout.set(column, row, Some(kind));
+            if params.mirrored {
+                layout.set(columns - 1 - column, row, Some(kind));
+            }
+        }
+    }
+
+    // A wall without a breakable brick can't be won, so the holes and the
+    // kinds are dropped and the plain pattern is used.
+    if !layout.cells.contains(&Some(BrickKind::Normal))
+        && !layout.cells.contains(&Some(BrickKind::Weak))
+    {
+        for row in 0..rows {
+            for column in 0..columns {
+                if params.pattern.contains(column, row, columns, rows) {
+                    layout.set(column, row, Some(BrickKind::Normal));
+                }
+            }
+        }
+    }
+    layout
+}
+
+// The seed of the given level of an endless run.
+pub fn level_seed(run_seed: u64, level: i32) -> u64 {
+    ChaCha8Rng::seed_from_u64(run_seed ^ (level as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)).gen()
+}
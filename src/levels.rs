@@ -0,0 +1,248 @@
+use crate::gameplay_entities::{BrickKind, BrickPath};
+use crate::types::{GameError, GameResult};
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
+
+pub const LEVELS_DIR: &str = "levels";
+
+// The file which replaces the default wall of the given level.
+pub fn level_file_name(level: i32) -> String {
+    format!("{}/level_{}.txt", LEVELS_DIR, level)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PathTarget {
+    Row(usize),
+    Cell(usize, usize),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PathRule {
+    pub target: PathTarget,
+    pub path: BrickPath,
+}
+
+// The bricks of a level on the cells of the bricks grid. In the level file
+// every line is a row of the grid and every character is a cell: '.' is an
+// empty cell, '#' a normal brick, '+' a weak brick and '@' an unbreakable one.
+// Lines starting with "path" give a path to a row or to a single brick:
+//   path row <row> oscillate <amplitude> <period>
+//   path cell <column> <row> orbit <radius> <period>
+//   path row <row> descend <speed> <distance>
+// Lines starting with ';' are comments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelLayout {
+    pub columns: usize,
+    pub rows: usize,
+    pub cells: Vec<Option<BrickKind>>,
+    pub paths: Vec<PathRule>,
+}
+
+impl LevelLayout {
+    pub fn empty(columns: usize, rows: usize) -> Self {
+        LevelLayout {
+            columns,
+            rows,
+            cells: vec![None; columns * rows],
+            paths: Vec::new(),
+        }
+    }
+
+    pub fn full(columns: usize, rows: usize) -> Self {
+        LevelLayout {
+            columns,
+            rows,
+            cells: vec![Some(BrickKind::Normal); columns * rows],
+            paths: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, column: usize, row: usize) -> Option<BrickKind> {
+        if column < self.columns && row < self.rows {
+            self.cells[row * self.columns + column]
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, column: usize, row: usize, kind: Option<BrickKind>) {
+        if column < self.columns && row < self.rows {
+            self.cells[row * self.columns + column] = kind;
+        }
+    }
+
+    // The path of the brick in the given cell. The path of the cell is used
+    // before the path of its row and a later rule before an earlier one.
+    pub fn path_at(&self, column: usize, row: usize) -> Option<BrickPath> {
+        let cell_path = self
+            .paths
+            .iter()
+            .rev()
+            .find(|rule| rule.target == PathTarget::Cell(column, row));
+        let row_path = self
+            .paths
+            .iter()
+            .rev()
+            .find(|rule| rule.target == PathTarget::Row(row));
+        cell_path.or(row_path).map(|rule| rule.path)
+    }
+
+    // Changes an empty cell to a normal brick and every brick to the next
+    // kind, the last kind becomes an empty cell again.
+    pub fn cycle(&mut self, column: usize, row: usize) {
+        let kind = match self.get(column, row) {
+            None => Some(BrickKind::ALL[0]),
+            Some(kind) => {
+                let index = BrickKind::ALL.iter().position(|&k| k == kind).unwrap_or(0);
+                BrickKind::ALL.get(index + 1).copied()
+            }
+        };
+        self.set(column, row, kind);
+    }
+
+    // The layout cut or padded with empty cells to the size of another grid,
+    // for example when a level is played on a smaller screen.
+    pub fn resized(&self, columns: usize, rows: usize) -> Self {
+        let mut layout = Self::empty(columns, rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                layout.set(column, row, self.get(column, row));
+            }
+        }
+        layout.paths = self.paths.clone();
+        layout
+    }
+
+    pub fn parse(contents: &str) -> GameResult<LevelLayout> {
+        let (path_lines, lines): (Vec<&str>, Vec<&str>) = contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with(';'))
+            .partition(|line| line.starts_with("path"));
+        let columns = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut layout = Self::empty(columns, lines.len());
+        for (row, line) in lines.iter().enumerate() {
+            for (column, character) in line.chars().enumerate() {
+                let kind = match character {
+                    '.' => None,
+                    '#' => Some(BrickKind::Normal),
+                    '+' => Some(BrickKind::Weak),
+                    '@' => Some(BrickKind::Unbreakable),
+                    _ => {
+                        return Err(GameError::ResourceLoadError(format!(
+                            "Invalid cell '{}' in the level: {}",
+                            character, line
+                        )))
+                    }
+                };
+                layout.set(column, row, kind);
+            }
+        }
+
+        for line in path_lines {
+            layout.paths.push(parse_path_rule(line)?);
+        }
+        Ok(layout)
+    }
+
+    pub fn to_file_contents(&self) -> String {
+        let mut contents = String::new();
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                contents.push(match self.get(column, row) {
+                    None => '.',
+                    Some(BrickKind::Normal) => '#',
+                    Some(BrickKind::Weak) => '+',
+                    Some(BrickKind::Unbreakable) => '@',
+                });
+            }
+            contents.push('\n');
+        }
+
+        for rule in &self.paths {
+            let target = match rule.target {
+                PathTarget::Row(row) => format!("row {}", row),
+                PathTarget::Cell(column, row) => format!("cell {} {}", column, row),
+            };
+            contents.push_str(&format!("path {} {}\n", target, format_path(rule.path)));
+        }
+        contents
+    }
+
+    // Reads the layout from the file, the missing file means the default wall.
+    pub fn load(file_name: &str) -> GameResult<Option<LevelLayout>> {
+        match fs::read_to_string(file_name) {
+            Ok(contents) => Self::parse(&contents).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn save(&self, file_name: &str) -> GameResult<()> {
+        if let Some(dir) = Path::new(file_name).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = File::create(file_name)?;
+        file.write_all(self.to_file_contents().as_bytes())?;
+        Ok(())
+    }
+}
+
+fn parse_path_rule(line: &str) -> GameResult<PathRule> {
+    let invalid = || GameError::ResourceLoadError(format!("Invalid path in the level: {}", line));
+    let words = line.split_whitespace().collect::<Vec<&str>>();
+    let index = |word: Option<&&str>| -> GameResult<usize> {
+        word.and_then(|word| word.parse().ok()).ok_or_else(invalid)
+    };
+
+    let (target, rest) = match words.get(1) {
+        Some(&"row") => (PathTarget::Row(index(words.get(2))?), &words[3..]),
+        Some(&"cell") if words.len() > 3 => (
+            PathTarget::Cell(index(words.get(2))?, index(words.get(3))?),
+            &words[4..],
+        ),
+        _ => return Err(invalid()),
+    };
+    let path = parse_path(rest).ok_or_else(invalid)?;
+    Ok(PathRule { target, path })
+}
+
+// The path as the words "<name> <first value> <second value>".
+pub fn format_path(path: BrickPath) -> String {
+    match path {
+        BrickPath::Oscillate { amplitude, period } => {
+            format!("oscillate {} {}", amplitude, period)
+        }
+        BrickPath::Orbit { radius, period } => format!("orbit {} {}", radius, period),
+        BrickPath::Descend { speed, distance } => format!("descend {} {}", speed, distance),
+    }
+}
+
+pub fn parse_path(words: &[&str]) -> Option<BrickPath> {
+    if words.len() != 3 {
+        return None;
+    }
+    let number = |word: &str| word.parse().ok().filter(|value: &f32| value.is_finite());
+    let (first, second) = (number(words[1])?, number(words[2])?);
+    match words[0] {
+        "oscillate" if second > 0.0 => Some(BrickPath::Oscillate {
+            amplitude: first,
+            period: second,
+        }),
+        "orbit" if second > 0.0 => Some(BrickPath::Orbit {
+            radius: first,
+            period: second,
+        }),
+        // A brick which moved up without an end would leave the screen.
+        "descend" if first >= 0.0 => Some(BrickPath::Descend {
+            speed: first,
+            distance: second,
+        }),
+        _ => None,
+    }
+}
@@ -1,8 +1,28 @@
+pub mod achievements;
+#[cfg(feature = "ggez")]
 pub mod assets;
+pub mod autopilot;
 pub mod debug;
 pub mod dialogs_handler;
+#[cfg(feature = "ggez")]
 pub mod event_handler_wrapper;
 pub mod game_settings_entities;
 pub mod game_workflow_state_reducer;
 pub mod gameplay_entities;
+pub mod level_editor;
+pub mod level_generator;
+pub mod levels;
 pub mod math;
+pub mod progress;
+pub mod renderer;
+pub mod save_dir;
+pub mod saved_run;
+pub mod settings;
+pub mod simulation;
+pub mod software_renderer;
+pub mod statistics;
+pub mod terminal_renderer;
+pub mod theme;
+pub mod timestep;
+pub mod types;
+pub mod widgets;
@@ -1,14 +1,36 @@
+use brick_breaker::achievements::{self, Achievements};
 use brick_breaker::assets::Assets;
-use brick_breaker::event_handler_wrapper::{EventHandlerWrapper, SCORE_FILE_NAME};
+use brick_breaker::event_handler_wrapper::EventHandlerWrapper;
+use brick_breaker::progress::Progress;
+use brick_breaker::save_dir::SaveDir;
+use brick_breaker::saved_run::SavedRun;
+use brick_breaker::settings::Settings;
+use brick_breaker::statistics::Statistics;
+use brick_breaker::theme::{Theme, ThemeSelector};
 use ggez::conf::{Conf, WindowMode};
 use ggez::event::{self};
 use ggez::input;
 use ggez::timer;
 use ggez::{graphics, Context, ContextBuilder, GameError, GameResult};
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::path::Path;
 
 fn main() -> GameResult {
+    let args = std::env::args().collect::<Vec<String>>();
+    let save_dir = SaveDir::from_args(&args, |name| std::env::var(name).ok())?;
+    if args.iter().any(|arg| arg == "--profiles") {
+        for profile in save_dir.profiles() {
+            println!("{}", profile);
+        }
+        return Ok(());
+    }
+    save_dir.create()?;
+    if let Err(e) = save_dir.import_legacy_files(Path::new(".")) {
+        println!(
+            "The saves of the working directory were not imported: {:?} ",
+            e
+        );
+    }
+
     let conf = Conf::new().window_mode(WindowMode {
         width: 800.0,
         height: 600.0,
@@ -23,7 +45,7 @@ fn main() -> GameResult {
 
     graphics::set_window_title(&ctx, "Brick breaker");
 
-    let main_state = MainState::new(&mut ctx, conf).unwrap();
+    let main_state = MainState::new(&mut ctx, conf, save_dir).unwrap();
     event::run(ctx, event_loop, main_state);
 }
 
@@ -32,34 +54,68 @@ struct MainState {
 }
 
 impl MainState {
-    pub fn new(_ctx: &mut Context, conf: Conf) -> GameResult<MainState> {
-        match read_lines(String::from(SCORE_FILE_NAME)) {
-            Ok(lines) => {
-                let x = lines.map(|x| x.unwrap()).collect::<Vec<String>>();
-                if x.len() != 2 {
-                    let message = format!("There was an error with the file {}.", SCORE_FILE_NAME)
-                        .to_string();
-                    return Err(GameError::ResourceLoadError(message));
-                }
-                return Self::initialize_main_state(_ctx, conf, x);
-            }
-            _ => {
-                let message = format!("The file {} was not found.", SCORE_FILE_NAME).to_string();
-                return Err(GameError::ResourceLoadError(message));
-            }
-        };
+    pub fn new(_ctx: &mut Context, conf: Conf, save_dir: SaveDir) -> GameResult<MainState> {
+        let progress = Progress::load(&save_dir.score_file())?;
+        Self::initialize_main_state(_ctx, conf, progress, save_dir)
     }
 
     pub fn initialize_main_state(
         ctx: &mut Context,
         conf: Conf,
-        lines: Vec<String>,
+        progress: Progress,
+        save_dir: SaveDir,
     ) -> GameResult<MainState> {
-        let assets = Assets::new(ctx)?;
-        let level = lines[0].parse::<i32>().unwrap();
-        let max_score = lines[1].parse::<usize>().unwrap();
+        let mut settings = Settings::load(&save_dir.settings_file()).unwrap_or_else(|e| {
+            println!("Settings error, the default settings are used: {:?} ", e);
+            Settings::default()
+        });
+        let theme = Theme::load(ctx, &settings.theme).unwrap_or_else(|e| {
+            println!("Theme loading error, the built-in theme is used: {:?} ", e);
+            Theme::built_in()
+        });
+        settings.theme = theme.name.clone();
 
-        let e = EventHandlerWrapper::new(conf, assets, level, max_score);
+        let theme_selector = ThemeSelector::new(Theme::available(ctx), &settings.theme);
+        let assets = Assets::new(ctx, &theme)?;
+
+        let statistics = Statistics::load(&save_dir.stats_file()).unwrap_or_else(|e| {
+            println!("Statistics error, the statistics start again: {:?} ", e);
+            Statistics::default()
+        });
+        let achievements = Achievements::load(
+            achievements::DEFINITIONS_FILE_NAME,
+            &save_dir.achievements_file(),
+        )
+        .unwrap_or_else(|e| {
+            println!("Achievements error, there are no achievements: {:?} ", e);
+            Achievements::default()
+        });
+        let run_file = save_dir.run_file();
+        let mut e = EventHandlerWrapper::new(
+            conf,
+            assets,
+            theme_selector,
+            settings,
+            progress,
+            statistics,
+            achievements,
+            save_dir,
+        );
+        e.apply_settings(ctx)?;
+        match SavedRun::load(&run_file) {
+            Ok(Some(saved_run)) => e.offer_saved_run(saved_run),
+            Ok(None) => {}
+            Err(error) => println!("Saved run error, the run is not offered: {:?} ", error),
+        }
+        let args = std::env::args().collect::<Vec<String>>();
+        if args.iter().any(|arg| arg == "--edit") {
+            e.open_editor();
+        } else if let Some(index) = args.iter().position(|arg| arg == "--seed") {
+            match args.get(index + 1).map(|seed| seed.parse::<u64>()) {
+                Some(Ok(seed)) => e.start_endless_run(seed),
+                _ => println!("The --seed argument needs a number."),
+            }
+        }
 
         return Ok(MainState {
             event_handler_wrapper: e,
@@ -67,26 +123,34 @@ impl MainState {
     }
 }
 
-// The output is wrapped in a Result to allow matching on errors
-// Returns an Iterator to the Reader of the lines of the file.
-pub fn read_lines(filename: String) -> io::Result<io::Lines<io::BufReader<File>>> {
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
-}
-
 impl event::EventHandler for MainState {
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        self.event_handler_wrapper.update(ctx)
+        self.event_handler_wrapper.update(ctx)?;
+        if self.event_handler_wrapper.quit_requested() {
+            self.event_handler_wrapper.on_quit();
+            event::quit(ctx);
+        }
+        Ok(())
+    }
+
+    // Closing the window in the middle of a level asks first, then saves the
+    // progress and the run like the Quit item of the menu.
+    fn quit_event(&mut self, _ctx: &mut Context) -> bool {
+        if !self.event_handler_wrapper.request_quit() {
+            return true;
+        }
+        self.event_handler_wrapper.on_quit();
+        false
     }
 
     fn key_down_event(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context,
         keycode: event::KeyCode,
         _keymod: input::keyboard::KeyMods,
         _repeat: bool,
     ) {
-        self.event_handler_wrapper.key_down_event(keycode);
+        self.event_handler_wrapper.key_down_event(ctx, keycode);
     }
 
     fn key_up_event(
@@ -98,8 +162,33 @@ impl event::EventHandler for MainState {
         self.event_handler_wrapper.key_up_event(keycode);
     }
 
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        self.event_handler_wrapper.mouse_motion_event(x, y);
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: event::MouseButton,
+        x: f32,
+        y: f32,
+    ) {
+        self.event_handler_wrapper
+            .mouse_button_down_event(ctx, button, x, y);
+    }
+
+    fn gamepad_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: event::Button,
+        _id: input::gamepad::GamepadId,
+    ) {
+        self.event_handler_wrapper
+            .gamepad_button_down_event(ctx, button);
+    }
+
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        graphics::clear(ctx, graphics::BLACK);
+        graphics::clear(ctx, self.event_handler_wrapper.background_color());
         match self.event_handler_wrapper.draw(ctx) {
             Ok(_) => {}
             _ => {
@@ -1,3 +1,14 @@
+use crate::types::{Point2, Vector2};
+
+// The point between the two points, alpha 0 gives the first one and alpha 1
+// the second one.
+pub fn lerp_point(from: Point2<f32>, to: Point2<f32>, alpha: f32) -> Point2<f32> {
+    Point2 {
+        x: from.x + (to.x - from.x) * alpha,
+        y: from.y + (to.y - from.y) * alpha,
+    }
+}
+
 #[derive(Debug)]
 pub enum Collision {
     Left,
@@ -54,3 +65,88 @@ pub fn circle_collides_rect(
         return Collision::None;
     }
 }
+
+// The contact of a circle with a rectangle. The normal is a unit vector which
+// points from the rectangle to the center of the circle and the depth is how
+// far the circle has to move along it to stop overlapping.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Contact {
+    pub normal: Vector2<f32>,
+    pub depth: f32,
+}
+
+pub fn circle_contact_rect(
+    cx: f32,
+    cy: f32,
+    radius: f32,
+    rx: f32,
+    ry: f32,
+    rw: f32,
+    rh: f32,
+) -> Option<Contact> {
+    // closest point of the rectangle to the center
+    let closest_x = cx.max(rx).min(rx + rw);
+    let closest_y = cy.max(ry).min(ry + rh);
+    let dist_x = cx - closest_x;
+    let dist_y = cy - closest_y;
+    let distance = (dist_x * dist_x + dist_y * dist_y).sqrt();
+
+    if distance > 0.0 {
+        if distance >= radius {
+            return None;
+        }
+        // On a corner the normal is diagonal.
+        return Some(Contact {
+            normal: Vector2 {
+                x: dist_x / distance,
+                y: dist_y / distance,
+            },
+            depth: radius - distance,
+        });
+    }
+
+    // The center is inside, so the circle leaves through the closest edge.
+    let edges = [
+        (cx - rx, Vector2 { x: -1.0, y: 0.0 }),
+        (rx + rw - cx, Vector2 { x: 1.0, y: 0.0 }),
+        (cy - ry, Vector2 { x: 0.0, y: -1.0 }),
+        (ry + rh - cy, Vector2 { x: 0.0, y: 1.0 }),
+    ];
+    let mut closest_edge = edges[0];
+    for edge in &edges[1..] {
+        if edge.0 < closest_edge.0 {
+            closest_edge = *edge;
+        }
+    }
+    Some(Contact {
+        normal: closest_edge.1,
+        depth: closest_edge.0 + radius,
+    })
+}
+
+// Merges all contacts of a step into a single one, so touching two bricks at
+// once gives one reflection instead of two which cancel each other. When the
+// normals cancel each other out the deepest contact is used.
+pub fn merge_contacts(contacts: &[Contact]) -> Option<Contact> {
+    let deepest = contacts
+        .iter()
+        .copied()
+        .fold(None, |deepest: Option<Contact>, contact| match deepest {
+            Some(deepest) if deepest.depth >= contact.depth => Some(deepest),
+            _ => Some(contact),
+        })?;
+
+    let sum_x: f32 = contacts.iter().map(|contact| contact.normal.x).sum();
+    let sum_y: f32 = contacts.iter().map(|contact| contact.normal.y).sum();
+    let length = (sum_x * sum_x + sum_y * sum_y).sqrt();
+    if length < 1e-3 {
+        return Some(deepest);
+    }
+    Some(Contact {
+        normal: Vector2 {
+            x: sum_x / length,
+            y: sum_y / length,
+        },
+        depth: deepest.depth,
+    })
+}
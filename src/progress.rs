@@ -0,0 +1,154 @@
+use crate::types::{GameError, GameResult};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::ErrorKind;
+
+pub const SCORE_FILE_NAME: &str = "score.txt";
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct LevelRecord {
+    pub best_score: usize,
+    pub stars: u8,
+}
+
+// The progress of the player which is kept in the score file. The first two
+// lines are the last reached level and the max score, every next line is
+// "<level> <best score> <stars>" for a level which was played at least once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub level: i32,
+    pub max_score: usize,
+    pub levels: BTreeMap<i32, LevelRecord>,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress {
+            level: 1,
+            max_score: 0,
+            levels: BTreeMap::new(),
+        }
+    }
+}
+
+impl Progress {
+    pub const MAX_STARS: u8 = 3;
+    // Seconds per brick of the wall which a clear may take for three stars.
+    pub const PAR_SECONDS_PER_BRICK: f32 = 2.0;
+
+    // A new profile has no file yet and starts from the first level.
+    pub fn load(file_name: &str) -> GameResult<Progress> {
+        match fs::read_to_string(file_name) {
+            Ok(contents) => Self::parse(&contents).map_err(|_| {
+                GameError::ResourceLoadError(format!(
+                    "There was an error with the file {}.",
+                    file_name
+                ))
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Progress::default()),
+            Err(_) => Err(GameError::ResourceLoadError(format!(
+                "The file {} could not be read.",
+                file_name
+            ))),
+        }
+    }
+
+    pub fn parse(contents: &str) -> GameResult<Progress> {
+        let invalid = |line: &str| {
+            GameError::ResourceLoadError(format!("Invalid line in the progress: {}", line))
+        };
+        let lines = contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<&str>>();
+        if lines.len() < 2 {
+            return Err(GameError::ResourceLoadError(String::from(
+                "The progress needs the level and the max score.",
+            )));
+        }
+
+        let mut progress = Progress {
+            level: lines[0].parse().map_err(|_| invalid(lines[0]))?,
+            max_score: lines[1].parse().map_err(|_| invalid(lines[1]))?,
+            levels: BTreeMap::new(),
+        };
+
+        for line in &lines[2..] {
+            let values = line.split_whitespace().collect::<Vec<&str>>();
+            if values.len() != 3 {
+                return Err(invalid(line));
+            }
+            let level = values[0].parse().map_err(|_| invalid(line))?;
+            let record = LevelRecord {
+                best_score: values[1].parse().map_err(|_| invalid(line))?,
+                stars: values[2].parse().map_err(|_| invalid(line))?,
+            };
+            progress.levels.insert(level, record);
+        }
+        Ok(progress)
+    }
+
+    pub fn to_file_contents(&self) -> String {
+        let mut contents = format!("{}\n{}", self.level, self.max_score);
+        for (level, record) in &self.levels {
+            contents.push_str(&format!(
+                "\n{} {} {}",
+                level, record.best_score, record.stars
+            ));
+        }
+        contents
+    }
+
+    pub fn save(&self, file_name: &str) -> GameResult<()> {
+        let mut file = File::create(file_name)?;
+        file.write_all(self.to_file_contents().as_bytes())?;
+        Ok(())
+    }
+
+    // The highest level which can be started from the level select screen.
+    pub fn highest_unlocked_level(&self) -> i32 {
+        let after_last_clear = self
+            .levels
+            .iter()
+            .filter(|(_, record)| record.stars > 0)
+            .map(|(level, _)| level + 1)
+            .max()
+            .unwrap_or(1);
+        self.level.max(after_last_clear).max(1)
+    }
+
+    pub fn is_unlocked(&self, level: i32) -> bool {
+        level >= 1 && level <= self.highest_unlocked_level()
+    }
+
+    pub fn record(&self, level: i32) -> LevelRecord {
+        self.levels.get(&level).copied().unwrap_or_default()
+    }
+
+    pub fn record_attempt(&mut self, level: i32, score: usize) {
+        let record = self.levels.entry(level).or_default();
+        record.best_score = record.best_score.max(score);
+    }
+
+    pub fn record_clear(&mut self, level: i32, score: usize, stars: u8) {
+        let record = self.levels.entry(level).or_default();
+        record.best_score = record.best_score.max(score);
+        record.stars = record.stars.max(stars);
+        self.level = self.level.max(level + 1);
+    }
+}
+
+// Three stars for a clear within the par time of the wall, two within twice
+// the par time and one for any other clear.
+pub fn stars_for_clear(seconds: f32, bricks_count: usize) -> u8 {
+    let par_seconds = Progress::PAR_SECONDS_PER_BRICK * bricks_count as f32;
+    if seconds <= par_seconds {
+        Progress::MAX_STARS
+    } else if seconds <= 2.0 * par_seconds {
+        2
+    } else {
+        1
+    }
+}
@@ -0,0 +1,291 @@
+#[cfg(feature = "ggez")]
+use crate::assets::{Assets, Sprite, TextCache};
+use crate::gameplay_entities::SpriteSizes;
+use crate::types::{Color, GameResult, Point2, Rect};
+#[cfg(feature = "ggez")]
+use ggez::{graphics, Context};
+
+// The size of the font of the texts in pixels.
+pub const FONT_SIZE: f32 = 26.0;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SpriteKind {
+    Background,
+    Ball,
+    SkateboardNormal,
+    SkateboardRebound,
+    BrickSurvived,
+    BrickTouched,
+}
+
+impl SpriteKind {
+    // The colour of the sprite for the renderers without the images of the
+    // theme, the bricks take the colours of the palette from the tint.
+    pub fn flat_color(self) -> Color {
+        match self {
+            SpriteKind::Background => Color::new(0.0, 0.0, 0.0, 0.0),
+            SpriteKind::Ball => Color::from_rgb(230, 230, 230),
+            SpriteKind::SkateboardNormal => Color::from_rgb(200, 120, 40),
+            SpriteKind::SkateboardRebound => Color::from_rgb(240, 170, 60),
+            SpriteKind::BrickSurvived => Color::from_rgb(180, 60, 60),
+            SpriteKind::BrickTouched => Color::from_rgb(255, 255, 255),
+        }
+    }
+
+    pub fn size(self, sizes: &SpriteSizes) -> (f32, f32) {
+        match self {
+            SpriteKind::Background => (0.0, 0.0),
+            SpriteKind::Ball => sizes.ball,
+            SpriteKind::SkateboardNormal => sizes.skateboard_normal,
+            SpriteKind::SkateboardRebound => sizes.skateboard_rebound,
+            SpriteKind::BrickSurvived => sizes.brick_survived,
+            SpriteKind::BrickTouched => sizes.brick_touched,
+        }
+    }
+}
+
+// The colour multiplied by the tint, as ggez tints the images.
+pub fn tinted(color: Color, tint: Color) -> Color {
+    Color::new(
+        color.r * tint.r,
+        color.g * tint.g,
+        color.b * tint.b,
+        color.a * tint.a,
+    )
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RectStyle {
+    Fill,
+    Stroke(f32),
+}
+
+pub trait TextMetrics {
+    fn text_size(&mut self, key: &str, label: &str) -> (f32, f32);
+}
+
+// Every character takes the same box, for the renderers without a font.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FixedTextMetrics {
+    pub glyph_width: f32,
+    pub glyph_height: f32,
+}
+
+impl Default for FixedTextMetrics {
+    fn default() -> Self {
+        FixedTextMetrics {
+            glyph_width: FONT_SIZE / 2.0,
+            glyph_height: FONT_SIZE,
+        }
+    }
+}
+
+impl TextMetrics for FixedTextMetrics {
+    fn text_size(&mut self, _key: &str, label: &str) -> (f32, f32) {
+        (
+            label.chars().count() as f32 * self.glyph_width,
+            self.glyph_height,
+        )
+    }
+}
+
+// The drawing operations of the game. The entities draw through it, usually
+// into a draw list which a backend executes afterwards, so the same code
+// draws into the window, into an image without a GPU or into a terminal.
+pub trait Renderer: TextMetrics {
+    fn clear(&mut self, color: Color);
+
+    // Draws the sprite with its top left corner at dest, tinted by the color.
+    fn sprite(&mut self, sprite: SpriteKind, dest: Point2<f32>, color: Color) -> GameResult<()>;
+
+    fn rectangle(&mut self, rect: Rect, style: RectStyle, color: Color) -> GameResult<()>;
+
+    // Draws the label in the text colour of the theme, the key identifies the
+    // text between frames.
+    fn text(&mut self, key: &str, label: &str, top_left: Point2<f32>) -> GameResult<()>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCommand {
+    Clear(Color),
+    Sprite {
+        sprite: SpriteKind,
+        dest: Point2<f32>,
+        color: Color,
+    },
+    Rectangle {
+        rect: Rect,
+        style: RectStyle,
+        color: Color,
+    },
+    Text {
+        key: String,
+        label: String,
+        top_left: Point2<f32>,
+    },
+}
+
+impl DrawCommand {
+    pub fn execute(&self, renderer: &mut dyn Renderer) -> GameResult<()> {
+        match self {
+            DrawCommand::Clear(color) => {
+                renderer.clear(*color);
+                Ok(())
+            }
+            DrawCommand::Sprite {
+                sprite,
+                dest,
+                color,
+            } => renderer.sprite(*sprite, *dest, *color),
+            DrawCommand::Rectangle { rect, style, color } => {
+                renderer.rectangle(*rect, *style, *color)
+            }
+            DrawCommand::Text {
+                key,
+                label,
+                top_left,
+            } => renderer.text(key, label, *top_left),
+        }
+    }
+}
+
+// Records the drawing of a frame. The texts are measured by the backend which
+// will execute the commands, so the layout is the same as when drawn directly.
+pub struct DrawList<'a> {
+    pub commands: Vec<DrawCommand>,
+    metrics: &'a mut dyn TextMetrics,
+}
+
+impl<'a> DrawList<'a> {
+    pub fn new(metrics: &'a mut dyn TextMetrics) -> Self {
+        DrawList {
+            commands: Vec::new(),
+            metrics,
+        }
+    }
+
+    pub fn into_commands(self) -> Vec<DrawCommand> {
+        self.commands
+    }
+}
+
+impl<'a> TextMetrics for DrawList<'a> {
+    fn text_size(&mut self, key: &str, label: &str) -> (f32, f32) {
+        self.metrics.text_size(key, label)
+    }
+}
+
+impl<'a> Renderer for DrawList<'a> {
+    fn clear(&mut self, color: Color) {
+        self.commands.push(DrawCommand::Clear(color));
+    }
+
+    fn sprite(&mut self, sprite: SpriteKind, dest: Point2<f32>, color: Color) -> GameResult<()> {
+        self.commands.push(DrawCommand::Sprite {
+            sprite,
+            dest,
+            color,
+        });
+        Ok(())
+    }
+
+    fn rectangle(&mut self, rect: Rect, style: RectStyle, color: Color) -> GameResult<()> {
+        self.commands
+            .push(DrawCommand::Rectangle { rect, style, color });
+        Ok(())
+    }
+
+    fn text(&mut self, key: &str, label: &str, top_left: Point2<f32>) -> GameResult<()> {
+        self.commands.push(DrawCommand::Text {
+            key: String::from(key),
+            label: String::from(label),
+            top_left,
+        });
+        Ok(())
+    }
+}
+
+// Records the frame drawn by the closure and executes it with the renderer.
+pub fn render<R, F>(renderer: &mut R, draw: F) -> GameResult<()>
+where
+    R: Renderer,
+    F: FnOnce(&mut DrawList) -> GameResult<()>,
+{
+    let mut draw_list = DrawList::new(renderer);
+    draw(&mut draw_list)?;
+    let commands = draw_list.into_commands();
+    for command in &commands {
+        command.execute(renderer)?;
+    }
+    Ok(())
+}
+
+// Executes the draw commands with ggez in the window.
+#[cfg(feature = "ggez")]
+pub struct GgezRenderer<'a> {
+    ctx: &'a mut Context,
+    assets: &'a Assets,
+    text_cache: &'a mut TextCache,
+}
+
+#[cfg(feature = "ggez")]
+impl<'a> GgezRenderer<'a> {
+    pub fn new(ctx: &'a mut Context, assets: &'a Assets, text_cache: &'a mut TextCache) -> Self {
+        GgezRenderer {
+            ctx,
+            assets,
+            text_cache,
+        }
+    }
+}
+
+#[cfg(feature = "ggez")]
+impl<'a> Renderer for GgezRenderer<'a> {
+    fn clear(&mut self, color: Color) {
+        graphics::clear(self.ctx, color);
+    }
+
+    fn sprite(&mut self, sprite: SpriteKind, dest: Point2<f32>, color: Color) -> GameResult<()> {
+        let image = match sprite {
+            SpriteKind::Background => match &self.assets.background {
+                Some(background) => background,
+                None => return Ok(()),
+            },
+            SpriteKind::Ball => &self.assets.ball_flying,
+            SpriteKind::SkateboardNormal => &self.assets.skateboard_normal,
+            SpriteKind::SkateboardRebound => &self.assets.skateboard_rebound,
+            SpriteKind::BrickSurvived => &self.assets.brick_survived,
+            SpriteKind::BrickTouched => &self.assets.brick_touched,
+        };
+        graphics::draw(
+            self.ctx,
+            image,
+            graphics::DrawParam {
+                dest,
+                color,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn rectangle(&mut self, rect: Rect, style: RectStyle, color: Color) -> GameResult<()> {
+        let draw_mode = match style {
+            RectStyle::Fill => graphics::DrawMode::fill(),
+            RectStyle::Stroke(width) => graphics::DrawMode::stroke(width),
+        };
+        let rectangle = graphics::Mesh::new_rectangle(self.ctx, draw_mode, rect, color)?;
+        graphics::draw(self.ctx, &rectangle, graphics::DrawParam::default())
+    }
+
+    fn text(&mut self, key: &str, label: &str, top_left: Point2<f32>) -> GameResult<()> {
+        self.text_cache.sprite(key, label).draw(top_left, self.ctx)
+    }
+}
+
+#[cfg(feature = "ggez")]
+impl<'a> TextMetrics for GgezRenderer<'a> {
+    fn text_size(&mut self, key: &str, label: &str) -> (f32, f32) {
+        let sprite = self.text_cache.sprite(key, label);
+        (sprite.width(self.ctx), sprite.height(self.ctx))
+    }
+}
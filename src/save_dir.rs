@@ -0,0 +1,201 @@
+use crate::achievements::ACHIEVEMENTS_FILE_NAME;
+use crate::progress::SCORE_FILE_NAME;
+use crate::saved_run::RUN_FILE_NAME;
+use crate::settings::SETTINGS_FILE_NAME;
+use crate::statistics::STATS_FILE_NAME;
+use crate::types::{GameError, GameResult};
+use std::fs::{self, DirBuilder};
+use std::path::{Path, PathBuf};
+
+// The environment variable which moves the data directory like --data-dir.
+pub const DATA_DIR_ENV: &str = "BRICK_BREAKER_DATA_DIR";
+pub const DEFAULT_PROFILE: &str = "default";
+const APP_DIR_NAME: &str = "brick_breaker";
+const PROFILES_DIR_NAME: &str = "profiles";
+const MAX_PROFILE_NAME_LENGTH: usize = 32;
+
+// Where the files of the player are kept. The settings are shared by the
+// profiles, every profile has its own progress, statistics, achievements and
+// saved run:
+//   <data dir>/settings.txt
+//   <data dir>/profiles/<profile>/score.txt
+//   <data dir>/profiles/<profile>/stats.txt
+//   <data dir>/profiles/<profile>/achievements.txt
+//   <data dir>/profiles/<profile>/run.txt
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveDir {
+    pub data_dir: PathBuf,
+    pub profile: String,
+}
+
+impl SaveDir {
+    pub fn new(data_dir: PathBuf, profile: &str) -> GameResult<SaveDir> {
+        if !is_valid_profile_name(profile) {
+            return Err(GameError::ResourceLoadError(format!(
+                "Invalid profile name: {}. It needs up to {} letters, digits, - and _.",
+                profile, MAX_PROFILE_NAME_LENGTH
+            )));
+        }
+        // The file names are passed on as strings.
+        if data_dir.to_str().is_none() {
+            return Err(GameError::ResourceLoadError(format!(
+                "The data directory {} is not a valid UTF-8 path.",
+                data_dir.display()
+            )));
+        }
+        Ok(SaveDir {
+            data_dir,
+            profile: profile.to_string(),
+        })
+    }
+
+    // Takes the --data-dir and --profile arguments. Without --data-dir the
+    // directory is the one of the environment variable, then the one of the
+    // platform. The variables are read with the given function.
+    pub fn from_args(args: &[String], var: impl Fn(&str) -> Option<String>) -> GameResult<SaveDir> {
+        let argument = |name: &str| -> GameResult<Option<&String>> {
+            match args.iter().position(|arg| arg == name) {
+                Some(index) => args.get(index + 1).map(Some).ok_or_else(|| {
+                    GameError::ResourceLoadError(format!("The {} argument needs a value.", name))
+                }),
+                None => Ok(None),
+            }
+        };
+
+        let data_dir = match argument("--data-dir")? {
+            Some(data_dir) => PathBuf::from(data_dir),
+            None => match var(DATA_DIR_ENV).filter(|data_dir| !data_dir.is_empty()) {
+                Some(data_dir) => PathBuf::from(data_dir),
+                None => platform_data_dir(&var).ok_or_else(|| {
+                    GameError::ResourceLoadError(format!(
+                        "There is no data directory, set {} or use --data-dir.",
+                        DATA_DIR_ENV
+                    ))
+                })?,
+            },
+        };
+        let profile = argument("--profile")?.map_or(DEFAULT_PROFILE, |profile| profile.as_str());
+        Self::new(data_dir, profile)
+    }
+
+    pub fn profile_dir(&self) -> PathBuf {
+        self.data_dir.join(PROFILES_DIR_NAME).join(&self.profile)
+    }
+
+    pub fn settings_file(&self) -> String {
+        path_string(&self.data_dir.join(SETTINGS_FILE_NAME))
+    }
+
+    pub fn score_file(&self) -> String {
+        path_string(&self.profile_dir().join(SCORE_FILE_NAME))
+    }
+
+    pub fn stats_file(&self) -> String {
+        path_string(&self.profile_dir().join(STATS_FILE_NAME))
+    }
+
+    pub fn achievements_file(&self) -> String {
+        path_string(&self.profile_dir().join(ACHIEVEMENTS_FILE_NAME))
+    }
+
+    pub fn run_file(&self) -> String {
+        path_string(&self.profile_dir().join(RUN_FILE_NAME))
+    }
+
+    // Creates the directories on the first run. They are only readable by the
+    // user on unix. A file in the place of a directory is an error rather than
+    // something to replace.
+    pub fn create(&self) -> GameResult<()> {
+        let profile_dir = self.profile_dir();
+        if !profile_dir.is_dir() {
+            let mut builder = DirBuilder::new();
+            builder.recursive(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::DirBuilderExt;
+                builder.mode(0o700);
+            }
+            builder.create(&profile_dir).map_err(|e| {
+                GameError::ResourceLoadError(format!(
+                    "The directory {} could not be created: {}",
+                    profile_dir.display(),
+                    e
+                ))
+            })?;
+        }
+        if profile_dir.is_dir() {
+            Ok(())
+        } else {
+            Err(GameError::ResourceLoadError(format!(
+                "{} is not a directory.",
+                profile_dir.display()
+            )))
+        }
+    }
+
+    // The saves were kept in the working directory before. They are copied to
+    // the default profile once, the old files are left where they are.
+    pub fn import_legacy_files(&self, legacy_dir: &Path) -> GameResult<()> {
+        let mut files = vec![(SETTINGS_FILE_NAME, self.settings_file())];
+        if self.profile == DEFAULT_PROFILE {
+            files.push((SCORE_FILE_NAME, self.score_file()));
+            files.push((RUN_FILE_NAME, self.run_file()));
+        }
+        for (file_name, target) in files {
+            let source = legacy_dir.join(file_name);
+            if source.is_file() && !Path::new(&target).exists() {
+                fs::copy(&source, &target)?;
+            }
+        }
+        Ok(())
+    }
+
+    // The names of the profiles which were played, in alphabetical order.
+    pub fn profiles(&self) -> Vec<String> {
+        let mut profiles = fs::read_dir(self.data_dir.join(PROFILES_DIR_NAME))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_dir())
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .filter(|name| is_valid_profile_name(name))
+                    .collect::<Vec<String>>()
+            })
+            .unwrap_or_default();
+        profiles.sort();
+        profiles
+    }
+}
+
+// The profile name is a directory name, so it can't reach outside of the
+// profiles directory.
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_PROFILE_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// $XDG_DATA_HOME/brick_breaker or ~/.local/share/brick_breaker on Linux, the
+// Application Support directory on macOS and %APPDATA% on Windows.
+pub fn platform_data_dir(var: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    let absolute = |name: &str| {
+        var(name)
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+    };
+    let base = if cfg!(windows) {
+        absolute("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        absolute("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        absolute("XDG_DATA_HOME")
+            .or_else(|| absolute("HOME").map(|home| home.join(".local").join("share")))
+    };
+    base.map(|base| base.join(APP_DIR_NAME))
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_str().unwrap_or_default().to_string()
+}
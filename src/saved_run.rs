@@ -0,0 +1,368 @@
+use crate::game_settings_entities::{Score, ScoreDetails};
+use crate::gameplay_entities::{
+    Ball, BallState, BallWatchdog, Brick, BrickIndex, BrickKind, BrickState, BricksWall,
+    GameplayState, Size, Skateboard, SkateboardState,
+};
+use crate::levels;
+use crate::types::{GameError, GameResult, Point2, Vector2};
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::str::FromStr;
+
+pub const RUN_FILE_NAME: &str = "run.txt";
+
+// The level which was played when the game was closed, so the next launch can
+// continue it. In the run file every line is a name followed by its values:
+//   level <level>, score <score>, max_score <score>, endless_seed <seed>,
+//   level_seconds <seconds>
+//   ball <flying|crashing> <x> <y> <previous x> <previous y> <direction x> <direction y>
+//     <radius> <speed>
+//   skateboard <normal|rebound> <x> <y> <previous x> <previous y> <velocity x> <velocity y>
+//   watchdog <seconds without a brick> <nudges>
+//   wall <elapsed seconds> <brick width> <brick height>
+//   brick <normal|weak|unbreakable> <survived|touched|broken> <hit points> <hits left>
+//     <grid x> <grid y> <x> <y> <previous x> <previous y> <velocity x> <velocity y> [<path>]
+// where the path is written like in the level files. This line format is the
+// only format of the run file, the serde derives are not used for it: serde is
+// an optional feature while every build saves the run. So a new field of the
+// gameplay state has to be added here as well, the saved run test fails
+// until it is.
+#[derive(Debug, Clone)]
+pub struct SavedRun {
+    pub score: Score,
+    pub level_seconds: f32,
+    pub gameplay_state: GameplayState,
+}
+
+impl SavedRun {
+    pub fn capture(score_details: &ScoreDetails, gameplay_state: &GameplayState) -> Self {
+        SavedRun {
+            score: score_details.score,
+            level_seconds: score_details.level_seconds,
+            gameplay_state: gameplay_state.clone(),
+        }
+    }
+
+    // Puts the run back into the game. The max score stays the best one of the
+    // run and of the progress loaded since.
+    pub fn restore(self, score_details: &mut ScoreDetails, gameplay_state: &mut GameplayState) {
+        let max_score_result = score_details
+            .score
+            .max_score_result
+            .max(self.score.max_score_result);
+        score_details.score = self.score;
+        score_details.score.max_score_result = max_score_result;
+        score_details.level_seconds = self.level_seconds;
+        *gameplay_state = self.gameplay_state;
+    }
+
+    pub fn to_file_contents(&self) -> String {
+        let GameplayState {
+            skateboard,
+            bricks_wall,
+            ball,
+            watchdog,
+        } = &self.gameplay_state;
+        let mut lines = vec![
+            format!("level {}", self.score.level),
+            format!("score {}", self.score.score_result),
+            format!("max_score {}", self.score.max_score_result),
+        ];
+        if let Some(seed) = self.score.endless_seed {
+            lines.push(format!("endless_seed {}", seed));
+        }
+        lines.push(format!("level_seconds {}", self.level_seconds));
+
+        let ball_state = match ball.state {
+            BallState::Flying => "flying",
+            BallState::Crashing => "crashing",
+        };
+        lines.push(format!(
+            "ball {} {} {} {} {} {} {} {} {}",
+            ball_state,
+            ball.pos.x,
+            ball.pos.y,
+            ball.previous_pos.x,
+            ball.previous_pos.y,
+            ball.direction.x,
+            ball.direction.y,
+            ball.radius,
+            ball.speed
+        ));
+        let skateboard_state = match skateboard.state {
+            SkateboardState::Normal => "normal",
+            SkateboardState::Rebound => "rebound",
+        };
+        lines.push(format!(
+            "skateboard {} {} {} {} {} {} {}",
+            skateboard_state,
+            skateboard.pos.x,
+            skateboard.pos.y,
+            skateboard.previous_pos.x,
+            skateboard.previous_pos.y,
+            skateboard.velocity.x,
+            skateboard.velocity.y
+        ));
+        lines.push(format!(
+            "watchdog {} {}",
+            watchdog.seconds_without_brick, watchdog.nudges
+        ));
+        lines.push(format!(
+            "wall {} {} {}",
+            bricks_wall.elapsed, bricks_wall.brick_size.0, bricks_wall.brick_size.1
+        ));
+
+        for brick in &bricks_wall.bricks {
+            let kind = match brick.kind {
+                BrickKind::Normal => "normal",
+                BrickKind::Weak => "weak",
+                BrickKind::Unbreakable => "unbreakable",
+            };
+            let state = match brick.state {
+                BrickState::Survived => "survived",
+                BrickState::Touched => "touched",
+                BrickState::Broken => "broken",
+            };
+            let mut line = format!(
+                "brick {} {} {} {} {} {} {} {} {} {} {} {}",
+                kind,
+                state,
+                brick.hit_points,
+                brick.hits_left,
+                brick.base_pos.x,
+                brick.base_pos.y,
+                brick.pos.x,
+                brick.pos.y,
+                brick.previous_pos.x,
+                brick.previous_pos.y,
+                brick.velocity.x,
+                brick.velocity.y
+            );
+            if let Some(path) = brick.path {
+                line.push(' ');
+                line.push_str(&levels::format_path(path));
+            }
+            lines.push(line);
+        }
+
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+        contents
+    }
+
+    pub fn parse(contents: &str) -> GameResult<SavedRun> {
+        let mut level = None;
+        let mut score_result = None;
+        let mut max_score_result = None;
+        let mut endless_seed = None;
+        let mut level_seconds = None;
+        let mut ball = None;
+        let mut skateboard = None;
+        let mut watchdog = None;
+        let mut wall = None;
+        let mut bricks = Vec::new();
+
+        for line in contents.lines().map(|line| line.trim()) {
+            if line.is_empty() {
+                continue;
+            }
+            let invalid =
+                || GameError::ResourceLoadError(format!("Invalid line in the saved run: {}", line));
+            let words = line.split_whitespace().collect::<Vec<&str>>();
+            let point = |index: usize| -> GameResult<Point2<f32>> {
+                Ok(Point2 {
+                    x: number(&words, index, line)?,
+                    y: number(&words, index + 1, line)?,
+                })
+            };
+            let vector = |index: usize| -> GameResult<Vector2<f32>> {
+                let point = point(index)?;
+                Ok(Vector2 {
+                    x: point.x,
+                    y: point.y,
+                })
+            };
+
+            match (words[0], words.len()) {
+                ("level", 2) => level = Some(value(&words, 1, line)?),
+                ("score", 2) => score_result = Some(value(&words, 1, line)?),
+                ("max_score", 2) => max_score_result = Some(value(&words, 1, line)?),
+                ("endless_seed", 2) => endless_seed = Some(value(&words, 1, line)?),
+                ("level_seconds", 2) => level_seconds = Some(number(&words, 1, line)?),
+                ("ball", 10) => {
+                    ball = Some(Ball {
+                        state: match words[1] {
+                            "flying" => BallState::Flying,
+                            "crashing" => BallState::Crashing,
+                            _ => return Err(invalid()),
+                        },
+                        pos: point(2)?,
+                        previous_pos: point(4)?,
+                        direction: point(6)?,
+                        radius: number(&words, 8, line)?,
+                        speed: number(&words, 9, line)?,
+                    });
+                }
+                ("skateboard", 8) => {
+                    let mut saved = Skateboard::new(
+                        Size {
+                            height: 0.0,
+                            width: 0.0,
+                        },
+                        0.0,
+                        0.0,
+                    );
+                    saved.state = match words[1] {
+                        "normal" => SkateboardState::Normal,
+                        "rebound" => SkateboardState::Rebound,
+                        _ => return Err(invalid()),
+                    };
+                    saved.pos = point(2)?;
+                    saved.previous_pos = point(4)?;
+                    saved.velocity = vector(6)?;
+                    skateboard = Some(saved);
+                }
+                ("watchdog", 3) => {
+                    watchdog = Some(BallWatchdog {
+                        seconds_without_brick: number(&words, 1, line)?,
+                        nudges: value(&words, 2, line)?,
+                    });
+                }
+                ("wall", 4) => {
+                    wall = Some((
+                        number(&words, 1, line)?,
+                        (number(&words, 2, line)?, number(&words, 3, line)?),
+                    ))
+                }
+                ("brick", 13) | ("brick", 16) => {
+                    let kind = match words[1] {
+                        "normal" => BrickKind::Normal,
+                        "weak" => BrickKind::Weak,
+                        "unbreakable" => BrickKind::Unbreakable,
+                        _ => return Err(invalid()),
+                    };
+                    let mut brick = Brick::new(kind, point(5)?);
+                    brick.state = match words[2] {
+                        "survived" => BrickState::Survived,
+                        "touched" => BrickState::Touched,
+                        "broken" => BrickState::Broken,
+                        _ => return Err(invalid()),
+                    };
+                    brick.hit_points = value(&words, 3, line)?;
+                    brick.hits_left = value(&words, 4, line)?;
+                    if brick.hits_left > brick.hit_points {
+                        return Err(invalid());
+                    }
+                    brick.pos = point(7)?;
+                    brick.previous_pos = point(9)?;
+                    brick.velocity = vector(11)?;
+                    if words.len() > 13 {
+                        brick.path = Some(levels::parse_path(&words[13..]).ok_or_else(invalid)?);
+                    }
+                    bricks.push(brick);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        let score = match (level, score_result, max_score_result) {
+            (Some(level), Some(score_result), Some(max_score_result)) => Score {
+                score_result,
+                level,
+                max_score_result,
+                endless_seed,
+            },
+            _ => {
+                return Err(GameError::ResourceLoadError(String::from(
+                    "The saved run needs the level, the score and the max score.",
+                )))
+            }
+        };
+        if bricks.is_empty() {
+            return Err(GameError::ResourceLoadError(String::from(
+                "The saved run has no bricks.",
+            )));
+        }
+        match (ball, skateboard, watchdog, wall, level_seconds) {
+            (
+                Some(ball),
+                Some(skateboard),
+                Some(watchdog),
+                Some((elapsed, brick_size)),
+                Some(level_seconds),
+            ) => {
+                let mut bricks_wall = BricksWall {
+                    bricks,
+                    elapsed,
+                    brick_size,
+                    index: BrickIndex::default(),
+                    near_bricks: Vec::new(),
+                };
+                bricks_wall.rebuild_index();
+                Ok(SavedRun {
+                    score,
+                    level_seconds,
+                    gameplay_state: GameplayState {
+                        skateboard,
+                        bricks_wall,
+                        ball,
+                        watchdog,
+                    },
+                })
+            }
+            _ => Err(GameError::ResourceLoadError(String::from(
+                "The saved run needs the level seconds, the ball, the skateboard, the watchdog and the wall.",
+            ))),
+        }
+    }
+
+    // Reads the run from the file, the missing file means there is no run.
+    pub fn load(file_name: &str) -> GameResult<Option<SavedRun>> {
+        match fs::read_to_string(file_name) {
+            Ok(contents) => Self::parse(&contents).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    // The run is written next to the file and moved into its place, so a
+    // crash while saving leaves the previous run whole.
+    pub fn save(&self, file_name: &str) -> GameResult<()> {
+        let temp_file_name = format!("{}.tmp", file_name);
+        let mut file = File::create(&temp_file_name)?;
+        file.write_all(self.to_file_contents().as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp_file_name, file_name)?;
+        Ok(())
+    }
+
+    // The run is removed once it is continued or another run starts.
+    pub fn remove(file_name: &str) -> GameResult<()> {
+        match fs::remove_file(file_name) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn value<T: FromStr>(words: &[&str], index: usize, line: &str) -> GameResult<T> {
+    words
+        .get(index)
+        .and_then(|word| word.parse().ok())
+        .ok_or_else(|| {
+            GameError::ResourceLoadError(format!("Invalid line in the saved run: {}", line))
+        })
+}
+
+fn number(words: &[&str], index: usize, line: &str) -> GameResult<f32> {
+    value(words, index, line).and_then(|value: f32| {
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err(GameError::ResourceLoadError(format!(
+                "Invalid number in the saved run: {}",
+                line
+            )))
+        }
+    })
+}
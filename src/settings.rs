@@ -0,0 +1,309 @@
+use crate::theme::BUILT_IN_THEME_NAME;
+use crate::types::{GameError, GameResult, KeyCode};
+use std::fs::{self, File};
+use std::io::prelude::*;
+
+pub const SETTINGS_FILE_NAME: &str = "settings.txt";
+
+const BINDABLE_KEYS: [KeyCode; 33] = [
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Return,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+];
+
+pub fn key_name(keycode: KeyCode) -> String {
+    format!("{:?}", keycode)
+}
+
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS
+        .iter()
+        .find(|&&keycode| key_name(keycode) == name)
+        .copied()
+}
+
+pub fn is_bindable(keycode: KeyCode) -> bool {
+    BINDABLE_KEYS.contains(&keycode)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ControlScheme {
+    Keyboard,
+    Mouse,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|difficulty| difficulty.name() == name)
+    }
+
+    pub fn ball_speed_multiplier(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.3,
+        }
+    }
+
+    pub fn harder(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            _ => Difficulty::Hard,
+        }
+    }
+
+    pub fn easier(self) -> Self {
+        match self {
+            Difficulty::Hard => Difficulty::Normal,
+            _ => Difficulty::Easy,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct KeyBindings {
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub pause: KeyCode,
+}
+
+// The actions which can be bound to a key.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum KeyAction {
+    Left,
+    Right,
+    Pause,
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: KeyAction) -> KeyCode {
+        match action {
+            KeyAction::Left => self.left,
+            KeyAction::Right => self.right,
+            KeyAction::Pause => self.pause,
+        }
+    }
+
+    fn key_mut(&mut self, action: KeyAction) -> &mut KeyCode {
+        match action {
+            KeyAction::Left => &mut self.left,
+            KeyAction::Right => &mut self.right,
+            KeyAction::Pause => &mut self.pause,
+        }
+    }
+
+    // Binds the key to the action. The action which had the key before takes
+    // the previous key of this action, so a key never does two things.
+    pub fn bind(&mut self, action: KeyAction, keycode: KeyCode) {
+        let previous = self.key(action);
+        for other in &[KeyAction::Left, KeyAction::Right, KeyAction::Pause] {
+            if *other != action && self.key(*other) == keycode {
+                *self.key_mut(*other) = previous;
+            }
+        }
+        *self.key_mut(action) = keycode;
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        self.left == self.right || self.left == self.pause || self.right == self.pause
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            left: KeyCode::Left,
+            right: KeyCode::Right,
+            pause: KeyCode::P,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub volume: f32,
+    pub control_scheme: ControlScheme,
+    pub key_bindings: KeyBindings,
+    pub difficulty: Difficulty,
+    pub theme: String,
+    pub fullscreen: bool,
+    pub color_blind_mode: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            volume: 0.7,
+            control_scheme: ControlScheme::Keyboard,
+            key_bindings: KeyBindings::default(),
+            difficulty: Difficulty::Normal,
+            theme: String::from(BUILT_IN_THEME_NAME),
+            fullscreen: false,
+            color_blind_mode: false,
+        }
+    }
+}
+
+impl Settings {
+    pub const VOLUME_STEP: f32 = 0.1;
+
+    pub fn change_volume(&mut self, steps: f32) {
+        let volume = self.volume + steps * Self::VOLUME_STEP;
+        self.volume = (nalgebra::clamp(volume, 0.0, 1.0) * 10.0).round() / 10.0;
+    }
+
+    // Reads the settings from the file, the missing file means default settings.
+    pub fn load(file_name: &str) -> GameResult<Settings> {
+        match fs::read_to_string(file_name) {
+            Ok(contents) => Self::parse(&contents),
+            Err(_) => Ok(Settings::default()),
+        }
+    }
+
+    pub fn parse(contents: &str) -> GameResult<Settings> {
+        let mut settings = Settings::default();
+
+        for line in contents.lines().map(|line| line.trim()) {
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = match line.find('=') {
+                Some(index) => (line[..index].trim(), line[index + 1..].trim()),
+                None => return Err(settings_error(line)),
+            };
+
+            match key {
+                "volume" => {
+                    let volume = value
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|volume| volume.is_finite())
+                        .ok_or_else(|| settings_error(line))?;
+                    settings.volume = nalgebra::clamp(volume, 0.0, 1.0);
+                }
+                "control_scheme" => {
+                    settings.control_scheme = match value {
+                        "keyboard" => ControlScheme::Keyboard,
+                        "mouse" => ControlScheme::Mouse,
+                        _ => return Err(settings_error(line)),
+                    }
+                }
+                "key_left" => {
+                    settings.key_bindings.left =
+                        parse_key(value).ok_or_else(|| settings_error(line))?
+                }
+                "key_right" => {
+                    settings.key_bindings.right =
+                        parse_key(value).ok_or_else(|| settings_error(line))?
+                }
+                "key_pause" => {
+                    settings.key_bindings.pause =
+                        parse_key(value).ok_or_else(|| settings_error(line))?
+                }
+                "difficulty" => {
+                    settings.difficulty =
+                        Difficulty::from_name(value).ok_or_else(|| settings_error(line))?
+                }
+                "theme" => settings.theme = String::from(value),
+                "fullscreen" => settings.fullscreen = parse_bool(value, line)?,
+                "color_blind_mode" => settings.color_blind_mode = parse_bool(value, line)?,
+                _ => return Err(settings_error(line)),
+            }
+        }
+        if settings.key_bindings.has_conflicts() {
+            return Err(GameError::ResourceLoadError(String::from(
+                "Two actions are bound to the same key in the settings.",
+            )));
+        }
+        Ok(settings)
+    }
+
+    pub fn to_file_contents(&self) -> String {
+        let control_scheme = match self.control_scheme {
+            ControlScheme::Keyboard => "keyboard",
+            ControlScheme::Mouse => "mouse",
+        };
+        let difficulty = self.difficulty.name();
+
+        format!(
+            "volume = {}\ncontrol_scheme = {}\nkey_left = {}\nkey_right = {}\nkey_pause = {}\ndifficulty = {}\ntheme = {}\nfullscreen = {}\ncolor_blind_mode = {}\n",
+            self.volume,
+            control_scheme,
+            key_name(self.key_bindings.left),
+            key_name(self.key_bindings.right),
+            key_name(self.key_bindings.pause),
+            difficulty,
+            self.theme,
+            self.fullscreen,
+            self.color_blind_mode
+        )
+    }
+
+    pub fn save(&self, file_name: &str) -> GameResult<()> {
+        let mut file = File::create(file_name)?;
+        file.write_all(self.to_file_contents().as_bytes())?;
+        Ok(())
+    }
+}
+
+fn parse_bool(value: &str, line: &str) -> GameResult<bool> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(settings_error(line)),
+    }
+}
+
+fn settings_error(line: &str) -> GameError {
+    GameError::ResourceLoadError(format!("Invalid line in the settings: {}", line))
+}
@@ -0,0 +1,290 @@
+use crate::autopilot::{Autopilot, AutopilotSkill};
+use crate::gameplay_entities::{
+    Ball, BrickGrid, BrickKind, BricksWall, GameplayState, SpriteSizes,
+};
+use crate::level_generator;
+use crate::levels::{self, LevelLayout};
+use crate::settings::Difficulty;
+use crate::types::{GameError, GameResult};
+use std::fs;
+
+// A movement of the skateboard in a script, it is held from the given time of
+// the game until the next one.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ScriptedInput {
+    pub seconds: f32,
+    pub movement: f32,
+}
+
+// How the skateboard is moved in a simulated game.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Policy {
+    Tracking(AutopilotSkill),
+    Scripted(Vec<ScriptedInput>),
+}
+
+impl Policy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Policy::Tracking(skill) => skill.name(),
+            Policy::Scripted(_) => "script",
+        }
+    }
+
+    // Reads a script with a "seconds movement" pair on every line, the lines
+    // which start with ';' are comments.
+    pub fn parse_script(contents: &str) -> GameResult<Policy> {
+        let mut inputs = Vec::new();
+        for line in contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with(';'))
+        {
+            let values = line
+                .split_whitespace()
+                .map(|value| value.parse::<f32>().ok().filter(|value| value.is_finite()))
+                .collect::<Option<Vec<f32>>>();
+            match values.as_deref() {
+                Some([seconds, movement]) if *seconds >= 0.0 => inputs.push(ScriptedInput {
+                    seconds: *seconds,
+                    movement: nalgebra::clamp(*movement, -1.0, 1.0),
+                }),
+                _ => {
+                    return Err(GameError::ResourceLoadError(format!(
+                        "Invalid line in the script: {}",
+                        line
+                    )))
+                }
+            }
+        }
+        inputs.sort_by(|a, b| a.seconds.partial_cmp(&b.seconds).unwrap());
+        Ok(Policy::Scripted(inputs))
+    }
+
+    pub fn load_script(file_name: &str) -> GameResult<Policy> {
+        Self::parse_script(&fs::read_to_string(file_name)?)
+    }
+}
+
+// Where the walls of the simulated levels come from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LevelSource {
+    // The level files, the full default wall for a level without a file.
+    Files,
+    // The generated level of the seed of the game, as in an endless run.
+    Generated,
+    // The same layout file for every level.
+    File(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationConfig {
+    pub screen_size: (f32, f32),
+    pub sizes: SpriteSizes,
+    pub step_seconds: f32,
+    // A game which isn't cleared by then is stopped.
+    pub max_seconds: f32,
+    // The speed of the ball before the difficulty multiplier.
+    pub ball_speed: f32,
+    // The hits which break a normal brick, a weak brick always breaks from
+    // a single hit.
+    pub brick_hit_points: u8,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            screen_size: (800.0, 600.0),
+            sizes: SpriteSizes::HEADLESS,
+            step_seconds: 1.0 / 60.0,
+            max_seconds: 300.0,
+            ball_speed: Ball::SPEED,
+            brick_hit_points: BrickKind::Normal.hit_points(),
+        }
+    }
+}
+
+impl SimulationConfig {
+    pub fn brick_grid(&self) -> BrickGrid {
+        let (screen_width, screen_height) = self.screen_size;
+        let (brick_width, brick_height) = self.sizes.brick_survived;
+        BrickGrid::new(screen_width, screen_height, brick_height, brick_width)
+    }
+
+    pub fn level_layout(
+        &self,
+        source: &LevelSource,
+        level: i32,
+        seed: u64,
+    ) -> GameResult<LevelLayout> {
+        let grid = self.brick_grid();
+        let layout = match source {
+            LevelSource::Generated => {
+                let seed = level_generator::level_seed(seed, level);
+                return Ok(level_generator::generate(seed, grid.columns, grid.rows));
+            }
+            LevelSource::Files => LevelLayout::load(&levels::level_file_name(level))?,
+            LevelSource::File(file_name) => {
+                Some(LevelLayout::parse(&fs::read_to_string(file_name)?)?)
+            }
+        };
+        Ok(match layout {
+            Some(layout) => layout.resized(grid.columns, grid.rows),
+            None => LevelLayout::full(grid.columns, grid.rows),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameStats {
+    pub seed: u64,
+    pub level: i32,
+    pub difficulty: Difficulty,
+    pub policy: &'static str,
+    pub cleared: bool,
+    // The time to clear the level, or the time limit if it wasn't cleared.
+    pub seconds: f32,
+    pub balls_lost: u32,
+    pub bounces: u32,
+    pub bricks_broken: usize,
+}
+
+impl GameStats {
+    pub const CSV_HEADER: &'static str =
+        "seed,level,difficulty,policy,cleared,seconds,balls_lost,bounces,bricks_broken,bricks_per_minute";
+
+    pub fn bricks_per_minute(&self) -> f32 {
+        if self.seconds > 0.0 {
+            self.bricks_broken as f32 * 60.0 / self.seconds
+        } else {
+            0.0
+        }
+    }
+
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{:.2},{},{},{},{:.2}",
+            self.seed,
+            self.level,
+            self.difficulty.name(),
+            self.policy,
+            self.cleared,
+            self.seconds,
+            self.balls_lost,
+            self.bounces,
+            self.bricks_broken,
+            self.bricks_per_minute()
+        )
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"seed\":{},\"level\":{},\"difficulty\":\"{}\",\"policy\":\"{}\",\"cleared\":{},\"seconds\":{:.2},\"balls_lost\":{},\"bounces\":{},\"bricks_broken\":{},\"bricks_per_minute\":{:.2}}}",
+            self.seed,
+            self.level,
+            self.difficulty.name(),
+            self.policy,
+            self.cleared,
+            self.seconds,
+            self.balls_lost,
+            self.bounces,
+            self.bricks_broken,
+            self.bricks_per_minute()
+        )
+    }
+}
+
+pub fn stats_to_csv(stats: &[GameStats]) -> String {
+    let mut csv = format!("{}\n", GameStats::CSV_HEADER);
+    for game in stats {
+        csv.push_str(&game.to_csv_row());
+        csv.push('\n');
+    }
+    csv
+}
+
+pub fn stats_to_json(stats: &[GameStats]) -> String {
+    let games = stats
+        .iter()
+        .map(|game| format!("  {}", game.to_json()))
+        .collect::<Vec<String>>();
+    format!("[\n{}\n]\n", games.join(",\n"))
+}
+
+// Plays the layout without a window until all bricks are broken or the time
+// runs out. A lost ball is served again and counted, so the slow and the
+// clumsy games still give the time to clear the level.
+pub fn run_game(
+    config: &SimulationConfig,
+    layout: &LevelLayout,
+    difficulty: Difficulty,
+    policy: &Policy,
+    seed: u64,
+    level: i32,
+) -> GameResult<GameStats> {
+    let (screen_width, screen_height) = config.screen_size;
+    let sizes = &config.sizes;
+    let ball_speed = config.ball_speed * difficulty.ball_speed_multiplier();
+    let mut state = GameplayState::new(screen_width, screen_height, sizes);
+    state.bricks_wall = BricksWall::from_layout(&config.brick_grid(), layout);
+    state
+        .bricks_wall
+        .set_hit_points(BrickKind::Normal, config.brick_hit_points);
+    state.ball.speed = ball_speed;
+
+    let mut autopilot = match policy {
+        Policy::Tracking(skill) => Some(Autopilot::new(*skill, seed)),
+        Policy::Scripted(_) => None,
+    };
+    let mut stats = GameStats {
+        seed,
+        level,
+        difficulty,
+        policy: policy.name(),
+        cleared: false,
+        seconds: 0.0,
+        balls_lost: 0,
+        bounces: 0,
+        bricks_broken: 0,
+    };
+
+    while stats.seconds < config.max_seconds {
+        let movement = match policy {
+            Policy::Tracking(_) => autopilot.as_mut().map_or(0.0, |autopilot| {
+                autopilot
+                    .input(&state, sizes, screen_width, config.step_seconds)
+                    .movement
+            }),
+            Policy::Scripted(inputs) => inputs
+                .iter()
+                .take_while(|input| input.seconds <= stats.seconds)
+                .last()
+                .map_or(0.0, |input| input.movement),
+        };
+        let events = state.step(
+            config.step_seconds,
+            movement,
+            config.screen_size,
+            sizes,
+            false,
+        )?;
+        stats.seconds += config.step_seconds;
+
+        if events.bounced {
+            stats.bounces += 1;
+        }
+        if events.ball_lost {
+            stats.balls_lost += 1;
+            let served = GameplayState::new(screen_width, screen_height, sizes);
+            state.ball = served.ball;
+            state.ball.speed = ball_speed;
+            state.skateboard = served.skateboard;
+        }
+        if state.bricks_wall.all_bricks_are_broken() {
+            stats.cleared = true;
+            break;
+        }
+    }
+    stats.bricks_broken = state.bricks_wall.broken_bricks_count();
+    Ok(stats)
+}
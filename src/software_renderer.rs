@@ -0,0 +1,173 @@
+use crate::gameplay_entities::SpriteSizes;
+use crate::renderer::{self, FixedTextMetrics, RectStyle, Renderer, SpriteKind, TextMetrics};
+use crate::theme::Palette;
+use crate::types::{Color, GameResult, Point2, Rect};
+
+// Draws into an RGBA image in memory, without a window or a GPU. The sprites
+// are flat boxes of their sizes and every character of a text is a block, so
+// an image shows where things are drawn and in which colours, not the art of
+// the theme.
+#[derive(Debug, Clone)]
+pub struct SoftwareRenderer {
+    pub width: usize,
+    pub height: usize,
+    pub sizes: SpriteSizes,
+    pub palette: Palette,
+    pub metrics: FixedTextMetrics,
+    pixels: Vec<u8>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: usize, height: usize, sizes: SpriteSizes, palette: Palette) -> Self {
+        let mut renderer = SoftwareRenderer {
+            width,
+            height,
+            sizes,
+            palette,
+            metrics: FixedTextMetrics::default(),
+            pixels: vec![0; width * height * 4],
+        };
+        renderer.clear(palette.background);
+        renderer
+    }
+
+    // The pixels row by row, four bytes for every pixel.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let index = (y * self.width + x) * 4;
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+            self.pixels[index + 3],
+        ]
+    }
+
+    // Blends the colour over the pixels whose centers are inside the rectangle.
+    fn fill(&mut self, rect: Rect, color: Color) {
+        let x_range = pixel_range(rect.x, rect.x + rect.w, self.width);
+        let y_range = pixel_range(rect.y, rect.y + rect.h, self.height);
+        for y in y_range {
+            for x in x_range.clone() {
+                self.blend(x, y, color);
+            }
+        }
+    }
+
+    fn fill_disc(&mut self, rect: Rect, color: Color) {
+        let radius = rect.w.min(rect.h) / 2.0;
+        let (center_x, center_y) = (rect.x + rect.w / 2.0, rect.y + rect.h / 2.0);
+        for y in pixel_range(rect.y, rect.y + rect.h, self.height) {
+            for x in pixel_range(rect.x, rect.x + rect.w, self.width) {
+                let dx = x as f32 + 0.5 - center_x;
+                let dy = y as f32 + 0.5 - center_y;
+                if dx * dx + dy * dy <= radius * radius {
+                    self.blend(x, y, color);
+                }
+            }
+        }
+    }
+
+    fn blend(&mut self, x: usize, y: usize, color: Color) {
+        let index = (y * self.width + x) * 4;
+        let alpha = nalgebra::clamp(color.a, 0.0, 1.0);
+        for (channel, value) in [color.r, color.g, color.b].iter().enumerate() {
+            let old = self.pixels[index + channel] as f32 / 255.0;
+            let new = value * alpha + old * (1.0 - alpha);
+            self.pixels[index + channel] = (new * 255.0).round() as u8;
+        }
+        self.pixels[index + 3] = 255;
+    }
+}
+
+// The pixels whose centers are between the two coordinates.
+fn pixel_range(from: f32, to: f32, size: usize) -> std::ops::Range<usize> {
+    let first = (from - 0.5).ceil().max(0.0) as usize;
+    let last = ((to - 0.5).ceil().max(0.0) as usize).min(size);
+    first.min(last)..last
+}
+
+impl Renderer for SoftwareRenderer {
+    fn clear(&mut self, color: Color) {
+        let (r, g, b, a) = color.to_rgba();
+        for pixel in self.pixels.chunks_mut(4) {
+            pixel.copy_from_slice(&[r, g, b, a]);
+        }
+    }
+
+    fn sprite(&mut self, sprite: SpriteKind, dest: Point2<f32>, color: Color) -> GameResult<()> {
+        if sprite == SpriteKind::Background {
+            return Ok(());
+        }
+        let (width, height) = sprite.size(&self.sizes);
+        let rect = Rect::new(dest.x, dest.y, width, height);
+        let color = renderer::tinted(sprite.flat_color(), color);
+        if sprite == SpriteKind::Ball {
+            self.fill_disc(rect, color);
+        } else {
+            self.fill(rect, color);
+        }
+        Ok(())
+    }
+
+    fn rectangle(&mut self, rect: Rect, style: RectStyle, color: Color) -> GameResult<()> {
+        match style {
+            RectStyle::Fill => self.fill(rect, color),
+            RectStyle::Stroke(width) => {
+                let half = width / 2.0;
+                let (left, top) = (rect.x - half, rect.y - half);
+                let (outer_w, outer_h) = (rect.w + width, rect.h + width);
+                self.fill(Rect::new(left, top, outer_w, width), color);
+                self.fill(
+                    Rect::new(left, top + outer_h - width, outer_w, width),
+                    color,
+                );
+                self.fill(
+                    Rect::new(left, top + width, width, outer_h - 2.0 * width),
+                    color,
+                );
+                self.fill(
+                    Rect::new(
+                        left + outer_w - width,
+                        top + width,
+                        width,
+                        outer_h - 2.0 * width,
+                    ),
+                    color,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, _key: &str, label: &str, top_left: Point2<f32>) -> GameResult<()> {
+        let color = self.palette.text;
+        let FixedTextMetrics {
+            glyph_width,
+            glyph_height,
+        } = self.metrics;
+        for (index, character) in label.chars().enumerate() {
+            if character.is_whitespace() {
+                continue;
+            }
+            let x = top_left.x + index as f32 * glyph_width;
+            let glyph = Rect::new(
+                x + 2.0,
+                top_left.y + 6.0,
+                glyph_width - 4.0,
+                glyph_height - 10.0,
+            );
+            self.fill(glyph, color);
+        }
+        Ok(())
+    }
+}
+
+impl TextMetrics for SoftwareRenderer {
+    fn text_size(&mut self, key: &str, label: &str) -> (f32, f32) {
+        self.metrics.text_size(key, label)
+    }
+}
@@ -0,0 +1,142 @@
+use crate::gameplay_entities::{BrokenBricks, StepEvents};
+use crate::types::{GameError, GameResult};
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::ErrorKind;
+
+pub const STATS_FILE_NAME: &str = "stats.txt";
+
+// The lifetime statistics of a profile, collected from the events of the
+// played levels. In the stats file every line is a name followed by its value:
+//   games_played <count>, bricks_normal <count>, bricks_weak <count>,
+//   balls_lost <count>, longest_rally <hits>, best_combo <bricks>,
+//   play_seconds <seconds>
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Statistics {
+    pub games_played: usize,
+    pub bricks_broken: BrokenBricks,
+    pub balls_lost: usize,
+    // The most bounces off the skateboard between two lost balls.
+    pub longest_rally: usize,
+    // The most bricks broken without touching the skateboard.
+    pub best_combo: usize,
+    pub play_seconds: f64,
+    // The rally, the combo and the cleared levels of the running game, they
+    // are not saved.
+    rally: usize,
+    combo: usize,
+    clears: usize,
+}
+
+impl Statistics {
+    pub fn record_game_start(&mut self) {
+        self.games_played += 1;
+        self.rally = 0;
+        self.combo = 0;
+        self.clears = 0;
+    }
+
+    pub fn record_clear(&mut self) {
+        self.clears += 1;
+    }
+
+    pub fn clears_in_game(&self) -> usize {
+        self.clears
+    }
+
+    // Counts the events of a single update of a played level.
+    pub fn record_step(&mut self, events: &StepEvents, seconds: f32) {
+        self.play_seconds += f64::from(seconds);
+        self.bricks_broken.add(events.bricks_broken);
+        self.combo += events.bricks_broken.total();
+        self.best_combo = self.best_combo.max(self.combo);
+        if events.board_bounced {
+            self.rally += 1;
+            self.longest_rally = self.longest_rally.max(self.rally);
+            self.combo = 0;
+        }
+        if events.ball_lost {
+            self.balls_lost += 1;
+            self.rally = 0;
+            self.combo = 0;
+        }
+    }
+
+    // A new profile has no file yet and starts without statistics.
+    pub fn load(file_name: &str) -> GameResult<Statistics> {
+        match fs::read_to_string(file_name) {
+            Ok(contents) => Self::parse(&contents),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Statistics::default()),
+            Err(_) => Err(GameError::ResourceLoadError(format!(
+                "The file {} could not be read.",
+                file_name
+            ))),
+        }
+    }
+
+    pub fn parse(contents: &str) -> GameResult<Statistics> {
+        let mut statistics = Statistics::default();
+        for line in contents.lines().map(|line| line.trim()) {
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || {
+                GameError::ResourceLoadError(format!("Invalid line in the statistics: {}", line))
+            };
+            let words = line.split_whitespace().collect::<Vec<&str>>();
+            if words.len() != 2 {
+                return Err(invalid());
+            }
+            let count = || words[1].parse::<usize>().map_err(|_| invalid());
+            match words[0] {
+                "games_played" => statistics.games_played = count()?,
+                "bricks_normal" => statistics.bricks_broken.normal = count()?,
+                "bricks_weak" => statistics.bricks_broken.weak = count()?,
+                "balls_lost" => statistics.balls_lost = count()?,
+                "longest_rally" => statistics.longest_rally = count()?,
+                "best_combo" => statistics.best_combo = count()?,
+                "play_seconds" => {
+                    statistics.play_seconds = words[1]
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                        .ok_or_else(invalid)?
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(statistics)
+    }
+
+    pub fn to_file_contents(&self) -> String {
+        let lines = [
+            format!("games_played {}", self.games_played),
+            format!("bricks_normal {}", self.bricks_broken.normal),
+            format!("bricks_weak {}", self.bricks_broken.weak),
+            format!("balls_lost {}", self.balls_lost),
+            format!("longest_rally {}", self.longest_rally),
+            format!("best_combo {}", self.best_combo),
+            format!("play_seconds {}", self.play_seconds),
+        ];
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+        contents
+    }
+
+    pub fn save(&self, file_name: &str) -> GameResult<()> {
+        let mut file = File::create(file_name)?;
+        file.write_all(self.to_file_contents().as_bytes())?;
+        Ok(())
+    }
+
+    // The play time as hours, minutes and seconds.
+    pub fn play_time_text(&self) -> String {
+        let seconds = self.play_seconds as u64;
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    }
+}
@@ -0,0 +1,222 @@
+use crate::gameplay_entities::SpriteSizes;
+use crate::renderer::{self, RectStyle, Renderer, SpriteKind, TextMetrics};
+use crate::theme::Palette;
+use crate::types::{Color, GameResult, Point2, Rect};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cell {
+    pub character: char,
+    pub foreground: Color,
+    // None keeps the background of the terminal.
+    pub background: Option<Color>,
+}
+
+// Draws into a grid of character cells, which a terminal front end prints.
+// The screen of the game is scaled down to the grid, the sprites become block
+// characters in their flat colours and every character of a text takes a cell.
+#[derive(Debug, Clone)]
+pub struct TerminalRenderer {
+    pub columns: usize,
+    pub rows: usize,
+    pub screen_size: (f32, f32),
+    pub sizes: SpriteSizes,
+    pub palette: Palette,
+    cells: Vec<Cell>,
+}
+
+impl TerminalRenderer {
+    // A brick of the headless sizes takes six cells of a row.
+    pub const COLUMNS: usize = 80;
+    pub const ROWS: usize = 30;
+
+    pub fn new(
+        columns: usize,
+        rows: usize,
+        screen_size: (f32, f32),
+        sizes: SpriteSizes,
+        palette: Palette,
+    ) -> Self {
+        // A terminal can report no columns or rows while it is resized.
+        let mut renderer = TerminalRenderer {
+            columns: columns.max(1),
+            rows: rows.max(1),
+            screen_size,
+            sizes,
+            palette,
+            cells: Vec::new(),
+        };
+        renderer.clear(palette.background);
+        renderer
+    }
+
+    pub fn cell(&self, column: usize, row: usize) -> Cell {
+        self.cells[row * self.columns + column]
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    // The characters of the grid row by row, without the colours.
+    pub fn lines(&self) -> Vec<String> {
+        self.cells
+            .chunks(self.columns)
+            .map(|row| row.iter().map(|cell| cell.character).collect())
+            .collect()
+    }
+
+    pub fn cell_width(&self) -> f32 {
+        self.screen_size.0 / self.columns as f32
+    }
+
+    pub fn cell_height(&self) -> f32 {
+        self.screen_size.1 / self.rows as f32
+    }
+
+    // The cells whose centers are inside the rectangle. A rectangle smaller
+    // than a cell still takes the cell of its center, so the ball is never lost.
+    fn cell_ranges(&self, rect: Rect) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let (cell_width, cell_height) = (self.cell_width(), self.cell_height());
+        let columns = cell_range(
+            rect.x / cell_width,
+            (rect.x + rect.w) / cell_width,
+            self.columns,
+        );
+        let rows = cell_range(
+            rect.y / cell_height,
+            (rect.y + rect.h) / cell_height,
+            self.rows,
+        );
+        (columns, rows)
+    }
+
+    fn set(&mut self, column: usize, row: usize, character: char, foreground: Color) {
+        let cell = &mut self.cells[row * self.columns + column];
+        cell.character = character;
+        cell.foreground = foreground;
+    }
+}
+
+fn cell_range(from: f32, to: f32, size: usize) -> std::ops::Range<usize> {
+    let first = (from - 0.5).ceil().max(0.0) as usize;
+    let last = ((to - 0.5).ceil().max(0.0) as usize).min(size);
+    if first < last {
+        first..last
+    } else {
+        let center = (((from + to) / 2.0).max(0.0) as usize).min(size);
+        center..(center + 1).min(size)
+    }
+}
+
+fn mixed(color: Color, under: Color) -> Color {
+    let alpha = nalgebra::clamp(color.a, 0.0, 1.0);
+    let mix = |new: f32, old: f32| new * alpha + old * (1.0 - alpha);
+    Color::new(
+        mix(color.r, under.r),
+        mix(color.g, under.g),
+        mix(color.b, under.b),
+        1.0,
+    )
+}
+
+impl Renderer for TerminalRenderer {
+    fn clear(&mut self, color: Color) {
+        let cell = Cell {
+            character: ' ',
+            foreground: self.palette.text,
+            background: Some(color),
+        };
+        self.cells = vec![cell; self.columns * self.rows];
+    }
+
+    fn sprite(&mut self, sprite: SpriteKind, dest: Point2<f32>, color: Color) -> GameResult<()> {
+        let character = match sprite {
+            SpriteKind::Background => return Ok(()),
+            SpriteKind::Ball => '●',
+            SpriteKind::SkateboardNormal | SpriteKind::SkateboardRebound => '▀',
+            SpriteKind::BrickSurvived => '█',
+            SpriteKind::BrickTouched => '▓',
+        };
+        let color = renderer::tinted(sprite.flat_color(), color);
+        if color.a <= 0.0 {
+            return Ok(());
+        }
+        let (width, height) = sprite.size(&self.sizes);
+        let (columns, rows) = self.cell_ranges(Rect::new(dest.x, dest.y, width, height));
+        for row in rows {
+            for column in columns.clone() {
+                self.set(column, row, character, color);
+            }
+        }
+        Ok(())
+    }
+
+    fn rectangle(&mut self, rect: Rect, style: RectStyle, color: Color) -> GameResult<()> {
+        let (columns, rows) = self.cell_ranges(rect);
+        if columns.is_empty() || rows.is_empty() {
+            return Ok(());
+        }
+        match style {
+            // A translucent fill dims what is under it, an opaque one hides it.
+            RectStyle::Fill => {
+                for row in rows {
+                    for column in columns.clone() {
+                        let cell = &mut self.cells[row * self.columns + column];
+                        let under = cell.background.unwrap_or(self.palette.background);
+                        cell.background = Some(mixed(color, under));
+                        if color.a >= 1.0 {
+                            cell.character = ' ';
+                        } else {
+                            cell.foreground = mixed(color, cell.foreground);
+                        }
+                    }
+                }
+            }
+            RectStyle::Stroke(_) => {
+                let (left, right) = (columns.start, columns.end - 1);
+                let (top, bottom) = (rows.start, rows.end - 1);
+                for column in left..=right {
+                    self.set(column, top, '─', color);
+                    self.set(column, bottom, '─', color);
+                }
+                for row in top..=bottom {
+                    self.set(left, row, '│', color);
+                    self.set(right, row, '│', color);
+                }
+                if left < right && top < bottom {
+                    self.set(left, top, '┌', color);
+                    self.set(right, top, '┐', color);
+                    self.set(left, bottom, '└', color);
+                    self.set(right, bottom, '┘', color);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, _key: &str, label: &str, top_left: Point2<f32>) -> GameResult<()> {
+        let row = (top_left.y / self.cell_height()).round().max(0.0) as usize;
+        let first_column = (top_left.x / self.cell_width()).round().max(0.0) as usize;
+        if row >= self.rows {
+            return Ok(());
+        }
+        let color = self.palette.text;
+        for (index, character) in label.chars().enumerate() {
+            let column = first_column + index;
+            if column >= self.columns {
+                break;
+            }
+            self.set(column, row, character, color);
+        }
+        Ok(())
+    }
+}
+
+impl TextMetrics for TerminalRenderer {
+    fn text_size(&mut self, _key: &str, label: &str) -> (f32, f32) {
+        (
+            label.chars().count() as f32 * self.cell_width(),
+            self.cell_height(),
+        )
+    }
+}
@@ -0,0 +1,247 @@
+use crate::types::{Color, GameError, GameResult, BLACK, WHITE};
+#[cfg(feature = "ggez")]
+use ggez::filesystem;
+#[cfg(feature = "ggez")]
+use ggez::Context;
+use std::collections::HashMap;
+#[cfg(feature = "ggez")]
+use std::io::Read;
+
+pub const THEMES_DIR: &str = "/themes";
+pub const MANIFEST_FILE_NAME: &str = "theme.txt";
+pub const BUILT_IN_THEME_NAME: &str = "classic";
+
+pub const BRICK_SURVIVED: &str = "brick_survived";
+pub const BRICK_TOUCHED: &str = "brick_touched";
+pub const SKATEBOARD_NORMAL: &str = "skateboard_normal";
+pub const SKATEBOARD_REBOUND: &str = "skateboard_rebound";
+pub const BALL_FLYING: &str = "ball_flying";
+pub const BACKGROUND: &str = "background";
+pub const FONT: &str = "font";
+pub const BOUNCE_SOUND: &str = "bounce_sound";
+
+// The keys of the manifest which name files, the other keys are colours.
+const ASSET_KEYS: [&str; 8] = [
+    BRICK_SURVIVED,
+    BRICK_TOUCHED,
+    SKATEBOARD_NORMAL,
+    SKATEBOARD_REBOUND,
+    BALL_FLYING,
+    BACKGROUND,
+    FONT,
+    BOUNCE_SOUND,
+];
+
+const BUILT_IN_FILES: [(&str, &str); 7] = [
+    (BRICK_SURVIVED, "/brick_survived.png"),
+    (BRICK_TOUCHED, "/brick_touched.png"),
+    (SKATEBOARD_NORMAL, "/skateboard_normal_1.png"),
+    (SKATEBOARD_REBOUND, "/skateboard_rebound.png"),
+    (BALL_FLYING, "/ball_flying.png"),
+    (FONT, "/DejaVuSerif.ttf"),
+    (BOUNCE_SOUND, "/bounce.ogg"),
+];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Palette {
+    pub background: Color,
+    pub text: Color,
+    pub highlight: Color,
+    pub touched_brick: Color,
+    pub unbreakable_brick: Color,
+    pub debug_outline: Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette {
+            background: BLACK,
+            text: WHITE,
+            highlight: Color::from_rgb(70, 110, 200),
+            touched_brick: WHITE,
+            unbreakable_brick: Color::from_rgb(120, 120, 120),
+            debug_outline: Color::from_rgb(255, 0, 0),
+        }
+    }
+}
+
+impl Palette {
+    // Replaces the colours which carry meaning with ones from the Okabe-Ito
+    // palette, which stay distinguishable with the common colour vision deficiencies.
+    pub fn color_blind(self) -> Self {
+        Palette {
+            highlight: Color::from_rgb(230, 159, 0),
+            touched_brick: Color::from_rgb(86, 180, 233),
+            debug_outline: Color::from_rgb(213, 94, 0),
+            ..self
+        }
+    }
+}
+
+// A theme is a directory in resources/themes with a theme.txt manifest.
+// The manifest has one "key = value" pair per line, where the keys are the
+// asset names above (the value is a file in the theme directory) or one of the
+// palette colours (background_color, text_color, highlight_color,
+// touched_brick_color, unbreakable_brick_color and debug_outline_color as #rrggbb).
+// Every asset which is not listed falls back to the built-in one.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub files: HashMap<String, String>,
+    pub palette: Palette,
+}
+
+impl Theme {
+    pub fn built_in() -> Self {
+        Theme {
+            name: String::from(BUILT_IN_THEME_NAME),
+            files: HashMap::new(),
+            palette: Palette::default(),
+        }
+    }
+
+    #[cfg(feature = "ggez")]
+    pub fn load(ctx: &mut Context, name: &str) -> GameResult<Theme> {
+        if name == BUILT_IN_THEME_NAME {
+            return Ok(Self::built_in());
+        }
+
+        let manifest_path = format!("{}/{}/{}", THEMES_DIR, name, MANIFEST_FILE_NAME);
+        let mut manifest = String::new();
+        filesystem::open(ctx, &manifest_path)?.read_to_string(&mut manifest)?;
+        Self::parse(name, &manifest)
+    }
+
+    pub fn parse(name: &str, manifest: &str) -> GameResult<Theme> {
+        let mut theme = Theme {
+            name: String::from(name),
+            ..Self::built_in()
+        };
+
+        for (number, line) in manifest.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.find('=') {
+                Some(index) => (line[..index].trim(), line[index + 1..].trim()),
+                None => {
+                    return Err(GameError::ResourceLoadError(format!(
+                        "Line {} of the manifest of the theme {} is not a \"key = value\" pair.",
+                        number + 1,
+                        name
+                    )))
+                }
+            };
+
+            match key {
+                "background_color" => theme.palette.background = parse_color(value)?,
+                "text_color" => theme.palette.text = parse_color(value)?,
+                "highlight_color" => theme.palette.highlight = parse_color(value)?,
+                "touched_brick_color" => theme.palette.touched_brick = parse_color(value)?,
+                "unbreakable_brick_color" => theme.palette.unbreakable_brick = parse_color(value)?,
+                "debug_outline_color" => theme.palette.debug_outline = parse_color(value)?,
+                key if ASSET_KEYS.contains(&key) => {
+                    theme.files.insert(
+                        String::from(key),
+                        format!("{}/{}/{}", THEMES_DIR, name, value),
+                    );
+                }
+                _ => {
+                    return Err(GameError::ResourceLoadError(format!(
+                        "Line {} of the manifest of the theme {} has the unknown key {}.",
+                        number + 1,
+                        name,
+                        key
+                    )))
+                }
+            }
+        }
+        Ok(theme)
+    }
+
+    // The path of the file of the theme if it provides one, otherwise the built-in file.
+    #[cfg(feature = "ggez")]
+    pub fn asset_path(&self, ctx: &Context, asset: &str) -> Option<String> {
+        match self.files.get(asset) {
+            Some(path) if filesystem::exists(ctx, path) => Some(path.clone()),
+            _ => built_in_path(asset),
+        }
+    }
+
+    #[cfg(feature = "ggez")]
+    pub fn available(ctx: &mut Context) -> Vec<String> {
+        let mut names = vec![String::from(BUILT_IN_THEME_NAME)];
+        if let Ok(directories) = filesystem::read_dir(ctx, THEMES_DIR) {
+            let mut found = directories
+                .filter(|directory| filesystem::exists(ctx, directory.join(MANIFEST_FILE_NAME)))
+                .filter_map(|directory| {
+                    directory
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                })
+                .collect::<Vec<String>>();
+            found.sort();
+            names.extend(found);
+        }
+        names
+    }
+}
+
+pub fn built_in_path(asset: &str) -> Option<String> {
+    BUILT_IN_FILES
+        .iter()
+        .find(|(name, _)| *name == asset)
+        .map(|(_, path)| String::from(*path))
+}
+
+pub fn parse_color(value: &str) -> GameResult<Color> {
+    let hex = value.trim_start_matches('#');
+    let channel = |index: usize| {
+        hex.get(index..index + 2)
+            .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+    };
+
+    match (hex.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(r), Some(g), Some(b)) => Ok(Color::from_rgb(r, g, b)),
+        _ => Err(GameError::ResourceLoadError(format!(
+            "The colour {} is not in the #rrggbb format.",
+            value
+        ))),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ThemeSelector {
+    pub names: Vec<String>,
+    pub current: usize,
+}
+
+impl ThemeSelector {
+    pub fn new(names: Vec<String>, selected: &str) -> Self {
+        let current = names.iter().position(|name| name == selected).unwrap_or(0);
+        ThemeSelector { names, current }
+    }
+
+    pub fn current_name(&self) -> &str {
+        self.names
+            .get(self.current)
+            .map(|name| name.as_str())
+            .unwrap_or(BUILT_IN_THEME_NAME)
+    }
+
+    pub fn select_next(&mut self) -> &str {
+        if !self.names.is_empty() {
+            self.current = (self.current + 1) % self.names.len();
+        }
+        self.current_name()
+    }
+
+    pub fn select_previous(&mut self) -> &str {
+        if !self.names.is_empty() {
+            self.current = (self.current + self.names.len() - 1) % self.names.len();
+        }
+        self.current_name()
+    }
+}
@@ -0,0 +1,34 @@
+// A fixed timestep accumulator. The time of every frame is added up and the
+// game is updated in steps of the same length, so it plays the same at any
+// frame rate. When the game falls behind by more than max_steps steps the
+// rest of the time is dropped, so a long hitch slows the game down once
+// instead of running many steps to catch up.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FixedTimestep {
+    pub step_seconds: f32,
+    pub max_steps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(steps_per_second: u32, max_steps: u32) -> Self {
+        FixedTimestep {
+            step_seconds: 1.0 / steps_per_second as f32,
+            max_steps,
+            accumulator: 0.0,
+        }
+    }
+
+    // Adds the time of a frame and returns how many steps to run for it.
+    pub fn advance(&mut self, frame_seconds: f32) -> u32 {
+        self.accumulator += frame_seconds.max(0.0);
+        let steps = (self.accumulator / self.step_seconds) as u32;
+        self.accumulator -= steps as f32 * self.step_seconds;
+        steps.min(self.max_steps)
+    }
+
+    // How far the time is between the last step and the next one, from 0 to 1.
+    pub fn alpha(&self) -> f32 {
+        nalgebra::clamp(self.accumulator / self.step_seconds, 0.0, 1.0)
+    }
+}
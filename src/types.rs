@@ -0,0 +1,246 @@
+// The types shared by the engine and the front ends. With the ggez feature
+// they are the types of ggez, so the entities draw into the window without
+// conversions. Without it the engine builds with the plain types below, which
+// keep the names, fields and behaviour of the ggez ones it uses.
+pub use mint::{Point2, Vector2};
+
+#[cfg(feature = "ggez")]
+pub use ggez::event::KeyCode;
+#[cfg(feature = "ggez")]
+pub use ggez::graphics::{Color, Rect, BLACK, WHITE};
+#[cfg(feature = "ggez")]
+pub use ggez::{GameError, GameResult};
+
+#[cfg(not(feature = "ggez"))]
+pub use self::plain::*;
+
+// The mint types serialize as arrays by themselves. The points and vectors of
+// the entities are written as {"x": .., "y": ..} with
+// #[serde(with = "crate::types::serde_point2")] instead, which reads better
+// and doesn't mix them up with the sizes, which are tuples.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerdeXy {
+    x: f32,
+    y: f32,
+}
+
+#[cfg(feature = "serde")]
+pub mod serde_point2 {
+    use super::{Point2, SerdeXy};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(point: &Point2<f32>, serializer: S) -> Result<S::Ok, S::Error> {
+        SerdeXy {
+            x: point.x,
+            y: point.y,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Point2<f32>, D::Error> {
+        let SerdeXy { x, y } = SerdeXy::deserialize(deserializer)?;
+        Ok(Point2 { x, y })
+    }
+}
+
+#[cfg(feature = "serde")]
+pub mod serde_vector2 {
+    use super::{SerdeXy, Vector2};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        vector: &Vector2<f32>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        SerdeXy {
+            x: vector.x,
+            y: vector.y,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vector2<f32>, D::Error> {
+        let SerdeXy { x, y } = SerdeXy::deserialize(deserializer)?;
+        Ok(Vector2 { x, y })
+    }
+}
+
+#[cfg(not(feature = "ggez"))]
+mod plain {
+    use super::Point2;
+    use std::fmt;
+    use std::io;
+    use std::sync::Arc;
+
+    #[derive(Debug, Clone)]
+    pub enum GameError {
+        EventLoopError(String),
+        ResourceLoadError(String),
+        IOError(Arc<io::Error>),
+    }
+
+    impl fmt::Display for GameError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                GameError::EventLoopError(message) => write!(f, "Event loop error: {}", message),
+                GameError::ResourceLoadError(message) => {
+                    write!(f, "Error loading resource: {}", message)
+                }
+                GameError::IOError(e) => write!(f, "IO error: {}", e),
+            }
+        }
+    }
+
+    impl std::error::Error for GameError {}
+
+    impl From<io::Error> for GameError {
+        fn from(e: io::Error) -> Self {
+            GameError::IOError(Arc::new(e))
+        }
+    }
+
+    pub type GameResult<T = ()> = Result<T, GameError>;
+
+    // The channels are from 0 to 1.
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    pub struct Color {
+        pub r: f32,
+        pub g: f32,
+        pub b: f32,
+        pub a: f32,
+    }
+
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+
+    impl Color {
+        pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+            Color { r, g, b, a }
+        }
+
+        pub fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+            Self::from_rgba(r, g, b, 255)
+        }
+
+        pub fn from_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+            Color::new(
+                r as f32 / 255.0,
+                g as f32 / 255.0,
+                b as f32 / 255.0,
+                a as f32 / 255.0,
+            )
+        }
+
+        pub fn to_rgba(self) -> (u8, u8, u8, u8) {
+            (
+                (self.r * 255.0) as u8,
+                (self.g * 255.0) as u8,
+                (self.b * 255.0) as u8,
+                (self.a * 255.0) as u8,
+            )
+        }
+
+        pub fn to_rgb(self) -> (u8, u8, u8) {
+            let (r, g, b, _) = self.to_rgba();
+            (r, g, b)
+        }
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq, Default)]
+    pub struct Rect {
+        pub x: f32,
+        pub y: f32,
+        pub w: f32,
+        pub h: f32,
+    }
+
+    impl Rect {
+        pub const fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+            Rect { x, y, w, h }
+        }
+
+        pub fn left(&self) -> f32 {
+            self.x
+        }
+
+        pub fn right(&self) -> f32 {
+            self.x + self.w
+        }
+
+        pub fn top(&self) -> f32 {
+            self.y
+        }
+
+        pub fn bottom(&self) -> f32 {
+            self.y + self.h
+        }
+
+        pub fn point(&self) -> Point2<f32> {
+            Point2 {
+                x: self.x,
+                y: self.y,
+            }
+        }
+
+        pub fn contains<P: Into<Point2<f32>>>(&self, point: P) -> bool {
+            let point = point.into();
+            point.x >= self.left()
+                && point.x <= self.right()
+                && point.y <= self.bottom()
+                && point.y >= self.top()
+        }
+
+        pub fn overlaps(&self, other: &Rect) -> bool {
+            self.left() <= other.right()
+                && self.right() >= other.left()
+                && self.top() <= other.bottom()
+                && self.bottom() >= other.top()
+        }
+    }
+
+    // The keys the engine knows by name, named as the winit keys of ggez so
+    // the settings files are the same with and without the window.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    pub enum KeyCode {
+        A,
+        B,
+        C,
+        D,
+        E,
+        F,
+        G,
+        H,
+        I,
+        J,
+        K,
+        L,
+        M,
+        N,
+        O,
+        P,
+        Q,
+        R,
+        S,
+        T,
+        U,
+        V,
+        W,
+        X,
+        Y,
+        Z,
+        Escape,
+        Left,
+        Up,
+        Right,
+        Down,
+        Back,
+        Return,
+        Space,
+        Tab,
+    }
+}
@@ -0,0 +1,350 @@
+use crate::renderer::{RectStyle, Renderer};
+use crate::theme::Palette;
+use crate::types::{GameResult, KeyCode, Point2, Rect};
+#[cfg(feature = "ggez")]
+use ggez::event::Button as GamepadButton;
+use std::collections::VecDeque;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Navigation {
+    Previous,
+    Next,
+    Decrease,
+    Increase,
+    Activate,
+    Back,
+}
+
+pub fn navigation_from_key(keycode: KeyCode) -> Option<Navigation> {
+    match keycode {
+        KeyCode::Up => Some(Navigation::Previous),
+        KeyCode::Down | KeyCode::Tab => Some(Navigation::Next),
+        KeyCode::Left => Some(Navigation::Decrease),
+        KeyCode::Right => Some(Navigation::Increase),
+        KeyCode::Space | KeyCode::Return => Some(Navigation::Activate),
+        KeyCode::Escape | KeyCode::Back => Some(Navigation::Back),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "ggez")]
+pub fn navigation_from_gamepad(button: GamepadButton) -> Option<Navigation> {
+    match button {
+        GamepadButton::DPadUp => Some(Navigation::Previous),
+        GamepadButton::DPadDown => Some(Navigation::Next),
+        GamepadButton::DPadLeft => Some(Navigation::Decrease),
+        GamepadButton::DPadRight => Some(Navigation::Increase),
+        GamepadButton::South | GamepadButton::Start => Some(Navigation::Activate),
+        GamepadButton::East | GamepadButton::Select => Some(Navigation::Back),
+        _ => None,
+    }
+}
+
+// Places items of the given sizes one under another, centered horizontally on
+// the screen, with the whole column centered vertically.
+pub fn centered_column(
+    sizes: &[(f32, f32)],
+    screen_width: f32,
+    screen_height: f32,
+    spacing: f32,
+) -> Vec<Rect> {
+    let total_height = sizes.iter().map(|(_, height)| height).sum::<f32>()
+        + spacing * (sizes.len().saturating_sub(1) as f32);
+
+    let mut top = (screen_height - total_height) / 2.0;
+    sizes
+        .iter()
+        .map(|&(width, height)| {
+            let rect = Rect::new((screen_width - width) / 2.0, top, width, height);
+            top += height + spacing;
+            rect
+        })
+        .collect()
+}
+
+// Places the given number of cells of the same size in rows of the given
+// number of columns, with the whole grid centered on the screen.
+pub fn centered_grid(
+    count: usize,
+    columns: usize,
+    cell_size: (f32, f32),
+    screen_width: f32,
+    screen_height: f32,
+    spacing: f32,
+) -> Vec<Rect> {
+    let columns = columns.max(1);
+    let rows = count.div_ceil(columns);
+    let (cell_width, cell_height) = cell_size;
+    let grid_width = columns as f32 * (cell_width + spacing) - spacing;
+    let grid_height = rows as f32 * (cell_height + spacing) - spacing;
+    let left = (screen_width - grid_width) / 2.0;
+    let top = (screen_height - grid_height) / 2.0;
+
+    (0..count)
+        .map(|index| {
+            let (row, column) = (index / columns, index % columns);
+            Rect::new(
+                left + column as f32 * (cell_width + spacing),
+                top + row as f32 * (cell_height + spacing),
+                cell_width,
+                cell_height,
+            )
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub struct Button<T> {
+    pub item: T,
+    pub key: String,
+    pub label: String,
+    pub enabled: bool,
+    pub bounds: Rect,
+}
+
+// A vertical list or a grid of buttons with a single focused one. The focus
+// moves with the keyboard or the gamepad and follows the mouse cursor.
+#[derive(Debug)]
+pub struct Menu<T> {
+    pub buttons: Vec<Button<T>>,
+    pub focused: usize,
+    pub columns: usize,
+}
+
+impl<T: Copy + PartialEq> Menu<T> {
+    pub const BUTTON_WIDTH: f32 = 360.0;
+    pub const BUTTON_HEIGHT: f32 = 44.0;
+    pub const SPACING: f32 = 12.0;
+
+    pub fn new(items: Vec<(T, &'static str, &str)>, screen_width: f32, screen_height: f32) -> Self {
+        let sizes = vec![(Self::BUTTON_WIDTH, Self::BUTTON_HEIGHT); items.len()];
+        let bounds = centered_column(&sizes, screen_width, screen_height, Self::SPACING);
+        let items = items
+            .into_iter()
+            .map(|(item, key, label)| (item, String::from(key), String::from(label)))
+            .collect();
+
+        Self::with_bounds(items, bounds, 1)
+    }
+
+    // The up and down navigation of a grid moves the focus between the rows,
+    // the left and right one between the columns.
+    pub fn grid(
+        items: Vec<(T, String, String)>,
+        columns: usize,
+        cell_size: (f32, f32),
+        screen_width: f32,
+        screen_height: f32,
+    ) -> Self {
+        let bounds = centered_grid(
+            items.len(),
+            columns,
+            cell_size,
+            screen_width,
+            screen_height,
+            Self::SPACING,
+        );
+        Self::with_bounds(items, bounds, columns.max(1))
+    }
+
+    fn with_bounds(items: Vec<(T, String, String)>, bounds: Vec<Rect>, columns: usize) -> Self {
+        let buttons = items
+            .into_iter()
+            .zip(bounds)
+            .map(|((item, key, label), bounds)| Button {
+                item,
+                key,
+                label,
+                enabled: true,
+                bounds,
+            })
+            .collect();
+
+        Menu {
+            buttons,
+            focused: 0,
+            columns,
+        }
+    }
+
+    pub fn set_enabled(&mut self, item: T, enabled: bool) {
+        for button in self.buttons.iter_mut().filter(|button| button.item == item) {
+            button.enabled = enabled;
+        }
+        if !self
+            .buttons
+            .get(self.focused)
+            .is_some_and(|button| button.enabled)
+        {
+            self.focus_next();
+        }
+    }
+
+    pub fn set_label(&mut self, item: T, label: &str) {
+        for button in self.buttons.iter_mut().filter(|button| button.item == item) {
+            if button.label != label {
+                button.label = String::from(label);
+            }
+        }
+    }
+
+    pub fn focused_item(&self) -> Option<T> {
+        self.buttons
+            .get(self.focused)
+            .filter(|button| button.enabled)
+            .map(|button| button.item)
+    }
+
+    pub fn focus_item(&mut self, item: T) {
+        if let Some(index) = self
+            .buttons
+            .iter()
+            .position(|button| button.item == item && button.enabled)
+        {
+            self.focused = index;
+        }
+    }
+
+    pub fn focus_next(&mut self) {
+        self.move_focus(1);
+    }
+
+    pub fn focus_previous(&mut self) {
+        self.move_focus(self.buttons.len().saturating_sub(1));
+    }
+
+    pub fn focus_next_row(&mut self) {
+        self.move_focus(self.columns);
+    }
+
+    pub fn focus_previous_row(&mut self) {
+        let count = self.buttons.len();
+        self.move_focus(count - self.columns % count.max(1));
+    }
+
+    fn move_focus(&mut self, step: usize) {
+        let count = self.buttons.len();
+        for offset in 1..=count {
+            let index = (self.focused + offset * step) % count;
+            if self.buttons[index].enabled {
+                self.focused = index;
+                return;
+            }
+        }
+    }
+
+    pub fn item_at(&self, point: Point2<f32>) -> Option<T> {
+        self.buttons
+            .iter()
+            .find(|button| button.enabled && button.bounds.contains(point))
+            .map(|button| button.item)
+    }
+
+    // Moves the focus under the cursor, returns the item if there is one.
+    pub fn hover(&mut self, point: Point2<f32>) -> Option<T> {
+        let item = self.item_at(point);
+        if let Some(item) = item {
+            self.focus_item(item);
+        }
+        item
+    }
+
+    // Returns the item which has to be activated for the given navigation input.
+    pub fn navigate(&mut self, navigation: Navigation) -> Option<T> {
+        match navigation {
+            Navigation::Previous => self.focus_previous_row(),
+            Navigation::Next => self.focus_next_row(),
+            Navigation::Decrease => self.focus_previous(),
+            Navigation::Increase => self.focus_next(),
+            Navigation::Activate => return self.focused_item(),
+            Navigation::Back => {}
+        }
+        None
+    }
+
+    pub fn draw(&self, renderer: &mut dyn Renderer, palette: &Palette) -> GameResult<()> {
+        for (index, button) in self.buttons.iter().enumerate() {
+            let mut color = if index == self.focused {
+                palette.highlight
+            } else {
+                palette.text
+            };
+            if !button.enabled {
+                color.a = 0.35;
+            }
+
+            let style = if index == self.focused && button.enabled {
+                RectStyle::Fill
+            } else {
+                RectStyle::Stroke(2.0)
+            };
+            renderer.rectangle(button.bounds, style, color)?;
+
+            let (label_width, label_height) = renderer.text_size(&button.key, &button.label);
+            let label_pos = Point2 {
+                x: button.bounds.x + (button.bounds.w - label_width) / 2.0,
+                y: button.bounds.y + (button.bounds.h - label_height) / 2.0,
+            };
+            renderer.text(&button.key, &button.label, label_pos)?;
+        }
+        Ok(())
+    }
+}
+
+// The notifications on the HUD, shown one after another for SECONDS each at
+// the top of the screen.
+#[derive(Debug, Default)]
+pub struct Toasts {
+    queue: VecDeque<String>,
+    shown_seconds: f32,
+}
+
+impl Toasts {
+    pub const SECONDS: f32 = 3.0;
+    pub const TOP: f32 = 40.0;
+    pub const PADDING: f32 = 8.0;
+
+    pub fn push(&mut self, text: String) {
+        self.queue.push_back(text);
+    }
+
+    pub fn update(&mut self, seconds: f32) {
+        if self.queue.is_empty() {
+            return;
+        }
+        self.shown_seconds += seconds;
+        if self.shown_seconds >= Self::SECONDS {
+            self.queue.pop_front();
+            self.shown_seconds = 0.0;
+        }
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.queue.front().map(String::as_str)
+    }
+
+    pub fn draw(
+        &self,
+        renderer: &mut dyn Renderer,
+        screen_width: f32,
+        palette: &Palette,
+    ) -> GameResult<()> {
+        let text = match self.current() {
+            Some(text) => text,
+            None => return Ok(()),
+        };
+        let (width, height) = renderer.text_size("toast", text);
+        let bounds = Rect::new(
+            (screen_width - width) / 2.0 - Self::PADDING,
+            Self::TOP,
+            width + 2.0 * Self::PADDING,
+            height + 2.0 * Self::PADDING,
+        );
+        renderer.rectangle(bounds, RectStyle::Fill, palette.highlight)?;
+        let text_pos = Point2 {
+            x: bounds.x + Self::PADDING,
+            y: bounds.y + Self::PADDING,
+        };
+        renderer.text("toast", text, text_pos)
+    }
+}
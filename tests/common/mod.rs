@@ -0,0 +1,43 @@
+// The game the test files share: an 800x600 screen with the sprite sizes of
+// the headless front ends. Every test file uses only a part of it.
+#![allow(dead_code)]
+
+use crate::gameplay_entities::{BrickGrid, BricksWall, GameplayState, SpriteSizes, StepEvents};
+use crate::levels::LevelLayout;
+
+pub const SCREEN_SIZE: (f32, f32) = (800.0, 600.0);
+pub const STEP_SECONDS: f32 = 1.0 / 60.0;
+pub const SIZES: SpriteSizes = SpriteSizes::HEADLESS;
+
+pub fn brick_grid() -> BrickGrid {
+    BrickGrid::new(
+        SCREEN_SIZE.0,
+        SCREEN_SIZE.1,
+        SIZES.brick_survived.1,
+        SIZES.brick_survived.0,
+    )
+}
+
+// A new game with the wall of the layout.
+pub fn gameplay_state(layout: &LevelLayout) -> GameplayState {
+    let mut gameplay_state = GameplayState::new(SCREEN_SIZE.0, SCREEN_SIZE.1, &SIZES);
+    gameplay_state.bricks_wall = BricksWall::from_layout(&brick_grid(), layout);
+    gameplay_state
+}
+
+// A new game with the wall of the level file contents.
+pub fn level_gameplay_state(contents: &str) -> GameplayState {
+    gameplay_state(&LevelLayout::parse(contents).unwrap())
+}
+
+// Steps the game with the skateboard moving the same way all the time and
+// returns the events of every step.
+pub fn step(gameplay_state: &mut GameplayState, movement: f32, steps: usize) -> Vec<StepEvents> {
+    (0..steps)
+        .map(|_| {
+            gameplay_state
+                .step(STEP_SECONDS, movement, SCREEN_SIZE, &SIZES, true)
+                .unwrap()
+        })
+        .collect()
+}
@@ -0,0 +1,128 @@
+use crate::autopilot::{predict_landing_x, Autopilot, AutopilotSkill};
+use crate::game_workflow_state_reducer::{reduce, GameWorkflowIntent, GameWorkflowState};
+use crate::gameplay_entities::{Ball, GameplayState};
+use crate::level_generator::generate;
+use crate::types::Point2;
+use brick_breaker::*;
+
+mod common;
+
+const SCREEN_WIDTH: f32 = 800.0;
+const SCREEN_HEIGHT: f32 = 600.0;
+const STEP_SECONDS: f32 = 1.0 / 60.0;
+
+#[derive(Debug, Default)]
+struct Outcome {
+    cleared: bool,
+    balls_lost: u32,
+    seconds: f32,
+}
+
+// Plays the generated level of the seed with the autopilot, a lost ball is
+// served again until the time runs out.
+fn play_generated_level(skill: AutopilotSkill, seed: u64, max_seconds: f32) -> Outcome {
+    let sizes = common::SIZES;
+    let grid = common::brick_grid();
+    let mut state = common::gameplay_state(&generate(seed, grid.columns, grid.rows));
+    let mut autopilot = Autopilot::new(skill, seed);
+
+    let mut outcome = Outcome::default();
+    while outcome.seconds < max_seconds {
+        let input = autopilot.input(&state, &sizes, SCREEN_WIDTH, STEP_SECONDS);
+        let events = state
+            .step(
+                STEP_SECONDS,
+                input.movement,
+                (SCREEN_WIDTH, SCREEN_HEIGHT),
+                &sizes,
+                false,
+            )
+            .unwrap();
+        outcome.seconds += STEP_SECONDS;
+        assert!(state.ball.pos.x >= 0.0 && state.ball.pos.x <= SCREEN_WIDTH - sizes.ball.0);
+        assert!(state.ball.pos.y >= 0.0);
+
+        if events.ball_lost {
+            outcome.balls_lost += 1;
+            let served = GameplayState::new(SCREEN_WIDTH, SCREEN_HEIGHT, &sizes);
+            state.ball = served.ball;
+            state.skateboard = served.skateboard;
+        }
+        if state.bricks_wall.all_bricks_are_broken() {
+            outcome.cleared = true;
+            break;
+        }
+    }
+    outcome
+}
+
+#[test]
+fn test_predict_landing_x() {
+    let mut ball = Ball::new(SCREEN_WIDTH, SCREEN_HEIGHT, 20.0, 16.0, 16.0);
+    ball.pos = Point2 { x: 92.0, y: 92.0 };
+    ball.direction = Point2 { x: 0.0, y: 1.0 };
+    assert_eq!(predict_landing_x(&ball, 500.0, SCREEN_WIDTH), 100.0);
+
+    // 400 pixels down and 400 to the left, 300 of them after the left wall.
+    ball.direction = Point2 { x: -1.0, y: 1.0 };
+    assert!((predict_landing_x(&ball, 508.0, SCREEN_WIDTH) - 316.0).abs() < 0.01);
+
+    // Up to the ceiling first.
+    ball.direction = Point2 { x: 1.0, y: -1.0 };
+    assert!((predict_landing_x(&ball, 508.0, SCREEN_WIDTH) - 684.0).abs() < 0.01);
+}
+
+#[test]
+fn test_demo_transitions() {
+    let demo = reduce(GameWorkflowState::MainMenu, GameWorkflowIntent::StartDemo).unwrap();
+    assert!(matches!(demo, GameWorkflowState::Play));
+    let menu = reduce(demo, GameWorkflowIntent::StopDemo).unwrap();
+    assert!(matches!(menu, GameWorkflowState::MainMenu));
+
+    assert!(reduce(GameWorkflowState::NextLevel, GameWorkflowIntent::StartDemo).is_err());
+    assert!(reduce(GameWorkflowState::Pause, GameWorkflowIntent::StopDemo).is_err());
+}
+
+#[test]
+fn test_generated_levels_can_be_cleared() {
+    for seed in 0..10 {
+        let outcome = play_generated_level(AutopilotSkill::Perfect, seed, 600.0);
+        assert!(outcome.cleared, "seed {}: {:?}", seed, outcome);
+    }
+}
+
+#[test]
+fn test_autopilot_skills() {
+    let lost = |skill| {
+        (0..5)
+            .map(|seed| play_generated_level(skill, seed, 120.0).balls_lost)
+            .sum::<u32>()
+    };
+    let novice = lost(AutopilotSkill::Novice);
+    let perfect = lost(AutopilotSkill::Perfect);
+    assert!(novice > perfect, "novice {} perfect {}", novice, perfect);
+}
+
+// A long run over thousands of levels, started with cargo test -- --ignored.
+#[test]
+#[ignore]
+fn test_autopilot_soak() {
+    const LEVELS: u64 = 1000;
+    for &skill in AutopilotSkill::ALL.iter() {
+        let mut cleared = 0;
+        let mut balls_lost = 0;
+        for seed in 0..LEVELS {
+            let outcome = play_generated_level(skill, seed, 300.0);
+            cleared += outcome.cleared as u64;
+            balls_lost += outcome.balls_lost;
+        }
+        println!(
+            "{:?}: cleared {} of {} levels, lost {} balls",
+            skill, cleared, LEVELS, balls_lost
+        );
+        if skill == AutopilotSkill::Perfect {
+            assert!(cleared * 10 >= LEVELS * 9);
+            assert!(u64::from(balls_lost) < LEVELS);
+        }
+    }
+}
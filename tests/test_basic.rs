@@ -1,7 +1,30 @@
-use crate::gameplay_entities::{Ball, BallState, BricksWall, Size, Skateboard};
-use crate::math::{circle_collides_rect, Collision};
+use crate::achievements::{Achievements, Condition, Facts};
+use crate::dialogs_handler::DialogsHandler;
+use crate::game_settings_entities::ScoreDetails;
+use crate::game_workflow_state_reducer::{
+    reduce, transition, Effect, GameWorkflowIntent, GameWorkflowState, Guards,
+};
+use crate::gameplay_entities::{
+    Ball, BallState, Brick, BrickGrid, BrickIndex, BrickKind, BrickPath, BrickState, BricksWall,
+    BrokenBricks, GameplayState, Size, Skateboard, StepEvents,
+};
+use crate::level_generator::{
+    generate, generate_with_params, level_seed, GeneratorParams, Pattern,
+};
+use crate::levels::{LevelLayout, PathTarget};
+use crate::math::{circle_collides_rect, lerp_point, Collision};
+use crate::progress::{stars_for_clear, Progress};
+use crate::save_dir::{is_valid_profile_name, platform_data_dir, SaveDir, DATA_DIR_ENV};
+use crate::saved_run::SavedRun;
+use crate::settings::{ControlScheme, Difficulty, KeyAction, KeyBindings, Settings};
+use crate::statistics::Statistics;
+use crate::theme::{parse_color, Theme, ThemeSelector, BUILT_IN_THEME_NAME};
+use crate::timestep::FixedTimestep;
+use crate::types::{Color, KeyCode, Point2, Rect, Vector2};
+use crate::widgets::{centered_column, centered_grid, Menu, Navigation, Toasts};
 use brick_breaker::*;
-use ggez::mint::Point2;
+
+mod common;
 
 #[test]
 fn test_math_collisions() {
@@ -104,3 +127,1198 @@ fn test_skateboard_initialization() {
 
     assert_eq!(skate_3.pos, Point2 { x: 11.8, y: 6.0 });
 }
+
+#[test]
+fn test_theme_manifest_parsing() {
+    let manifest = "# comment\nball_flying = ball.png\n\nbackground_color = #102030\n";
+    let theme = Theme::parse("neon", manifest).unwrap();
+    assert_eq!(theme.name, "neon");
+    assert_eq!(
+        theme.files.get("ball_flying"),
+        Some(&String::from("/themes/neon/ball.png"))
+    );
+    assert_eq!(theme.palette.background, Color::from_rgb(16, 32, 48));
+    assert_eq!(theme.palette.text, Theme::built_in().palette.text);
+
+    assert!(Theme::parse("broken", "ball_flying").is_err());
+    assert!(Theme::parse("broken", "bal = ball.png").is_err());
+    assert!(Theme::parse("broken", "text_color = #12345").is_err());
+    assert!(parse_color("#zz0000").is_err());
+}
+
+#[test]
+fn test_theme_selector() {
+    let names = vec![
+        String::from(BUILT_IN_THEME_NAME),
+        String::from("midnight"),
+        String::from("neon"),
+    ];
+    let mut selector = ThemeSelector::new(names, "neon");
+    assert_eq!(selector.current_name(), "neon");
+    assert_eq!(selector.select_next(), BUILT_IN_THEME_NAME);
+    assert_eq!(selector.select_previous(), "neon");
+
+    let mut empty = ThemeSelector::new(Vec::new(), "neon");
+    assert_eq!(empty.select_next(), BUILT_IN_THEME_NAME);
+}
+
+#[test]
+fn test_main_menu_transitions() {
+    let state = reduce(GameWorkflowState::MainMenu, GameWorkflowIntent::NewGame).unwrap();
+    assert!(matches!(state, GameWorkflowState::NextLevel));
+
+    let state = reduce(
+        GameWorkflowState::MainMenu,
+        GameWorkflowIntent::OpenHighScores,
+    )
+    .unwrap();
+    assert!(matches!(state, GameWorkflowState::HighScores));
+
+    let state = reduce(
+        GameWorkflowState::GameOver,
+        GameWorkflowIntent::OpenMainMenu,
+    )
+    .unwrap();
+    assert!(matches!(state, GameWorkflowState::MainMenu));
+
+    assert!(reduce(GameWorkflowState::Play, GameWorkflowIntent::OpenMainMenu).is_err());
+    assert!(reduce(GameWorkflowState::MainMenu, GameWorkflowIntent::StartGame).is_err());
+}
+
+#[test]
+fn test_every_state_and_intent_pair() {
+    use GameWorkflowIntent as I;
+    use GameWorkflowState as S;
+    let allowed = [
+        (S::NextLevel, I::StartGame, S::Play),
+        (S::Play, I::Lose, S::GameOver),
+        (S::Play, I::Win, S::NextLevel),
+        (S::GameOver, I::GoToHomePage, S::NextLevel),
+        (S::NextLevel, I::OpenMainMenu, S::MainMenu),
+        (S::GameOver, I::OpenMainMenu, S::MainMenu),
+        (S::HighScores, I::OpenMainMenu, S::MainMenu),
+        (S::Pause, I::OpenMainMenu, S::MainMenu),
+        (S::LevelSelect, I::OpenMainMenu, S::MainMenu),
+        (S::Editor, I::OpenMainMenu, S::MainMenu),
+        (S::MainMenu, I::NewGame, S::NextLevel),
+        (S::MainMenu, I::ContinueGame, S::NextLevel),
+        (S::MainMenu, I::OpenHighScores, S::HighScores),
+        (S::Play, I::Pause, S::Pause),
+        (S::Pause, I::Resume, S::Play),
+        (
+            S::MainMenu,
+            I::OpenSettings,
+            S::Settings { from_pause: false },
+        ),
+        (S::Pause, I::OpenSettings, S::Settings { from_pause: true }),
+        (
+            S::Settings { from_pause: false },
+            I::CloseSettings,
+            S::MainMenu,
+        ),
+        (S::Settings { from_pause: true }, I::CloseSettings, S::Pause),
+        (S::MainMenu, I::OpenLevelSelect, S::LevelSelect),
+        (S::LevelSelect, I::SelectLevel, S::NextLevel),
+        (S::MainMenu, I::OpenEditor, S::Editor),
+        (S::Editor, I::TestLevel, S::Play),
+        (S::Play, I::StopTest, S::Editor),
+        (S::Pause, I::StopTest, S::Editor),
+        (S::MainMenu, I::StartDemo, S::Play),
+        (S::Play, I::StopDemo, S::MainMenu),
+        (S::Play, I::RequestQuit, S::ConfirmQuit),
+        (S::Pause, I::RequestQuit, S::ConfirmQuit),
+        (S::ConfirmQuit, I::CancelQuit, S::Pause),
+        (S::MainMenu, I::ResumeRun, S::Pause),
+        (S::MainMenu, I::OpenStatistics, S::Statistics),
+        (S::Statistics, I::OpenMainMenu, S::MainMenu),
+    ];
+    let guards = Guards {
+        can_continue: true,
+        testing_level: false,
+        saved_run: true,
+    };
+
+    for &state in GameWorkflowState::ALL.iter() {
+        for &intent in GameWorkflowIntent::ALL.iter() {
+            let expected = allowed
+                .iter()
+                .find(|(from, with, _)| *from == state && *with == intent)
+                .map(|(_, _, to)| *to);
+            let reduced = reduce(state, intent).ok();
+            assert_eq!(reduced, expected, "{:?} with {:?}", state, intent);
+            let transitioned = transition(state, intent, guards).ok().map(|t| t.state);
+            assert_eq!(transitioned, expected, "{:?} with {:?}", state, intent);
+        }
+    }
+}
+
+#[test]
+fn test_transition_guards() {
+    let guards = Guards::default();
+    assert!(transition(
+        GameWorkflowState::MainMenu,
+        GameWorkflowIntent::ContinueGame,
+        guards
+    )
+    .is_err());
+    let continued = Guards {
+        can_continue: true,
+        ..guards
+    };
+    assert!(transition(
+        GameWorkflowState::MainMenu,
+        GameWorkflowIntent::ContinueGame,
+        continued
+    )
+    .is_ok());
+
+    let testing = Guards {
+        testing_level: true,
+        ..guards
+    };
+    assert!(transition(GameWorkflowState::Play, GameWorkflowIntent::Lose, testing).is_err());
+    assert!(transition(GameWorkflowState::Play, GameWorkflowIntent::Win, testing).is_err());
+    assert!(transition(
+        GameWorkflowState::Pause,
+        GameWorkflowIntent::OpenMainMenu,
+        testing
+    )
+    .is_err());
+    assert!(transition(
+        GameWorkflowState::Play,
+        GameWorkflowIntent::StopTest,
+        testing
+    )
+    .is_ok());
+
+    assert!(transition(
+        GameWorkflowState::MainMenu,
+        GameWorkflowIntent::ResumeRun,
+        guards
+    )
+    .is_err());
+}
+
+#[test]
+fn test_transition_effects() {
+    let guards = Guards::default();
+    let lost = transition(GameWorkflowState::Play, GameWorkflowIntent::Lose, guards).unwrap();
+    assert_eq!(
+        lost.effects,
+        vec![
+            Effect::StopSkateboard,
+            Effect::RecordGameOver,
+            Effect::SaveProgress
+        ]
+    );
+
+    let won = transition(GameWorkflowState::Play, GameWorkflowIntent::Win, guards).unwrap();
+    assert_eq!(
+        won.effects,
+        vec![
+            Effect::StopSkateboard,
+            Effect::RecordClear,
+            Effect::ResetNextLevel,
+            Effect::SaveProgress
+        ]
+    );
+
+    let menu = transition(
+        GameWorkflowState::Pause,
+        GameWorkflowIntent::OpenMainMenu,
+        guards,
+    )
+    .unwrap();
+    assert_eq!(
+        menu.effects,
+        vec![
+            Effect::ResetLevel,
+            Effect::EndEndlessRun,
+            Effect::RefreshMainMenu
+        ]
+    );
+
+    let menu = transition(
+        GameWorkflowState::HighScores,
+        GameWorkflowIntent::OpenMainMenu,
+        guards,
+    )
+    .unwrap();
+    assert_eq!(
+        menu.effects,
+        vec![Effect::EndEndlessRun, Effect::RefreshMainMenu]
+    );
+
+    let new_game = transition(
+        GameWorkflowState::MainMenu,
+        GameWorkflowIntent::NewGame,
+        guards,
+    )
+    .unwrap();
+    assert_eq!(
+        new_game.effects,
+        vec![
+            Effect::DiscardSavedRun,
+            Effect::ResetFirstLevel,
+            Effect::RecordGameStart
+        ]
+    );
+
+    let retried = transition(
+        GameWorkflowState::GameOver,
+        GameWorkflowIntent::GoToHomePage,
+        guards,
+    )
+    .unwrap();
+    assert_eq!(
+        retried.effects,
+        vec![Effect::ResetLevel, Effect::RecordGameStart]
+    );
+
+    let resumed_run = transition(
+        GameWorkflowState::MainMenu,
+        GameWorkflowIntent::ResumeRun,
+        Guards {
+            saved_run: true,
+            ..guards
+        },
+    )
+    .unwrap();
+    assert_eq!(resumed_run.state, GameWorkflowState::Pause);
+    assert_eq!(resumed_run.effects, vec![Effect::RestoreRun]);
+
+    let resumed = transition(GameWorkflowState::Pause, GameWorkflowIntent::Resume, guards).unwrap();
+    assert!(resumed.effects.is_empty());
+}
+
+#[test]
+fn test_centered_column_layout() {
+    let bounds = centered_column(&[(100.0, 20.0), (50.0, 40.0)], 200.0, 100.0, 10.0);
+    assert_eq!(bounds.len(), 2);
+    assert_eq!((bounds[0].x, bounds[0].y), (50.0, 15.0));
+    assert_eq!((bounds[1].x, bounds[1].y), (75.0, 45.0));
+}
+
+#[test]
+fn test_menu_focus_navigation() {
+    let mut menu = Menu::new(
+        vec![
+            (1, "first", "First"),
+            (2, "second", "Second"),
+            (3, "third", "Third"),
+        ],
+        800.0,
+        600.0,
+    );
+    menu.set_enabled(2, false);
+    assert_eq!(menu.focused_item(), Some(1));
+
+    assert_eq!(menu.navigate(Navigation::Next), None);
+    assert_eq!(menu.focused_item(), Some(3));
+    menu.navigate(Navigation::Next);
+    assert_eq!(menu.focused_item(), Some(1));
+    menu.navigate(Navigation::Previous);
+    assert_eq!(menu.navigate(Navigation::Activate), Some(3));
+
+    let first_center = Point2 {
+        x: 400.0,
+        y: menu.buttons[0].bounds.y + 1.0,
+    };
+    assert_eq!(menu.hover(first_center), Some(1));
+    assert_eq!(menu.focused_item(), Some(1));
+    assert_eq!(menu.hover(Point2 { x: 0.0, y: 0.0 }), None);
+
+    let second_center = Point2 {
+        x: 400.0,
+        y: menu.buttons[1].bounds.y + 1.0,
+    };
+    assert_eq!(menu.item_at(second_center), None);
+}
+
+#[test]
+fn test_pause_and_settings_transitions() {
+    let paused = reduce(GameWorkflowState::Play, GameWorkflowIntent::Pause).unwrap();
+    assert!(matches!(paused, GameWorkflowState::Pause));
+
+    let settings = reduce(paused, GameWorkflowIntent::OpenSettings).unwrap();
+    assert!(matches!(
+        settings,
+        GameWorkflowState::Settings { from_pause: true }
+    ));
+    let back = reduce(settings, GameWorkflowIntent::CloseSettings).unwrap();
+    assert!(matches!(back, GameWorkflowState::Pause));
+    let resumed = reduce(back, GameWorkflowIntent::Resume).unwrap();
+    assert!(matches!(resumed, GameWorkflowState::Play));
+
+    let settings = reduce(
+        GameWorkflowState::MainMenu,
+        GameWorkflowIntent::OpenSettings,
+    )
+    .unwrap();
+    let back = reduce(settings, GameWorkflowIntent::CloseSettings).unwrap();
+    assert!(matches!(back, GameWorkflowState::MainMenu));
+
+    assert!(reduce(GameWorkflowState::Play, GameWorkflowIntent::OpenSettings).is_err());
+    assert!(reduce(GameWorkflowState::MainMenu, GameWorkflowIntent::Resume).is_err());
+}
+
+#[test]
+fn test_settings_file_round_trip() {
+    let mut settings = Settings::default();
+    settings.control_scheme = ControlScheme::Mouse;
+    settings.difficulty = Difficulty::Hard;
+    settings.key_bindings.left = KeyCode::A;
+    settings.theme = String::from("midnight");
+    settings.color_blind_mode = true;
+    settings.change_volume(2.0);
+
+    let parsed = Settings::parse(&settings.to_file_contents()).unwrap();
+    assert_eq!(parsed, settings);
+    assert_eq!(parsed.volume, 0.9);
+
+    assert_eq!(Settings::parse("").unwrap(), Settings::default());
+    assert!(Settings::parse("key_left = Escape").is_err());
+    assert!(Settings::parse("difficulty = impossible").is_err());
+    assert_eq!(Settings::parse("volume = 3").unwrap().volume, 1.0);
+    assert_eq!(Settings::parse("volume = -1").unwrap().volume, 0.0);
+    assert!(Settings::parse("volume = NaN").is_err());
+    assert!(Settings::parse("fullscreen = yes").is_err());
+    assert!(Settings::parse("fullscreen = false").is_ok());
+    assert!(Settings::parse("key_left = P").is_err());
+}
+
+#[test]
+fn test_key_binding_swaps_a_taken_key() {
+    let mut key_bindings = KeyBindings::default();
+    key_bindings.bind(KeyAction::Left, KeyCode::P);
+    assert_eq!(key_bindings.left, KeyCode::P);
+    assert_eq!(key_bindings.pause, KeyCode::Left);
+    assert!(!key_bindings.has_conflicts());
+    key_bindings.bind(KeyAction::Right, KeyCode::A);
+    assert_eq!(key_bindings.right, KeyCode::A);
+    assert_eq!(key_bindings.left, KeyCode::P);
+}
+
+#[test]
+fn test_progress_parsing_and_unlocks() {
+    let old_score_file = Progress::parse("4\n57").unwrap();
+    assert_eq!(old_score_file.level, 4);
+    assert_eq!(old_score_file.max_score, 57);
+    assert!(old_score_file.levels.is_empty());
+    assert_eq!(old_score_file.highest_unlocked_level(), 4);
+
+    let mut progress = Progress::default();
+    assert!(progress.is_unlocked(1));
+    assert!(!progress.is_unlocked(2));
+
+    progress.record_attempt(1, 12);
+    assert!(!progress.is_unlocked(2));
+    progress.record_clear(1, 60, 2);
+    progress.record_clear(1, 60, 1);
+    assert_eq!(progress.record(1).stars, 2);
+    assert_eq!(progress.record(1).best_score, 60);
+    assert!(progress.is_unlocked(2));
+    assert!(!progress.is_unlocked(3));
+
+    let parsed = Progress::parse(&progress.to_file_contents()).unwrap();
+    assert_eq!(parsed, progress);
+
+    assert!(Progress::parse("3").is_err());
+    assert!(Progress::parse("3\n10\n2 five 1").is_err());
+
+    assert_eq!(stars_for_clear(100.0, 60), 3);
+    assert_eq!(stars_for_clear(200.0, 60), 2);
+    assert_eq!(stars_for_clear(300.0, 60), 1);
+}
+
+// A save file in a directory of its own under the temporary directory.
+fn temp_save_file(name: &str, file_name: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("brick_breaker_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(file_name).to_str().unwrap().to_string()
+}
+
+// Runs the transition and its effects on the score like the front ends do.
+fn run_score_transition(
+    score_details: &mut ScoreDetails,
+    intent: GameWorkflowIntent,
+    breakable_bricks: usize,
+    file_name: &str,
+) {
+    let guards = Guards::default();
+    let transition = transition(score_details.game_workflow_state, intent, guards).unwrap();
+    score_details.game_workflow_state = transition.state;
+    for effect in transition.effects {
+        score_details
+            .run_effect(effect, breakable_bricks, file_name)
+            .unwrap();
+    }
+}
+
+// Removes the directory of a save file made by temp_save_file.
+fn remove_temp_save_file(file_name: &str) {
+    let dir = std::path::Path::new(file_name).parent().unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_game_over_saves_the_new_best_score() {
+    let file_name = temp_save_file("game_over", "score.txt");
+    let mut score_details = ScoreDetails::new(Progress::parse("2\n10").unwrap());
+    score_details.game_workflow_state = GameWorkflowState::Play;
+    score_details.start_level(2);
+    score_details.score.score_result = 42;
+
+    run_score_transition(&mut score_details, GameWorkflowIntent::Lose, 60, &file_name);
+    let saved = Progress::load(&file_name).unwrap();
+    assert_eq!(saved.level, 2);
+    assert_eq!(saved.max_score, 42);
+    assert_eq!(saved.record(2).best_score, 42);
+    assert_eq!(saved.record(2).stars, 0);
+    remove_temp_save_file(&file_name);
+}
+
+#[test]
+fn test_level_clear_saves_the_reached_level() {
+    let file_name = temp_save_file("level_clear", "score.txt");
+    let mut score_details = ScoreDetails::new(Progress::default());
+    score_details.game_workflow_state = GameWorkflowState::Play;
+    score_details.score.score_result = 60;
+    score_details.level_seconds = 30.0;
+
+    run_score_transition(&mut score_details, GameWorkflowIntent::Win, 60, &file_name);
+    assert_eq!(score_details.score.level, 2);
+    let saved = Progress::load(&file_name).unwrap();
+    assert_eq!(saved.level, 2);
+    assert_eq!(saved.max_score, 60);
+    assert_eq!(saved.record(1).stars, Progress::MAX_STARS);
+    remove_temp_save_file(&file_name);
+}
+
+#[test]
+fn test_exit_saves_the_best_score_of_an_endless_run() {
+    let file_name = temp_save_file("exit", "score.txt");
+    let mut score_details = ScoreDetails::new(Progress::parse("4\n10").unwrap());
+    score_details.score.endless_seed = Some(7);
+    score_details.start_level(9);
+    score_details.score.score_result = 99;
+
+    score_details.save_progress(&file_name).unwrap();
+    let saved = Progress::load(&file_name).unwrap();
+    assert_eq!(saved.level, 4);
+    assert_eq!(saved.max_score, 99);
+    assert!(saved.levels.is_empty());
+    remove_temp_save_file(&file_name);
+}
+
+#[test]
+fn test_saved_run_continues_the_same_game() {
+    let file_name = temp_save_file("saved_run", "run.txt");
+    let mut gameplay_state = common::level_gameplay_state("#+@#\n.##.\npath row 1 orbit 20 3\n");
+    gameplay_state
+        .bricks_wall
+        .set_hit_points(BrickKind::Normal, 4);
+    gameplay_state.bricks_wall.bricks[0].broke().unwrap();
+    common::step(&mut gameplay_state, 0.5, 90);
+    // The game doesn't move the skateboard by its velocity yet.
+    gameplay_state.skateboard.velocity = Vector2 { x: 2.0, y: 0.0 };
+    let mut score_details = ScoreDetails::new(Progress::default());
+    score_details.score.level = 3;
+    score_details.score.score_result = 1;
+    score_details.score.endless_seed = Some(11);
+    score_details.level_seconds = 1.5;
+
+    SavedRun::capture(&score_details, &gameplay_state)
+        .save(&file_name)
+        .unwrap();
+    let saved_run = SavedRun::load(&file_name).unwrap().unwrap();
+    let mut restored_details = ScoreDetails::new(Progress::default());
+    let mut restored_state =
+        GameplayState::new(common::SCREEN_SIZE.0, common::SCREEN_SIZE.1, &common::SIZES);
+    saved_run.restore(&mut restored_details, &mut restored_state);
+    assert_eq!(restored_details.score.level, 3);
+    assert_eq!(restored_details.score.score_result, 1);
+    assert_eq!(restored_details.score.endless_seed, Some(11));
+    assert_eq!(restored_details.level_seconds, 1.5);
+    assert_eq!(restored_state.bricks_wall.bricks[0].hit_points, 4);
+    assert_eq!(restored_state.bricks_wall.bricks[0].hits_left, 3);
+
+    // Every field of the game comes back, only the index of the wall and its
+    // buffer are built again. A field which the run file misses fails here.
+    let without_index = |state: &GameplayState| {
+        let mut state = state.clone();
+        state.bricks_wall.index = BrickIndex::default();
+        state.bricks_wall.near_bricks.clear();
+        format!("{:?}", state)
+    };
+    assert_eq!(
+        without_index(&restored_state),
+        without_index(&gameplay_state)
+    );
+
+    SavedRun::remove(&file_name).unwrap();
+    assert!(SavedRun::load(&file_name).unwrap().is_none());
+    SavedRun::remove(&file_name).unwrap();
+    let contents = SavedRun::capture(&score_details, &gameplay_state).to_file_contents();
+    let without_line = |name: &str| {
+        contents
+            .lines()
+            .filter(|line| !line.starts_with(name))
+            .collect::<Vec<&str>>()
+            .join("\n")
+    };
+    assert!(SavedRun::parse(&contents).is_ok());
+    assert!(SavedRun::parse(&without_line("level ")).is_err());
+    assert!(SavedRun::parse(&without_line("score ")).is_err());
+    assert!(SavedRun::parse(&without_line("watchdog ")).is_err());
+    assert!(SavedRun::parse(&without_line("brick ")).is_err());
+    assert!(SavedRun::parse("level 2\nscore 5\n").is_err());
+    assert!(SavedRun::parse("ball rolling 1 2 3 4 5 6\n").is_err());
+    remove_temp_save_file(&file_name);
+}
+
+#[test]
+fn test_level_select_transitions() {
+    let state = reduce(
+        GameWorkflowState::MainMenu,
+        GameWorkflowIntent::OpenLevelSelect,
+    )
+    .unwrap();
+    assert!(matches!(state, GameWorkflowState::LevelSelect));
+
+    let selected = reduce(state, GameWorkflowIntent::SelectLevel).unwrap();
+    assert!(matches!(selected, GameWorkflowState::NextLevel));
+    let back = reduce(state, GameWorkflowIntent::OpenMainMenu).unwrap();
+    assert!(matches!(back, GameWorkflowState::MainMenu));
+
+    assert!(reduce(GameWorkflowState::Play, GameWorkflowIntent::OpenLevelSelect).is_err());
+    assert!(reduce(GameWorkflowState::MainMenu, GameWorkflowIntent::SelectLevel).is_err());
+}
+
+#[test]
+fn test_brick_hit_points() {
+    let mut brick = Brick::new(BrickKind::Normal, Point2 { x: 0.0, y: 0.0 });
+    brick.broke().unwrap();
+    assert_eq!(brick.state, BrickState::Touched);
+    brick.reset();
+    assert_eq!(brick.state, BrickState::Survived);
+
+    brick.set_hit_points(3);
+    brick.broke().unwrap();
+    assert_eq!((brick.state, brick.hits_left), (BrickState::Survived, 2));
+    brick.broke().unwrap();
+    assert_eq!(brick.state, BrickState::Touched);
+    brick.broke().unwrap();
+    assert_eq!(brick.state, BrickState::Broken);
+    brick.reset();
+    assert_eq!((brick.state, brick.hits_left), (BrickState::Survived, 3));
+
+    let mut unbreakable = Brick::new(BrickKind::Unbreakable, Point2 { x: 0.0, y: 0.0 });
+    unbreakable.set_hit_points(3);
+    unbreakable.broke().unwrap();
+    assert_eq!(unbreakable.state, BrickState::Survived);
+}
+
+#[test]
+fn test_level_select_pages() {
+    let progress = Progress {
+        level: 23,
+        ..Progress::default()
+    };
+    let mut dialogs_handler = DialogsHandler::new(800.0, 600.0);
+    dialogs_handler.refresh_level_select_menu(&progress);
+    assert_eq!(dialogs_handler.level_select_page, 1);
+    assert_eq!(dialogs_handler.level_select_menu.focused_item(), Some(23));
+
+    // The earlier levels stay reachable on the first page.
+    assert!(dialogs_handler.turn_level_select_page(&progress, -1));
+    assert_eq!(dialogs_handler.level_select_page, 0);
+    assert_eq!(dialogs_handler.level_select_menu.focused_item(), Some(3));
+    assert!(!dialogs_handler.turn_level_select_page(&progress, -1));
+
+    // The right edge of the grid turns to the next page, which is the last.
+    dialogs_handler.level_select_menu.focus_item(5);
+    assert_eq!(
+        dialogs_handler.navigate_level_select(&progress, Navigation::Increase),
+        None
+    );
+    assert_eq!(dialogs_handler.level_select_page, 1);
+    assert_eq!(dialogs_handler.level_select_menu.focused_item(), Some(21));
+    dialogs_handler.navigate_level_select(&progress, Navigation::Decrease);
+    assert_eq!(dialogs_handler.level_select_page, 0);
+    assert_eq!(dialogs_handler.level_select_menu.focused_item(), Some(5));
+    // The locked cell of the last page leaves the focus on the highest level.
+    assert!(dialogs_handler.turn_level_select_page(&progress, 5));
+    assert_eq!(dialogs_handler.level_select_page, 1);
+    assert_eq!(
+        dialogs_handler.navigate_level_select(&progress, Navigation::Activate),
+        Some(23)
+    );
+}
+
+#[test]
+fn test_grid_menu_navigation() {
+    let bounds = centered_grid(5, 3, (10.0, 20.0), 100.0, 100.0, 5.0);
+    assert_eq!(bounds.len(), 5);
+    assert_eq!((bounds[0].x, bounds[0].y), (30.0, 27.5));
+    assert_eq!((bounds[4].x, bounds[4].y), (45.0, 52.5));
+
+    let items = (1..=6)
+        .map(|level| (level, format!("cell_{}", level), level.to_string()))
+        .collect();
+    let mut grid = Menu::grid(items, 3, (10.0, 20.0), 100.0, 100.0);
+    grid.set_enabled(6, false);
+
+    grid.navigate(Navigation::Next);
+    assert_eq!(grid.focused_item(), Some(4));
+    grid.navigate(Navigation::Increase);
+    assert_eq!(grid.focused_item(), Some(5));
+    grid.navigate(Navigation::Previous);
+    assert_eq!(grid.focused_item(), Some(2));
+    grid.navigate(Navigation::Decrease);
+    assert_eq!(grid.navigate(Navigation::Activate), Some(1));
+}
+
+#[test]
+fn test_level_layout_file_round_trip() {
+    let layout = LevelLayout::parse("; comment\n#+@\n.#\n").unwrap();
+    assert_eq!((layout.columns, layout.rows), (3, 2));
+    assert_eq!(layout.get(1, 0), Some(BrickKind::Weak));
+    assert_eq!(layout.get(2, 1), None);
+    assert_eq!(layout.to_file_contents(), "#+@\n.#.\n");
+    assert_eq!(
+        LevelLayout::parse(&layout.to_file_contents()).unwrap(),
+        layout
+    );
+    assert!(LevelLayout::parse("#x#").is_err());
+
+    let mut resized = layout.resized(2, 3);
+    assert_eq!(resized.to_file_contents(), "#+\n.#\n..\n");
+    resized.cycle(0, 0);
+    assert_eq!(resized.get(0, 0), Some(BrickKind::Weak));
+    resized.cycle(0, 2);
+    assert_eq!(resized.get(0, 2), Some(BrickKind::Normal));
+    resized.cycle(0, 0);
+    resized.cycle(0, 0);
+    assert_eq!(resized.get(0, 0), None);
+}
+
+#[test]
+fn test_brick_grid_and_kinds() {
+    let grid = BrickGrid::new(100.0, 400.0, 20.0, 40.0);
+    assert_eq!((grid.columns, grid.rows), (1, 6));
+    let pos = grid.cell_position(0, 2);
+    assert_eq!(grid.cell_at(pos), Some((0, 2)));
+    assert_eq!(grid.cell_at(Point2 { x: 0.0, y: 0.0 }), None);
+
+    let mut layout = LevelLayout::empty(grid.columns, grid.rows);
+    layout.set(0, 0, Some(BrickKind::Weak));
+    layout.set(0, 1, Some(BrickKind::Unbreakable));
+    let mut wall = BricksWall::from_layout(&grid, &layout);
+    assert_eq!(wall.bricks.len(), 2);
+    assert_eq!(wall.breakable_bricks_count(), 1);
+
+    wall.bricks[1].broke().unwrap();
+    assert!(matches!(wall.bricks[1].state, BrickState::Survived));
+    wall.bricks[0].broke().unwrap();
+    assert!(wall.all_bricks_are_broken());
+
+    let mut weak = Brick::new(BrickKind::Weak, pos);
+    weak.broke().unwrap();
+    weak.reset();
+    assert!(matches!(weak.state, BrickState::Touched));
+}
+
+#[test]
+fn test_editor_transitions() {
+    let editor = reduce(GameWorkflowState::MainMenu, GameWorkflowIntent::OpenEditor).unwrap();
+    assert!(matches!(editor, GameWorkflowState::Editor));
+
+    let testing = reduce(editor, GameWorkflowIntent::TestLevel).unwrap();
+    assert!(matches!(testing, GameWorkflowState::Play));
+    let paused = reduce(testing, GameWorkflowIntent::Pause).unwrap();
+    let back = reduce(paused, GameWorkflowIntent::StopTest).unwrap();
+    assert!(matches!(back, GameWorkflowState::Editor));
+    let menu = reduce(back, GameWorkflowIntent::OpenMainMenu).unwrap();
+    assert!(matches!(menu, GameWorkflowState::MainMenu));
+
+    assert!(reduce(GameWorkflowState::MainMenu, GameWorkflowIntent::TestLevel).is_err());
+    assert!(reduce(GameWorkflowState::NextLevel, GameWorkflowIntent::StopTest).is_err());
+}
+
+#[test]
+fn test_level_generator_is_reproducible() {
+    for seed in 0..50 {
+        let layout = generate(seed, 17, 9);
+        assert_eq!(layout, generate(seed, 17, 9));
+        assert_eq!((layout.columns, layout.rows), (17, 9));
+        assert!(layout
+            .cells
+            .iter()
+            .any(|&cell| cell == Some(BrickKind::Normal) || cell == Some(BrickKind::Weak)));
+    }
+    assert!((0..10).any(|seed| generate(seed, 17, 9) != generate(seed + 10, 17, 9)));
+
+    assert_eq!(level_seed(42, 3), level_seed(42, 3));
+    assert_ne!(level_seed(42, 3), level_seed(42, 4));
+    assert_ne!(level_seed(42, 3), level_seed(43, 3));
+}
+
+// The levels of a seed never change, so a shared seed is the same run for
+// everyone.
+#[test]
+fn test_level_generator_output_is_pinned() {
+    assert_eq!(
+        generate(42, 12, 5).to_file_contents(),
+        "######+##+++\n............\n##+#+#####+#\n............\n@#..#+######\n"
+    );
+    assert_eq!(level_seed(42, 3), 9374325213463818850);
+}
+
+#[test]
+fn test_level_generator_patterns() {
+    let params = GeneratorParams {
+        pattern: Pattern::Checkerboard,
+        mirrored: true,
+        hole_threshold: 0.3,
+        weak_chance: 0.3,
+        unbreakable_chance: 0.1,
+    };
+    let mut rng = rand::SeedableRng::seed_from_u64(7);
+    let layout = generate_with_params(&mut rng, &params, 10, 6);
+    for row in 0..6 {
+        for column in 0..10 {
+            assert_eq!(layout.get(column, row), layout.get(9 - column, row));
+        }
+    }
+
+    let params = GeneratorParams {
+        pattern: Pattern::Pyramid,
+        mirrored: false,
+        hole_threshold: 0.0,
+        weak_chance: 0.0,
+        unbreakable_chance: 0.0,
+    };
+    let layout = generate_with_params(&mut rng, &params, 5, 5);
+    assert_eq!(
+        layout.to_file_contents(),
+        "#####\n.###.\n.###.\n..#..\n..#..\n"
+    );
+}
+
+#[test]
+fn test_level_paths_parsing() {
+    let contents =
+        "##\n##\npath row 0 oscillate 40 2\npath cell 1 0 orbit 10 4\npath row 1 descend 5 30\n";
+    let layout = LevelLayout::parse(contents).unwrap();
+    assert_eq!(layout.paths.len(), 3);
+    assert_eq!(layout.paths[1].target, PathTarget::Cell(1, 0));
+    assert_eq!(
+        layout.path_at(0, 0),
+        Some(BrickPath::Oscillate {
+            amplitude: 40.0,
+            period: 2.0
+        })
+    );
+    assert_eq!(
+        layout.path_at(1, 0),
+        Some(BrickPath::Orbit {
+            radius: 10.0,
+            period: 4.0
+        })
+    );
+    assert_eq!(layout.to_file_contents(), contents);
+
+    assert!(LevelLayout::parse("#\npath row 0 oscillate 40 0").is_err());
+    assert!(LevelLayout::parse("#\npath row 0 spin 1 1").is_err());
+    assert!(LevelLayout::parse("#\npath cell 0 descend 1 1").is_err());
+}
+
+#[test]
+fn test_moving_bricks() {
+    let grid = BrickGrid::new(100.0, 400.0, 20.0, 40.0);
+    let layout =
+        LevelLayout::parse("#\n#\n#\npath row 0 oscillate 40 4\npath row 2 descend 10 15\n")
+            .unwrap();
+    let mut wall = BricksWall::from_layout(&grid, &layout);
+    let start = wall
+        .bricks
+        .iter()
+        .map(|brick| brick.pos)
+        .collect::<Vec<_>>();
+
+    wall.update(1.0);
+    assert!((wall.bricks[0].pos.x - (start[0].x + 40.0)).abs() < 0.001);
+    assert!(wall.bricks[0].velocity.x > 0.0);
+    assert_eq!(wall.bricks[1].pos, start[1]);
+    assert_eq!(wall.bricks[2].pos.y, start[2].y + 10.0);
+
+    wall.update(1.0);
+    assert_eq!(wall.bricks[2].pos.y, start[2].y + 15.0);
+
+    wall.reset_on_game_over();
+    assert_eq!(wall.bricks[2].pos, start[2]);
+    assert_eq!(wall.elapsed, 0.0);
+}
+
+#[test]
+fn test_ball_bounce_off_moving_brick() {
+    let mut ball = Ball::new(200.0, 100.0, 60.0, 10.0, 10.0);
+    ball.bounce_off_moving(Collision::Top, Vector2 { x: 0.0, y: 0.0 });
+    assert_eq!(ball.direction, Point2 { x: 1.0, y: -1.0 });
+
+    let mut ball = Ball::new(200.0, 100.0, 60.0, 10.0, 10.0);
+    ball.bounce_off_moving(Collision::Top, Vector2 { x: -350.0, y: 0.0 });
+    assert!(ball.direction.y < 0.0);
+    assert!(ball.direction.x < 1.0);
+    let length = (ball.direction.x.powi(2) + ball.direction.y.powi(2)).sqrt();
+    assert!((length - 2.0_f32.sqrt()).abs() < 0.001);
+
+    let mut ball = Ball::new(200.0, 100.0, 60.0, 10.0, 10.0);
+    ball.bounce_off_moving(Collision::Bottom, Vector2 { x: 0.0, y: 10.0 });
+    assert_eq!(ball.direction, Point2 { x: 1.0, y: 1.0 });
+}
+
+#[test]
+fn test_bricks_index_matches_linear_scan() {
+    let grid = BrickGrid::new(2000.0, 1500.0, 20.0, 40.0);
+    let mut wall = BricksWall::from_layout(&grid, &LevelLayout::full(grid.columns, grid.rows));
+    let mut ball = Ball::new(2000.0, 1500.0, 16.0, 16.0, 16.0);
+
+    for step in 0..200 {
+        ball.pos = Point2 {
+            x: (step * 37 % 2000) as f32,
+            y: (step * 53 % 900) as f32,
+        };
+        let bounds = ball.swept_bounds(1.0 / 60.0);
+        let near = wall.bricks_near(bounds);
+        let colliding = (0..wall.bricks.len())
+            .filter(|&index| !matches!(ball.collides_rect(wall.brick_rect(index)), Collision::None))
+            .collect::<Vec<usize>>();
+        assert!(colliding.iter().all(|index| near.contains(index)));
+        assert!(near.len() < 20);
+    }
+
+    let moving = LevelLayout::parse("#\npath row 0 descend 100 500\n").unwrap();
+    wall = BricksWall::from_layout(&grid, &moving);
+    wall.update(2.0);
+    assert_eq!(wall.bricks_near(wall.brick_rect(0)), vec![0]);
+    assert!(wall.bricks_near(Rect::new(0.0, 0.0, 10.0, 10.0)).is_empty());
+
+    // The moved bricks are found like in an index built for their positions.
+    let moving = LevelLayout::parse(
+        "####\n####\n####\npath row 0 oscillate 120 1\npath cell 1 1 orbit 60 2\npath row 2 descend 80 300\n",
+    )
+    .unwrap();
+    wall = BricksWall::from_layout(&grid, &moving);
+    for step in 0..120 {
+        wall.update(1.0 / 30.0);
+        let mut rebuilt = wall.clone();
+        rebuilt.rebuild_index();
+        for query in 0..40 {
+            let bounds = Rect::new(
+                (query * 29 % 400) as f32,
+                (step * 7 + query * 13) as f32 % 500.0,
+                16.0,
+                16.0,
+            );
+            assert_eq!(wall.bricks_near(bounds), rebuilt.bricks_near(bounds));
+        }
+    }
+    assert!(LevelLayout::parse("#\npath row 0 descend -1 5\n").is_err());
+}
+
+#[test]
+fn test_fixed_timestep() {
+    let mut timestep = FixedTimestep::new(60, 5);
+    assert_eq!(timestep.advance(1.0 / 120.0), 0);
+    assert!((timestep.alpha() - 0.5).abs() < 0.001);
+    assert_eq!(timestep.advance(1.0 / 120.0 + 0.0001), 1);
+    assert_eq!(timestep.advance(1.0 / 30.0), 2);
+
+    // A long hitch runs only the allowed steps and drops the rest.
+    assert_eq!(timestep.advance(1.0), 5);
+    assert!(timestep.alpha() < 1.0);
+    assert_eq!(timestep.advance(0.0), 0);
+
+    let from = Point2 { x: 0.0, y: 10.0 };
+    let to = Point2 { x: 10.0, y: 30.0 };
+    assert_eq!(lerp_point(from, to, 0.0), from);
+    assert_eq!(lerp_point(from, to, 0.5), Point2 { x: 5.0, y: 20.0 });
+    assert_eq!(lerp_point(from, to, 1.0), to);
+}
+
+#[test]
+fn test_save_dir_location() {
+    let args = |args: &[&str]| {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<String>>()
+    };
+    let vars = |name: &str| match name {
+        "HOME" => Some(String::from("/home/player")),
+        DATA_DIR_ENV => Some(String::from("/srv/saves")),
+        _ => None,
+    };
+
+    let save_dir = SaveDir::from_args(&args(&["game"]), vars).unwrap();
+    assert_eq!(save_dir.data_dir, std::path::PathBuf::from("/srv/saves"));
+    assert_eq!(save_dir.profile, "default");
+    assert_eq!(save_dir.settings_file(), "/srv/saves/settings.txt");
+    assert_eq!(
+        save_dir.score_file(),
+        "/srv/saves/profiles/default/score.txt"
+    );
+
+    let save_dir = SaveDir::from_args(
+        &args(&["game", "--data-dir", "/tmp/saves", "--profile", "ana_2"]),
+        vars,
+    )
+    .unwrap();
+    assert_eq!(save_dir.run_file(), "/tmp/saves/profiles/ana_2/run.txt");
+    assert!(SaveDir::from_args(&args(&["game", "--profile", "../ana"]), vars).is_err());
+    assert!(SaveDir::from_args(&args(&["game", "--data-dir"]), vars).is_err());
+    assert!(is_valid_profile_name("Player-1"));
+    assert!(!is_valid_profile_name(""));
+    assert!(!is_valid_profile_name("a/b"));
+
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        let xdg = |name: &str| match name {
+            "XDG_DATA_HOME" => Some(String::from("/data")),
+            "HOME" => Some(String::from("/home/player")),
+            _ => None,
+        };
+        assert_eq!(
+            platform_data_dir(xdg),
+            Some(std::path::PathBuf::from("/data/brick_breaker"))
+        );
+        // A relative XDG_DATA_HOME is ignored like the specification says.
+        let relative = |name: &str| match name {
+            "XDG_DATA_HOME" => Some(String::from("data")),
+            "HOME" => Some(String::from("/home/player")),
+            _ => None,
+        };
+        assert_eq!(
+            platform_data_dir(relative),
+            Some(std::path::PathBuf::from(
+                "/home/player/.local/share/brick_breaker"
+            ))
+        );
+        assert_eq!(platform_data_dir(|_| None), None);
+    }
+}
+
+#[test]
+fn test_save_dir_profiles_keep_their_own_progress() {
+    let data_dir = std::env::temp_dir().join(format!("brick_breaker_data_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&data_dir);
+    let legacy_dir = data_dir.join("legacy");
+    std::fs::create_dir_all(&legacy_dir).unwrap();
+    std::fs::write(legacy_dir.join("score.txt"), "5\n50").unwrap();
+
+    let default = SaveDir::new(data_dir.clone(), "default").unwrap();
+    let other = SaveDir::new(data_dir.clone(), "other").unwrap();
+    assert_eq!(
+        Progress::load(&default.score_file()).unwrap(),
+        Progress::default()
+    );
+    default.create().unwrap();
+    default.import_legacy_files(&legacy_dir).unwrap();
+    other.create().unwrap();
+    other.import_legacy_files(&legacy_dir).unwrap();
+    assert_eq!(Progress::load(&default.score_file()).unwrap().level, 5);
+    assert_eq!(Progress::load(&other.score_file()).unwrap().level, 1);
+
+    // The import doesn't overwrite the progress made since.
+    Progress::parse("7\n70")
+        .unwrap()
+        .save(&default.score_file())
+        .unwrap();
+    default.import_legacy_files(&legacy_dir).unwrap();
+    assert_eq!(Progress::load(&default.score_file()).unwrap().level, 7);
+    assert_eq!(default.profiles(), vec!["default", "other"]);
+
+    // A file in the place of the profile directory is not replaced.
+    let blocked = SaveDir::new(data_dir.clone(), "blocked").unwrap();
+    std::fs::write(blocked.profile_dir(), "").unwrap();
+    assert!(blocked.create().is_err());
+    std::fs::remove_dir_all(&data_dir).unwrap();
+}
+
+#[test]
+fn test_statistics_rally_and_combo() {
+    let bounce = StepEvents {
+        bounced: true,
+        board_bounced: true,
+        ..StepEvents::default()
+    };
+    let broke = |normal, weak| StepEvents {
+        bounced: true,
+        bricks_broken: BrokenBricks { normal, weak },
+        ..StepEvents::default()
+    };
+    let lost = StepEvents {
+        ball_lost: true,
+        ..StepEvents::default()
+    };
+
+    let mut statistics = Statistics::default();
+    statistics.record_game_start();
+    for events in &[
+        bounce,
+        broke(1, 1),
+        broke(1, 0),
+        bounce,
+        broke(0, 1),
+        bounce,
+        bounce,
+    ] {
+        statistics.record_step(events, 0.5);
+    }
+    statistics.record_step(&lost, 0.5);
+    statistics.record_game_start();
+    statistics.record_step(&bounce, 0.5);
+
+    assert_eq!(statistics.games_played, 2);
+    assert_eq!(
+        statistics.bricks_broken,
+        BrokenBricks { normal: 2, weak: 2 }
+    );
+    assert_eq!(statistics.balls_lost, 1);
+    assert_eq!(statistics.longest_rally, 4);
+    assert_eq!(statistics.best_combo, 3);
+    assert_eq!(statistics.play_seconds, 4.5);
+    assert_eq!(statistics.play_time_text(), "0:00:04");
+
+    let saved = Statistics::parse(&statistics.to_file_contents()).unwrap();
+    assert_eq!(saved.to_file_contents(), statistics.to_file_contents());
+    assert_eq!(saved.longest_rally, 4);
+    assert!(Statistics::parse("games_played -1\n").is_err());
+    assert!(Statistics::parse("play_seconds inf\n").is_err());
+    assert!(Statistics::parse("high_score 3\n").is_err());
+}
+
+#[test]
+fn test_step_events_count_the_broken_bricks() {
+    let mut gameplay_state = common::level_gameplay_state("#+@#+@#+@#+@\n+#+#+#+#+#+#\n");
+    let mut statistics = Statistics::default();
+    for events in common::step(&mut gameplay_state, 0.0, 3000) {
+        statistics.record_step(&events, common::STEP_SECONDS);
+    }
+
+    let broken = |kind| {
+        gameplay_state
+            .bricks_wall
+            .bricks
+            .iter()
+            .filter(|brick| brick.kind == kind && brick.state == BrickState::Broken)
+            .count()
+    };
+    assert!(statistics.bricks_broken.total() > 0);
+    assert_eq!(statistics.bricks_broken.normal, broken(BrickKind::Normal));
+    assert_eq!(statistics.bricks_broken.weak, broken(BrickKind::Weak));
+    assert_eq!(statistics.balls_lost, 0);
+}
+
+#[test]
+fn test_achievement_definitions() {
+    let definitions = Achievements::parse_definitions(
+        "; a comment\n\nfirst_clear clears_in_a_game 1 Clear a level  without losing\n",
+    )
+    .unwrap();
+    assert_eq!(definitions.len(), 1);
+    assert_eq!(definitions[0].id, "first_clear");
+    assert_eq!(definitions[0].condition, Condition::ClearsInAGame);
+    assert_eq!(definitions[0].target, 1);
+    assert_eq!(definitions[0].title, "Clear a level without losing");
+
+    assert!(Achievements::parse_definitions("a games_played 1\n").is_err());
+    assert!(Achievements::parse_definitions("a high_score 1 Title\n").is_err());
+    assert!(Achievements::parse_definitions("a games_played -1 Title\n").is_err());
+    assert!(Achievements::parse_definitions("a/b games_played 1 Title\n").is_err());
+    assert!(Achievements::parse_definitions("a games_played 1 One\na stars 3 Two\n").is_err());
+
+    // The achievements of the game are all valid.
+    let contents = std::fs::read_to_string("resources/achievements.txt").unwrap();
+    let definitions = Achievements::parse_definitions(&contents).unwrap();
+    assert!(definitions
+        .iter()
+        .any(|achievement| achievement.id == "thousand_bricks"
+            && achievement.condition == Condition::BricksBroken
+            && achievement.target == 1000));
+}
+
+#[test]
+fn test_achievements_unlock_once_and_are_saved() {
+    let mut achievements = Achievements {
+        definitions: Achievements::parse_definitions(
+            "first_game games_played 1 Play\nlevel_3 reached_level 3 Reach level 3\n",
+        )
+        .unwrap(),
+        ..Achievements::default()
+    };
+    let mut facts = Facts {
+        games_played: 1,
+        reached_level: 2,
+        ..Facts::default()
+    };
+    let unlocked = achievements.check(&facts);
+    assert_eq!(unlocked.len(), 1);
+    assert_eq!(unlocked[0].id, "first_game");
+    assert!(achievements.check(&facts).is_empty());
+
+    facts.reached_level = 3;
+    facts.play_seconds = 119.0;
+    assert_eq!(facts.value(Condition::PlayMinutes), 1);
+    assert_eq!(achievements.check(&facts)[0].title, "Reach level 3");
+    assert!(achievements.is_unlocked("level_3"));
+
+    let mut statistics = Statistics::default();
+    statistics.record_game_start();
+    statistics.record_clear();
+    assert_eq!(
+        Facts::new(&statistics, &Progress::default()).clears_in_a_game,
+        1
+    );
+    statistics.record_game_start();
+    assert_eq!(
+        Facts::new(&statistics, &Progress::default()).clears_in_a_game,
+        0
+    );
+
+    let dir =
+        std::env::temp_dir().join(format!("brick_breaker_achievements_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("achievements.txt");
+    let file = file.to_str().unwrap();
+    achievements.save(file).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(file).unwrap(),
+        "first_game\nlevel_3\n"
+    );
+
+    // The definitions fall back to the built-in ones.
+    let missing = dir.join("missing.txt");
+    let loaded = Achievements::load(missing.to_str().unwrap(), file).unwrap();
+    assert!(loaded.is_unlocked("first_game"));
+    assert!(!loaded.definitions.is_empty());
+    let loaded = Achievements::load(missing.to_str().unwrap(), missing.to_str().unwrap()).unwrap();
+    assert!(loaded.unlocked.is_empty());
+    std::fs::write(file, "not an id!\n").unwrap();
+    assert!(Achievements::load(missing.to_str().unwrap(), file).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_toasts_show_one_after_another() {
+    let mut toasts = Toasts::default();
+    assert_eq!(toasts.current(), None);
+    toasts.push(String::from("First"));
+    toasts.push(String::from("Second"));
+    toasts.update(Toasts::SECONDS - 0.5);
+    assert_eq!(toasts.current(), Some("First"));
+    toasts.update(0.5);
+    assert_eq!(toasts.current(), Some("Second"));
+    toasts.update(Toasts::SECONDS);
+    assert_eq!(toasts.current(), None);
+}
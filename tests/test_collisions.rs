@@ -0,0 +1,256 @@
+use crate::gameplay_entities::{
+    Ball, BallWatchdog, Brick, BrickGrid, BrickHits, BrickKind, BrickState, BricksWall,
+};
+use crate::levels::LevelLayout;
+use crate::math::{circle_contact_rect, merge_contacts, Contact};
+use crate::types::{Point2, Rect, Vector2};
+use brick_breaker::*;
+use proptest::prelude::*;
+
+const SCREEN_WIDTH: f32 = 800.0;
+const SCREEN_HEIGHT: f32 = 600.0;
+const STEP_SECONDS: f32 = 1.0 / 60.0;
+// The ball may still touch a brick by the floating point error.
+const MAX_DEPTH: f32 = 0.001;
+
+fn brick_rect(brick: &Brick, brick_size: (f32, f32)) -> Rect {
+    Rect::new(brick.pos.x, brick.pos.y, brick_size.0, brick_size.1)
+}
+
+fn ball_at(x: f32, y: f32, direction: Point2<f32>) -> Ball {
+    let mut ball = Ball::new(SCREEN_WIDTH, SCREEN_HEIGHT, 16.0, 16.0, 16.0);
+    ball.pos = Point2 {
+        x: x - ball.radius,
+        y: y - ball.radius,
+    };
+    ball.direction = direction;
+    ball
+}
+
+// Moves the ball for a step and resolves its collisions with the wall.
+fn step(ball: &mut Ball, wall: &mut BricksWall) -> BrickHits {
+    wall.update(STEP_SECONDS);
+    ball.pos.x += ball.speed * STEP_SECONDS * ball.direction.x;
+    ball.pos.y += ball.speed * STEP_SECONDS * ball.direction.y;
+    let brick_size = wall.brick_size;
+    wall.collide_ball(ball, STEP_SECONDS, |brick| brick_rect(brick, brick_size))
+        .unwrap()
+}
+
+fn deepest_overlap(ball: &Ball, wall: &BricksWall, bricks: &[usize]) -> f32 {
+    bricks
+        .iter()
+        .filter_map(|&index| ball.contact_rect(wall.brick_rect(index)))
+        .map(|contact| contact.depth)
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn test_circle_contact_rect() {
+    let contact = circle_contact_rect(1.0, 1.0, 1.5, 2.0, 0.0, 4.0, 2.0).unwrap();
+    assert_eq!(contact.normal, Vector2 { x: -1.0, y: 0.0 });
+    assert!((contact.depth - 0.5).abs() < 0.001);
+
+    assert_eq!(circle_contact_rect(0.0, 3.0, 1.0, 2.0, 0.0, 4.0, 2.0), None);
+
+    // A corner gives a diagonal normal.
+    let contact = circle_contact_rect(1.5, -0.5, 1.0, 2.0, 0.0, 4.0, 2.0).unwrap();
+    assert!((contact.normal.x + 0.7071).abs() < 0.001);
+    assert!((contact.normal.y + 0.7071).abs() < 0.001);
+
+    // The center inside the rectangle leaves through the closest edge.
+    let contact = circle_contact_rect(5.5, 1.0, 1.0, 2.0, 0.0, 4.0, 2.0).unwrap();
+    assert_eq!(contact.normal, Vector2 { x: 1.0, y: 0.0 });
+    assert!((contact.depth - 1.5).abs() < 0.001);
+}
+
+#[test]
+fn test_merge_contacts() {
+    assert_eq!(merge_contacts(&[]), None);
+
+    let top = Contact {
+        normal: Vector2 { x: 0.0, y: -1.0 },
+        depth: 2.0,
+    };
+    let merged = merge_contacts(&[top, top]).unwrap();
+    assert_eq!(merged.normal, top.normal);
+
+    let left = Contact {
+        normal: Vector2 { x: -1.0, y: 0.0 },
+        depth: 3.0,
+    };
+    let merged = merge_contacts(&[top, left]).unwrap();
+    assert!((merged.normal.x + 0.7071).abs() < 0.001);
+    assert!((merged.normal.y + 0.7071).abs() < 0.001);
+    assert_eq!(merged.depth, 3.0);
+
+    let bottom = Contact {
+        normal: Vector2 { x: 0.0, y: 1.0 },
+        depth: 1.0,
+    };
+    assert_eq!(merge_contacts(&[top, bottom]), Some(top));
+}
+
+#[test]
+fn test_ball_on_the_seam_of_two_bricks_bounces_once() {
+    let grid = BrickGrid::new(SCREEN_WIDTH, SCREEN_HEIGHT, 20.0, 40.0);
+    let mut layout = LevelLayout::empty(grid.columns, grid.rows);
+    layout.set(0, 0, Some(BrickKind::Normal));
+    layout.set(1, 0, Some(BrickKind::Normal));
+    let mut wall = BricksWall::from_layout(&grid, &layout);
+    wall.bricks[1].pos.x = wall.bricks[0].pos.x + wall.brick_size.0;
+    wall.rebuild_index();
+
+    let seam = wall.bricks[1].pos;
+    let mut ball = ball_at(seam.x, seam.y - 7.0, Point2 { x: 0.0, y: 1.0 });
+    let brick_size = wall.brick_size;
+    let hits = wall
+        .collide_ball(&mut ball, 0.0, |brick| brick_rect(brick, brick_size))
+        .unwrap();
+    assert!(hits.bricks > 0);
+    assert_eq!(ball.direction, Point2 { x: 0.0, y: -1.0 });
+    assert!(wall
+        .bricks
+        .iter()
+        .all(|brick| matches!(brick.state, BrickState::Touched)));
+    assert_eq!(deepest_overlap(&ball, &wall, &[0, 1]), 0.0);
+}
+
+#[test]
+fn test_ball_bounces_back_from_a_corner() {
+    let grid = BrickGrid::new(SCREEN_WIDTH, SCREEN_HEIGHT, 20.0, 40.0);
+    let mut layout = LevelLayout::empty(grid.columns, grid.rows);
+    layout.set(0, 0, Some(BrickKind::Normal));
+    let mut wall = BricksWall::from_layout(&grid, &layout);
+
+    let corner = wall.bricks[0].pos;
+    let mut ball = ball_at(corner.x - 5.0, corner.y - 5.0, Point2 { x: 1.0, y: 1.0 });
+    let brick_size = wall.brick_size;
+    let hits = wall
+        .collide_ball(&mut ball, 0.0, |brick| brick_rect(brick, brick_size))
+        .unwrap();
+    assert!(hits.bricks > 0);
+    assert!((ball.direction.x + 1.0).abs() < 0.001);
+    assert!((ball.direction.y + 1.0).abs() < 0.001);
+    assert_eq!(deepest_overlap(&ball, &wall, &[0]), 0.0);
+}
+
+#[test]
+fn test_ball_stays_inside_the_screen() {
+    let mut ball = ball_at(3.0, 3.0, Point2 { x: -1.0, y: -1.0 });
+    let pos = ball.calculate_new_position(1.0 / 60.0, SCREEN_WIDTH, SCREEN_HEIGHT, 16.0, 16.0);
+    assert!(pos.x >= 0.0 && pos.y >= 0.0);
+    assert_eq!(ball.direction, Point2 { x: 1.0, y: 1.0 });
+
+    // A ball which is already outside doesn't flip back and forth.
+    let mut ball = ball_at(SCREEN_WIDTH + 20.0, 100.0, Point2 { x: 1.0, y: 1.0 });
+    for _ in 0..3 {
+        let pos = ball.calculate_new_position(1.0 / 60.0, SCREEN_WIDTH, SCREEN_HEIGHT, 16.0, 16.0);
+        assert!(pos.x <= SCREEN_WIDTH - 16.0);
+        assert_eq!(ball.direction.x, -1.0);
+        ball.update(pos);
+    }
+}
+
+#[test]
+fn test_ball_inside_the_skateboard_leaves_through_the_top() {
+    let board = Rect::new(300.0, 560.0, 120.0, 20.0);
+    let mut ball = ball_at(310.0, 575.0, Point2 { x: 1.0, y: 1.0 });
+    assert!(ball.bounce_off_board(board));
+    assert!((ball.direction.x - 1.0).abs() < 0.001);
+    assert!((ball.direction.y + 1.0).abs() < 0.001);
+    assert!(ball.contact_rect(board).is_none());
+    assert!(!ball.bounce_off_board(board));
+}
+
+#[test]
+fn test_min_vertical_speed_and_watchdog() {
+    let mut ball = ball_at(
+        100.0,
+        100.0,
+        Point2 {
+            x: 2.0_f32.sqrt(),
+            y: 0.0,
+        },
+    );
+    ball.enforce_min_vertical_speed();
+    let length = (ball.direction.x.powi(2) + ball.direction.y.powi(2)).sqrt();
+    assert!((length - 2.0_f32.sqrt()).abs() < 0.001);
+    assert!(ball.direction.y.abs() >= Ball::MIN_VERTICAL_RATIO * length - 0.001);
+    assert!(ball.direction.x > 0.0);
+
+    let mut ball = ball_at(100.0, 100.0, Point2 { x: 0.0, y: -1.0 });
+    let mut watchdog = BallWatchdog::default();
+    assert!(!watchdog.update(BallWatchdog::NUDGE_SECONDS / 2.0, false, &mut ball));
+    assert!(!watchdog.update(BallWatchdog::NUDGE_SECONDS / 2.0, true, &mut ball));
+    assert_eq!(ball.direction, Point2 { x: 0.0, y: -1.0 });
+    assert!(!watchdog.update(BallWatchdog::NUDGE_SECONDS / 2.0, false, &mut ball));
+    assert!(watchdog.update(BallWatchdog::NUDGE_SECONDS / 2.0, false, &mut ball));
+    assert!(ball.direction.x != 0.0);
+    assert!(ball.direction.y < 0.0);
+    assert_eq!(watchdog.seconds_without_brick, 0.0);
+}
+
+fn layout_strategy(columns: usize, rows: usize) -> impl Strategy<Value = LevelLayout> {
+    (
+        prop::collection::vec(any::<bool>(), columns * rows),
+        prop::option::of((0..rows, 5.0_f32..60.0, 0.5_f32..4.0)),
+    )
+        .prop_map(move |(cells, path)| {
+            let mut contents = String::new();
+            for row in cells.chunks(columns) {
+                for &cell in row {
+                    contents.push(if cell { '#' } else { '.' });
+                }
+                contents.push('\n');
+            }
+            if let Some((row, amplitude, period)) = path {
+                contents.push_str(&format!(
+                    "path row {} oscillate {} {}\n",
+                    row, amplitude, period
+                ));
+            }
+            LevelLayout::parse(&contents).unwrap()
+        })
+}
+
+proptest! {
+    #[test]
+    fn test_ball_never_ends_up_inside_a_brick(
+        layout in layout_strategy(8, 5),
+        x in 0.0_f32..SCREEN_WIDTH,
+        y in 0.0_f32..SCREEN_HEIGHT * 0.7,
+        angle in 0.0_f32..std::f32::consts::TAU,
+    ) {
+        let grid = BrickGrid::new(SCREEN_WIDTH, SCREEN_HEIGHT, 20.0, 40.0);
+        let mut wall = BricksWall::from_layout(&grid, &layout);
+        let direction = Point2 {
+            x: angle.cos() * 2.0_f32.sqrt(),
+            y: angle.sin() * 2.0_f32.sqrt(),
+        };
+        let mut ball = ball_at(x, y, direction);
+
+        for _ in 0..60 {
+            let standing = (0..wall.bricks.len())
+                .filter(|&index| !matches!(wall.bricks[index].state, BrickState::Broken))
+                .collect::<Vec<usize>>();
+            step(&mut ball, &mut wall);
+            prop_assert!(deepest_overlap(&ball, &wall, &standing) <= MAX_DEPTH);
+        }
+    }
+
+    #[test]
+    fn test_single_contact_reflection_keeps_the_speed(
+        x in 0.0_f32..SCREEN_WIDTH,
+        y in 0.0_f32..SCREEN_HEIGHT * 0.7,
+        angle in 0.0_f32..std::f32::consts::TAU,
+    ) {
+        let mut wall = BricksWall::new(SCREEN_WIDTH, SCREEN_HEIGHT, 20.0, 40.0);
+        let direction = Point2 { x: angle.cos(), y: angle.sin() };
+        let mut ball = ball_at(x, y, direction);
+
+        step(&mut ball, &mut wall);
+        let length = (ball.direction.x.powi(2) + ball.direction.y.powi(2)).sqrt();
+        prop_assert!((length - 1.0).abs() < 0.001);
+    }
+}
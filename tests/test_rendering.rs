@@ -0,0 +1,351 @@
+use crate::dialogs_handler::{DialogType, DialogsHandler};
+use crate::game_settings_entities::{Score, ScoreDetails};
+use crate::gameplay_entities::{GameplayState, SpriteSizes};
+use crate::progress::Progress;
+use crate::renderer::{
+    render, DrawCommand, DrawList, FixedTextMetrics, RectStyle, Renderer, SpriteKind, TextMetrics,
+};
+use crate::software_renderer::SoftwareRenderer;
+use crate::statistics::Statistics;
+use crate::terminal_renderer::TerminalRenderer;
+use crate::theme::Palette;
+use crate::types::{Point2, WHITE};
+use crate::widgets::Toasts;
+use brick_breaker::*;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+mod common;
+
+const SCREEN_WIDTH: f32 = 800.0;
+const SCREEN_HEIGHT: f32 = 600.0;
+const GOLDEN_DIR: &str = "tests/golden";
+// The largest difference of a channel which still counts as the same colour.
+const CHANNEL_TOLERANCE: u8 = 2;
+// The part of the pixels which may differ more, for the rounding of the edges.
+const MAX_DIFFERENT_PIXELS: f32 = 0.001;
+
+fn renderer() -> SoftwareRenderer {
+    SoftwareRenderer::new(
+        SCREEN_WIDTH as usize,
+        SCREEN_HEIGHT as usize,
+        SpriteSizes::HEADLESS,
+        Palette::default(),
+    )
+}
+
+fn read_png(path: &Path) -> (u32, u32, Vec<u8>) {
+    let decoder = png::Decoder::new(File::open(path).unwrap());
+    let (info, mut reader) = decoder.read_info().unwrap();
+    let mut pixels = vec![0; info.buffer_size()];
+    reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(info.color_type, png::ColorType::RGBA);
+    (info.width, info.height, pixels)
+}
+
+fn write_png(path: &Path, renderer: &SoftwareRenderer) {
+    let file = BufWriter::new(File::create(path).unwrap());
+    let mut encoder = png::Encoder::new(file, renderer.width as u32, renderer.height as u32);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(renderer.pixels()).unwrap();
+}
+
+// Compares the image with the golden one of the name. UPDATE_GOLDEN=1 writes
+// the images after an intended change of the drawing or for a new one, a
+// missing image fails otherwise.
+fn assert_matches_golden(name: &str, renderer: &SoftwareRenderer) {
+    let path = Path::new(GOLDEN_DIR).join(format!("{}.png", name));
+    if std::env::var("UPDATE_GOLDEN").as_deref() == Ok("1") {
+        write_png(&path, renderer);
+        return;
+    }
+    assert!(
+        path.exists(),
+        "The golden image {} is missing, UPDATE_GOLDEN=1 writes it",
+        path.display()
+    );
+
+    let (width, height, golden) = read_png(&path);
+    assert_eq!(
+        (width as usize, height as usize),
+        (renderer.width, renderer.height)
+    );
+    let different_pixels = golden
+        .chunks(4)
+        .zip(renderer.pixels().chunks(4))
+        .filter(|(expected, actual)| {
+            expected
+                .iter()
+                .zip(actual.iter())
+                .any(|(a, b)| (*a as i16 - *b as i16).abs() > CHANNEL_TOLERANCE as i16)
+        })
+        .count();
+    let allowed = (MAX_DIFFERENT_PIXELS * (width * height) as f32) as usize;
+    assert!(
+        different_pixels <= allowed,
+        "{} pixels of {} differ from {}",
+        different_pixels,
+        name,
+        path.display()
+    );
+}
+
+fn score() -> Score {
+    Score {
+        score_result: 120,
+        level: 3,
+        max_score_result: 450,
+        endless_seed: None,
+    }
+}
+
+fn gameplay_state() -> GameplayState {
+    let mut state = common::level_gameplay_state("#+@#\n.##.\n@..+\n");
+    state.bricks_wall.bricks[0].broke().unwrap();
+    state.bricks_wall.bricks[1].broke().unwrap();
+    state
+}
+
+fn statistics() -> Statistics {
+    Statistics::parse(
+        "games_played 12\nbricks_normal 340\nbricks_weak 95\nballs_lost 11\n\
+         longest_rally 27\nbest_combo 9\nplay_seconds 5025.5\n",
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_gameplay_matches_golden() {
+    let sizes = SpriteSizes::HEADLESS;
+    let state = gameplay_state();
+    let mut score_details = ScoreDetails::new(Progress::default());
+    score_details.score = score();
+
+    let mut renderer = renderer();
+    render(&mut renderer, |draw_list| {
+        score_details.draw(draw_list, SCREEN_WIDTH)?;
+        state.draw(draw_list, &Palette::default(), &sizes, 1.0)
+    })
+    .unwrap();
+    assert_matches_golden("gameplay", &renderer);
+}
+
+#[test]
+fn test_dialogs_match_golden() {
+    let dialogs = [
+        ("main_menu", DialogType::MainMenuDialog),
+        ("game_over", DialogType::GameOverDialog),
+        ("next_level", DialogType::NextLevelDialog),
+        ("pause", DialogType::PauseDialog),
+        ("quit", DialogType::QuitDialog),
+        ("statistics", DialogType::StatisticsDialog),
+    ];
+    for (name, dialog_type) in dialogs.iter() {
+        let mut dialogs_handler = DialogsHandler::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+        let mut renderer = renderer();
+        render(&mut renderer, |draw_list| {
+            dialogs_handler.draw(
+                draw_list,
+                *dialog_type,
+                score(),
+                &Progress::default(),
+                &statistics(),
+                &Palette::default(),
+            )
+        })
+        .unwrap();
+        assert_matches_golden(name, &renderer);
+    }
+}
+
+#[test]
+fn test_software_renderer_draws_the_sprites_and_the_text() {
+    let mut renderer = renderer();
+    let background = renderer.pixel(0, 0);
+    renderer
+        .sprite(
+            SpriteKind::BrickSurvived,
+            Point2 { x: 10.0, y: 10.0 },
+            WHITE,
+        )
+        .unwrap();
+    assert_ne!(renderer.pixel(15, 15), background);
+    assert_eq!(renderer.pixel(75, 15), background);
+
+    let (width, height) = renderer.text_size("label", "Score");
+    assert_eq!(width, 5.0 * renderer.metrics.glyph_width);
+    assert_eq!(height, renderer.metrics.glyph_height);
+}
+
+#[test]
+fn test_gameplay_draw_commands() {
+    let state = gameplay_state();
+    let mut metrics = FixedTextMetrics::default();
+    let mut draw_list = DrawList::new(&mut metrics);
+    state
+        .draw(
+            &mut draw_list,
+            &Palette::default(),
+            &SpriteSizes::HEADLESS,
+            1.0,
+        )
+        .unwrap();
+
+    let sprites = draw_list
+        .commands
+        .iter()
+        .filter_map(|command| match command {
+            DrawCommand::Sprite { sprite, .. } => Some(*sprite),
+            _ => None,
+        })
+        .collect::<Vec<SpriteKind>>();
+    let standing_bricks = state.bricks_wall.bricks.len() - state.bricks_wall.broken_bricks_count();
+    assert_eq!(sprites.len(), standing_bricks + 2);
+    assert_eq!(sprites.first(), Some(&SpriteKind::SkateboardNormal));
+    assert_eq!(sprites.last(), Some(&SpriteKind::Ball));
+}
+
+#[test]
+fn test_menu_draw_commands() {
+    let mut dialogs_handler = DialogsHandler::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+    let mut metrics = FixedTextMetrics::default();
+    let mut draw_list = DrawList::new(&mut metrics);
+    dialogs_handler
+        .draw(
+            &mut draw_list,
+            DialogType::PauseDialog,
+            score(),
+            &Progress::default(),
+            &Statistics::default(),
+            &Palette::default(),
+        )
+        .unwrap();
+
+    let commands = draw_list.into_commands();
+    let styles = commands
+        .iter()
+        .filter_map(|command| match command {
+            DrawCommand::Rectangle { style, .. } => Some(*style),
+            _ => None,
+        })
+        .collect::<Vec<RectStyle>>();
+    assert_eq!(
+        styles,
+        vec![
+            RectStyle::Fill,
+            RectStyle::Stroke(2.0),
+            RectStyle::Stroke(2.0)
+        ]
+    );
+    assert!(matches!(
+        &commands[0],
+        DrawCommand::Text { label, .. } if label == "Paused"
+    ));
+}
+
+#[test]
+fn test_toast_draw_commands() {
+    let mut toasts = Toasts::default();
+    toasts.push(String::from("Achievement unlocked: Play"));
+    let mut metrics = FixedTextMetrics::default();
+    let mut draw_list = DrawList::new(&mut metrics);
+    toasts
+        .draw(&mut draw_list, SCREEN_WIDTH, &Palette::default())
+        .unwrap();
+
+    let commands = draw_list.into_commands();
+    assert_eq!(commands.len(), 2);
+    assert!(matches!(
+        &commands[0],
+        DrawCommand::Rectangle {
+            style: RectStyle::Fill,
+            ..
+        }
+    ));
+    assert!(matches!(
+        &commands[1],
+        DrawCommand::Text { label, .. } if label == "Achievement unlocked: Play"
+    ));
+}
+
+fn terminal_renderer() -> TerminalRenderer {
+    TerminalRenderer::new(
+        TerminalRenderer::COLUMNS,
+        TerminalRenderer::ROWS,
+        (SCREEN_WIDTH, SCREEN_HEIGHT),
+        SpriteSizes::HEADLESS,
+        Palette::default(),
+    )
+}
+
+#[test]
+fn test_terminal_renderer_draws_the_gameplay_in_cells() {
+    let sizes = SpriteSizes::HEADLESS;
+    let state = gameplay_state();
+    let mut renderer = terminal_renderer();
+    render(&mut renderer, |draw_list| {
+        state.draw(draw_list, &Palette::default(), &sizes, 1.0)
+    })
+    .unwrap();
+
+    let lines = renderer.lines();
+    assert_eq!(lines.len(), TerminalRenderer::ROWS);
+    assert!(lines
+        .iter()
+        .all(|line| line.chars().count() == TerminalRenderer::COLUMNS));
+    // A brick is 60 by 20 pixels, six cells of a row, and the two broken
+    // bricks are drawn touched.
+    assert_eq!(renderer.cell(5, 2).character, '▓');
+    assert_eq!(lines[2].matches("▓▓▓▓▓▓").count(), 2);
+    assert_eq!(lines[2].matches("██████").count(), 2);
+    assert!(lines.iter().any(|line| line.contains('●')));
+    assert!(lines.iter().any(|line| line.contains(&"▀".repeat(12))));
+}
+
+#[test]
+fn test_terminal_renderer_draws_the_dialogs_as_text() {
+    let mut dialogs_handler = DialogsHandler::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+    let mut renderer = terminal_renderer();
+    render(&mut renderer, |draw_list| {
+        dialogs_handler.draw(
+            draw_list,
+            DialogType::PauseDialog,
+            score(),
+            &Progress::default(),
+            &Statistics::default(),
+            &Palette::default(),
+        )
+    })
+    .unwrap();
+
+    let lines = renderer.lines();
+    assert!(lines.iter().any(|line| line.contains("Paused")));
+    assert!(lines.iter().any(|line| line.contains("Resume")));
+    assert!(lines.iter().any(|line| line.contains('┌')));
+
+    let (width, height) = renderer.text_size("label", "Score");
+    assert_eq!(width, 5.0 * renderer.cell_width());
+    assert_eq!(height, renderer.cell_height());
+}
+
+#[test]
+fn test_terminal_renderer_without_room_draws_a_cell() {
+    let sizes = SpriteSizes::HEADLESS;
+    let state = gameplay_state();
+    let mut renderer = TerminalRenderer::new(
+        0,
+        0,
+        (SCREEN_WIDTH, SCREEN_HEIGHT),
+        sizes,
+        Palette::default(),
+    );
+    render(&mut renderer, |draw_list| {
+        state.draw(draw_list, &Palette::default(), &sizes, 1.0)
+    })
+    .unwrap();
+    assert_eq!((renderer.columns, renderer.rows), (1, 1));
+    assert_eq!(renderer.lines().len(), 1);
+}
@@ -0,0 +1,106 @@
+#![cfg(feature = "serde")]
+
+use crate::game_settings_entities::Score;
+use crate::game_workflow_state_reducer::{GameWorkflowIntent, GameWorkflowState};
+use crate::gameplay_entities::{Ball, BallState, Brick, BrickKind, BrickPath, GameplayState};
+use crate::types::Point2;
+use brick_breaker::*;
+
+mod common;
+
+fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+    serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+}
+
+#[test]
+fn test_ball_snapshot() {
+    let ball = Ball {
+        state: BallState::Flying,
+        pos: Point2 { x: 10.0, y: 20.5 },
+        previous_pos: Point2 { x: 9.0, y: 20.0 },
+        direction: Point2 { x: 0.5, y: -1.0 },
+        radius: 8.0,
+        speed: 4.0,
+    };
+
+    // The points are written with their names, not as arrays.
+    assert_eq!(
+        serde_json::to_string(&ball).unwrap(),
+        concat!(
+            r#"{"state":"Flying","pos":{"x":10.0,"y":20.5},"#,
+            r#""previous_pos":{"x":9.0,"y":20.0},"direction":{"x":0.5,"y":-1.0},"#,
+            r#""radius":8.0,"speed":4.0}"#
+        )
+    );
+    let restored = round_trip(&ball);
+    assert_eq!(restored.pos, ball.pos);
+    assert_eq!(restored.direction, ball.direction);
+    assert_eq!(restored.state, ball.state);
+    assert!(serde_json::from_str::<Ball>(r#"{"state":"Flying","pos":[1.0,2.0]}"#).is_err());
+}
+
+#[test]
+fn test_brick_snapshot() {
+    let mut brick = Brick::new(BrickKind::Weak, Point2 { x: 40.0, y: 60.0 });
+    brick.path = Some(BrickPath::Orbit {
+        radius: 20.0,
+        period: 3.0,
+    });
+
+    let json = serde_json::to_string(&brick).unwrap();
+    assert!(json.contains(r#""kind":"Weak""#));
+    assert!(json.contains(r#""path":{"Orbit":{"radius":20.0,"period":3.0}}"#));
+    assert!(json.contains(r#""base_pos":{"x":40.0,"y":60.0}"#));
+    assert_eq!(serde_json::to_string(&round_trip(&brick)).unwrap(), json);
+}
+
+#[test]
+fn test_gameplay_state_round_trip_continues_the_same_game() {
+    let mut gameplay_state = common::level_gameplay_state("#+@#\n.##.\npath row 1 orbit 20 3\n");
+    common::step(&mut gameplay_state, 0.5, 90);
+
+    let json = serde_json::to_string(&gameplay_state).unwrap();
+    assert!(!json.contains("index"));
+    let mut restored_state: GameplayState = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&restored_state).unwrap(), json);
+
+    // The index of the wall is built again, so the bricks are still hit.
+    assert_eq!(
+        common::step(&mut gameplay_state, -0.5, 240),
+        common::step(&mut restored_state, -0.5, 240)
+    );
+    assert_eq!(
+        serde_json::to_string(&restored_state).unwrap(),
+        serde_json::to_string(&gameplay_state).unwrap()
+    );
+}
+
+#[test]
+fn test_score_and_workflow_round_trip() {
+    let score = Score {
+        score_result: 12,
+        level: 4,
+        max_score_result: 30,
+        endless_seed: Some(7),
+    };
+    assert_eq!(
+        serde_json::to_string(&score).unwrap(),
+        r#"{"score_result":12,"level":4,"max_score_result":30,"endless_seed":7}"#
+    );
+    let restored = round_trip(&score);
+    assert_eq!(restored.score_result, 12);
+    assert_eq!(restored.level, 4);
+    assert_eq!(restored.max_score_result, 30);
+    assert_eq!(restored.endless_seed, Some(7));
+
+    for state in GameWorkflowState::ALL.iter() {
+        assert_eq!(round_trip(state), *state);
+    }
+    for intent in GameWorkflowIntent::ALL.iter() {
+        assert_eq!(round_trip(intent), *intent);
+    }
+    assert_eq!(
+        serde_json::to_string(&GameWorkflowState::Settings { from_pause: true }).unwrap(),
+        r#"{"Settings":{"from_pause":true}}"#
+    );
+}
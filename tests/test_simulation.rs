@@ -0,0 +1,103 @@
+use crate::autopilot::AutopilotSkill;
+use crate::levels::LevelLayout;
+use crate::settings::Difficulty;
+use crate::simulation::{
+    run_game, stats_to_csv, stats_to_json, GameStats, LevelSource, Policy, ScriptedInput,
+    SimulationConfig,
+};
+use brick_breaker::*;
+
+fn stats() -> GameStats {
+    GameStats {
+        seed: 3,
+        level: 2,
+        difficulty: Difficulty::Hard,
+        policy: "perfect",
+        cleared: true,
+        seconds: 90.0,
+        balls_lost: 1,
+        bounces: 40,
+        bricks_broken: 30,
+    }
+}
+
+#[test]
+fn test_parse_script() {
+    let policy = Policy::parse_script("; go right, then left\n2 -1\n0 1.5\n").unwrap();
+    assert_eq!(
+        policy,
+        Policy::Scripted(vec![
+            ScriptedInput {
+                seconds: 0.0,
+                movement: 1.0
+            },
+            ScriptedInput {
+                seconds: 2.0,
+                movement: -1.0
+            },
+        ])
+    );
+    assert!(Policy::parse_script("1").is_err());
+    assert!(Policy::parse_script("left 1").is_err());
+    assert!(Policy::parse_script("1 NaN").is_err());
+    assert!(Policy::parse_script("inf 1").is_err());
+}
+
+#[test]
+fn test_stats_output() {
+    assert_eq!(stats().bricks_per_minute(), 20.0);
+    assert_eq!(
+        stats_to_csv(&[stats()]),
+        format!(
+            "{}\n3,2,hard,perfect,true,90.00,1,40,30,20.00\n",
+            GameStats::CSV_HEADER
+        )
+    );
+    assert_eq!(
+        stats_to_json(&[stats()]),
+        "[\n  {\"seed\":3,\"level\":2,\"difficulty\":\"hard\",\"policy\":\"perfect\",\"cleared\":true,\"seconds\":90.00,\"balls_lost\":1,\"bounces\":40,\"bricks_broken\":30,\"bricks_per_minute\":20.00}\n]\n"
+    );
+}
+
+#[test]
+fn test_run_game_is_repeatable() {
+    let config = SimulationConfig::default();
+    let layout = config.level_layout(&LevelSource::Generated, 1, 5).unwrap();
+    let policy = Policy::Tracking(AutopilotSkill::Skilled);
+    let first = run_game(&config, &layout, Difficulty::Normal, &policy, 5, 1).unwrap();
+    let second = run_game(&config, &layout, Difficulty::Normal, &policy, 5, 1).unwrap();
+    assert_eq!(first, second);
+    assert!(first.bricks_broken > 0);
+}
+
+#[test]
+fn test_idle_script_loses_balls_until_the_time_runs_out() {
+    let config = SimulationConfig {
+        max_seconds: 60.0,
+        ..SimulationConfig::default()
+    };
+    let grid = config.brick_grid();
+    let layout = LevelLayout::full(grid.columns, grid.rows);
+    let policy = Policy::Scripted(Vec::new());
+    let stats = run_game(&config, &layout, Difficulty::Easy, &policy, 0, 1).unwrap();
+    assert!(!stats.cleared);
+    assert!(stats.balls_lost > 0);
+    assert!(stats.seconds >= config.max_seconds);
+}
+
+#[test]
+fn test_brick_hit_points_make_the_level_longer() {
+    let layout = LevelLayout::parse("..#..\n").unwrap();
+    let policy = Policy::Tracking(AutopilotSkill::Perfect);
+    let run = |brick_hit_points| {
+        let config = SimulationConfig {
+            brick_hit_points,
+            ..SimulationConfig::default()
+        };
+        run_game(&config, &layout, Difficulty::Normal, &policy, 1, 1).unwrap()
+    };
+    let (one_hit, four_hits) = (run(1), run(4));
+    assert!(one_hit.cleared && four_hits.cleared);
+    assert_eq!((one_hit.bricks_broken, four_hits.bricks_broken), (1, 1));
+    assert!(four_hits.seconds > one_hit.seconds);
+}